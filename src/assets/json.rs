@@ -0,0 +1,331 @@
+/**
+    Just enough JSON to read the atlas files exported by sprite tools.
+
+    Objects keep their keys in file order, since atlases list their frames
+    as an object and that order is the order of the frames.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+// deep enough for any atlas, shallow enough that nesting can't overflow the stack
+const MAX_DEPTH: usize = 128;
+
+impl Json {
+    pub fn parse(source: &str) -> Result<Json, String> {
+        let mut parser = Parser { source: source.as_bytes(), position: 0, depth: 0 };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.position < parser.source.len() {
+            return Err(parser.error("unexpected data after the end of the document"));
+        }
+        Ok(value)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(number) => Some(*number),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(string) => Some(string),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    source: &'a [u8],
+    position: usize,
+    // objects and arrays currently open
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> String {
+        let line = self.source[..self.position.min(self.source.len())].iter().filter(|&&c| c == b'\n').count() + 1;
+        format!("{} on line {}", message, line)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.source.get(self.position).is_some_and(|c| c.is_ascii_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.source.get(self.position).copied()
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), String> {
+        if self.peek() != Some(expected) {
+            return Err(self.error(&format!("expected '{}'", expected as char)));
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    fn literal(&mut self, literal: &str, value: Json) -> Result<Json, String> {
+        if !self.source[self.position..].starts_with(literal.as_bytes()) {
+            return Err(self.error("unexpected character"));
+        }
+        self.position += literal.len();
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        match self.peek() {
+            Some(b'{') => self.nested(Parser::object),
+            Some(b'[') => self.nested(Parser::array),
+            Some(b'"') => Ok(Json::String(self.string()?)),
+            Some(b't') => self.literal("true", Json::Bool(true)),
+            Some(b'f') => self.literal("false", Json::Bool(false)),
+            Some(b'n') => self.literal("null", Json::Null),
+            Some(c) if c == b'-' || c.is_ascii_digit() => self.number(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of the document")),
+        }
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Json, String>) -> Result<Json, String> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("too deeply nested"));
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.expect(b'{')?;
+        let mut entries = Vec::new();
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+            return Ok(Json::Object(entries));
+        }
+
+        loop {
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected a key"));
+            }
+            let key = self.string()?;
+            self.expect(b':')?;
+            entries.push((key, self.value()?));
+
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b'}') => {
+                    self.position += 1;
+                    return Ok(Json::Object(entries));
+                }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect(b'[')?;
+        let mut values = Vec::new();
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(Json::Array(values));
+        }
+
+        loop {
+            values.push(self.value()?);
+
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(Json::Array(values));
+                }
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.position;
+        while self.source.get(self.position).is_some_and(|&c| c.is_ascii_digit() || b"+-.eE".contains(&c)) {
+            self.position += 1;
+        }
+
+        std::str::from_utf8(&self.source[start..self.position])
+            .ok()
+            .and_then(|number| number.parse().ok())
+            .map(Json::Number)
+            .ok_or_else(|| self.error("invalid number"))
+    }
+
+    fn hex_escape(&mut self) -> Result<u32, String> {
+        let digits = self.source.get(self.position..self.position + 4)
+            // `from_str_radix` would also take a sign
+            .filter(|digits| digits.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        self.position += 4;
+        Ok(digits)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect(b'"')?;
+        let mut bytes = Vec::new();
+
+        loop {
+            let Some(&c) = self.source.get(self.position) else {
+                return Err(self.error("unterminated string"));
+            };
+            self.position += 1;
+
+            match c {
+                b'"' => break,
+                b'\\' => {
+                    let Some(&escape) = self.source.get(self.position) else {
+                        return Err(self.error("unterminated string"));
+                    };
+                    self.position += 1;
+
+                    let unescaped = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let mut code = self.hex_escape()?;
+                            // characters outside the basic plane come as a pair of surrogates
+                            if (0xD800..0xDC00).contains(&code) && self.source[self.position..].starts_with(b"\\u") {
+                                let start = self.position;
+                                self.position += 2;
+                                match self.hex_escape()? {
+                                    low @ 0xDC00..=0xDFFF => code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00),
+                                    // not the other half, so it is an escape of its own
+                                    _ => self.position = start,
+                                }
+                            }
+                            char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)
+                        }
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    bytes.extend_from_slice(unescaped.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                _ => bytes.push(c),
+            }
+        }
+
+        String::from_utf8(bytes).map_err(|_| self.error("invalid UTF-8 in string"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(source: &str) -> String {
+        match Json::parse(source) {
+            Ok(Json::String(string)) => string,
+            other => panic!("expected a string from {}, got {:?}", source, other),
+        }
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(string(r#""\"\\\/\b\f\n\r\t""#), "\"\\/\u{8}\u{c}\n\r\t");
+        assert_eq!(string(r#""caf\u00e9 \u00E9""#), "caf\u{e9} \u{e9}");
+        assert_eq!(string("\"raw é ✓\""), "raw é ✓");
+        assert!(Json::parse(r#""\q""#).is_err());
+        assert!(Json::parse(r#""\u12""#).is_err());
+        assert!(Json::parse(r#""\u+123""#).is_err());
+        assert!(Json::parse(r#""\u-123""#).is_err());
+        assert!(Json::parse(r#""open"#).is_err());
+    }
+
+    #[test]
+    fn surrogate_pairs() {
+        assert_eq!(string(r#""\ud83d\ude00""#), "\u{1f600}");
+        assert_eq!(string(r#""\uD834\uDD1E""#), "\u{1d11e}");
+        // unpaired halves can't be characters
+        assert_eq!(string(r#""\ud83d!""#), "\u{fffd}!");
+        assert_eq!(string(r#""\ude00""#), "\u{fffd}");
+        // a high surrogate followed by an escape that isn't a low one leaves that escape alone
+        assert_eq!(string(r#""\ud83d\u0041""#), "\u{fffd}A");
+    }
+
+    #[test]
+    fn numbers() {
+        let numbers = Json::parse("[0, -0, 12, -3.5, 1e2, 2.5E-1, -1e+3, 1000000000000]").unwrap();
+        let numbers = numbers.as_array().unwrap().iter().map(|n| n.as_f64().unwrap()).collect::<Vec<_>>();
+        assert_eq!(numbers, [0.0, -0.0, 12.0, -3.5, 100.0, 0.25, -1000.0, 1e12]);
+
+        for invalid in ["-", "1.2.3", "1e", "--1", "+1", ".5"] {
+            assert!(Json::parse(invalid).is_err(), "{} was accepted", invalid);
+        }
+    }
+
+    #[test]
+    fn objects_keep_key_order() {
+        let json = Json::parse(r#"{ "b": 1, "a": [true, false, null], "c": {} }"#).unwrap();
+        let Json::Object(entries) = &json else {
+            panic!("expected an object, got {:?}", json);
+        };
+
+        let keys = entries.iter().map(|(key, _)| key.as_str()).collect::<Vec<_>>();
+        assert_eq!(keys, ["b", "a", "c"]);
+        assert_eq!(json.get("a"), Some(&Json::Array(vec![Json::Bool(true), Json::Bool(false), Json::Null])));
+        assert_eq!(json.get("c"), Some(&Json::Object(Vec::new())));
+        assert_eq!(json.get("missing"), None);
+    }
+
+    #[test]
+    fn malformed_documents() {
+        for invalid in ["", "{", "[1,]", "{\"a\" 1}", "{\"a\": 1,}", "[1] 2", "tru", "{1: 2}"] {
+            assert!(Json::parse(invalid).is_err(), "{:?} was accepted", invalid);
+        }
+        assert_eq!(Json::parse("[\n1,\n]").unwrap_err(), "unexpected character on line 3");
+    }
+
+    #[test]
+    fn nesting_is_limited() {
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(Json::parse(&nested(MAX_DEPTH)).is_ok());
+        assert_eq!(Json::parse(&nested(MAX_DEPTH + 1)).unwrap_err(), "too deeply nested on line 1");
+        assert!(Json::parse(&"{\"a\":".repeat(100_000)).is_err());
+    }
+}
@@ -0,0 +1,388 @@
+use core::fmt;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+use mlua::prelude::*;
+use mlua::AppDataRefMut;
+
+use crate::lune::signal::Signal;
+use crate::lune::table_builder::TableBuilder;
+use crate::lune::userdata::*;
+use crate::render::{Framebuffer, TextureId, TextureInfo};
+
+pub mod json;
+
+pub mod sprite_sheet;
+pub use sprite_sheet::{SpriteRegion, SpriteSheet};
+
+enum TextureState {
+    Loading,
+    Loaded(TextureInfo),
+    Failed(String),
+}
+
+struct TextureEntry {
+    path: String,
+    state: TextureState,
+    refs: u32,
+    loaded: Signal,
+    // the result of a load running in the background
+    pending: Option<Receiver<Result<Framebuffer, String>>>,
+    // fires `Loaded` on the next update for textures that were already done when asked for again
+    notify: bool,
+}
+
+/**
+    Every texture loaded through `Bee2D.Assets`, by path.
+
+    Images are decoded when they are loaded, either right away or on a
+    background thread, and handed to the renderer before the next frame is
+    drawn. Loading the same path again shares the texture and adds a
+    reference to it, the texture is only freed once every reference has
+    been unloaded.
+*/
+#[derive(Default)]
+pub struct Assets {
+    next_id: u32,
+    textures: HashMap<u32, TextureEntry>,
+    by_path: HashMap<String, u32>,
+    // textures that fire `Loaded` once they are done, in the order they were asked for
+    waiting: Vec<u32>,
+
+    // renderer ids are reused, but only once the renderer has freed them
+    next_texture_id: TextureId,
+    free_texture_ids: Vec<TextureId>,
+    uploads: Vec<(TextureId, String, Framebuffer)>,
+    unloads: Vec<TextureId>,
+}
+
+// PNGs are decoded the same way everywhere, other formats need raylib
+fn decode(path: &str) -> Result<Framebuffer, String> {
+    let path = Path::new(path);
+    if path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("png")) {
+        return Framebuffer::load_png(path);
+    }
+
+    #[cfg(feature = "raylib")]
+    return crate::render::raylib_backend::decode_image(path);
+
+    #[cfg(not(feature = "raylib"))]
+    Err("only PNG images can be loaded when built without the raylib feature".into())
+}
+
+fn load_error(path: &str, err: &str) -> LuaError {
+    LuaError::RuntimeError(format!("failed to load texture '{}': {}", path, err))
+}
+
+impl Assets {
+    fn entry(&self, texture: Texture) -> LuaResult<&TextureEntry> {
+        self.textures.get(&texture.id)
+            .ok_or_else(|| LuaError::RuntimeError("Texture has been unloaded".into()))
+    }
+
+    // finds the entry for `path`, or creates one that has yet to start loading
+    fn entry_for(&mut self, path: &str) -> (u32, &mut TextureEntry) {
+        let id = match self.by_path.get(path) {
+            Some(&id) => id,
+            None => {
+                let id = self.next_id;
+                self.next_id += 1;
+                self.by_path.insert(path.to_string(), id);
+                self.textures.insert(id, TextureEntry {
+                    path: path.to_string(),
+                    state: TextureState::Loading,
+                    refs: 0,
+                    loaded: Signal::new(),
+                    pending: None,
+                    notify: false,
+                });
+                id
+            }
+        };
+
+        (id, self.textures.get_mut(&id).unwrap())
+    }
+
+    // hands a decoded image to the renderer, under an id it can be drawn with right away
+    fn finish(&mut self, id: u32, image: Framebuffer) {
+        let texture_id = self.free_texture_ids.pop().unwrap_or_else(|| {
+            self.next_texture_id += 1;
+            self.next_texture_id - 1
+        });
+
+        let entry = self.textures.get_mut(&id).unwrap();
+        entry.state = TextureState::Loaded(TextureInfo { id: texture_id, width: image.width(), height: image.height() });
+        // a load still running in the background was finished here instead, its caller waits on `Loaded`
+        entry.notify = entry.pending.is_some();
+        entry.pending = None;
+        self.uploads.push((texture_id, entry.path.clone(), image));
+    }
+
+    /**
+        Loads the texture at `path`, or adds a reference to it if it is already
+        loaded. Finishes a load that is still running in the background.
+    */
+    pub fn load(&mut self, path: &str) -> LuaResult<Texture> {
+        let (id, entry) = self.entry_for(path);
+        if !matches!(entry.state, TextureState::Loaded(_)) {
+            match decode(path) {
+                Ok(image) => self.finish(id, image),
+                Err(err) => {
+                    // a texture nothing refers to yet shouldn't stay around just because it failed
+                    if entry.refs == 0 {
+                        self.textures.remove(&id);
+                        self.by_path.remove(path);
+                    }
+                    return Err(load_error(path, &err));
+                }
+            }
+        }
+
+        self.textures.get_mut(&id).unwrap().refs += 1;
+        Ok(Texture { id })
+    }
+
+    /**
+        Like [`Assets::load`], but decodes the image on another thread. The
+        texture fires `Loaded` once it is ready, or failed, even when it
+        already was.
+    */
+    pub fn load_async(&mut self, path: &str) -> Texture {
+        let (id, entry) = self.entry_for(path);
+        entry.refs += 1;
+
+        match entry.state {
+            TextureState::Loading if entry.pending.is_some() => {}
+            TextureState::Loaded(_) => entry.notify = true,
+            TextureState::Loading | TextureState::Failed(_) => {
+                let (sender, receiver) = mpsc::channel();
+                let path = path.to_string();
+                std::thread::spawn(move || {
+                    // the texture may be unloaded before it finishes, nobody is listening then
+                    let _ = sender.send(decode(&path));
+                });
+
+                entry.state = TextureState::Loading;
+                entry.pending = Some(receiver);
+            }
+        }
+
+        if !self.waiting.contains(&id) {
+            self.waiting.push(id);
+        }
+        Texture { id }
+    }
+
+    pub fn get(&self, path: &str) -> Option<Texture> {
+        self.by_path.get(path).map(|&id| Texture { id })
+    }
+
+    // removes a reference to the texture, freeing it once there are none left
+    fn unload(&mut self, texture: Texture) -> LuaResult<()> {
+        let entry = self.textures.get_mut(&texture.id)
+            .ok_or_else(|| LuaError::RuntimeError("Texture has already been unloaded".into()))?;
+
+        entry.refs = entry.refs.saturating_sub(1);
+        if entry.refs == 0 {
+            let entry = self.textures.remove(&texture.id).unwrap();
+            self.by_path.remove(&entry.path);
+            if let TextureState::Loaded(info) = entry.state {
+                self.unloads.push(info.id);
+            }
+        }
+
+        Ok(())
+    }
+
+    /**
+        The loaded texture `texture` refers to, if it has finished loading.
+    */
+    pub fn resolve(&self, texture: &TextureRef) -> Option<TextureInfo> {
+        let id = match texture {
+            TextureRef::Path(path) => *self.by_path.get(path)?,
+            TextureRef::Texture(texture) => texture.id,
+        };
+
+        match self.textures.get(&id)?.state {
+            TextureState::Loaded(info) => Some(info),
+            _ => None,
+        }
+    }
+
+    // decoded images the renderer still has to create textures for, with the path they came from
+    pub fn take_uploads(&mut self) -> Vec<(TextureId, String, Framebuffer)> {
+        std::mem::take(&mut self.uploads)
+    }
+
+    // textures the renderer should free, their ids are handed out again afterwards
+    pub fn take_unloads(&mut self) -> Vec<TextureId> {
+        let unloads = std::mem::take(&mut self.unloads);
+        self.free_texture_ids.extend(&unloads);
+        unloads
+    }
+}
+
+fn assets_mut(lua: &Lua) -> LuaResult<AppDataRefMut<'_, Assets>> {
+    lua.app_data_mut::<Assets>()
+        .ok_or_else(|| LuaError::RuntimeError("Bee2D has not been initialized".into()))
+}
+
+/**
+    Collects textures that finished loading in the background and fires
+    their `Loaded` signals, with `true` or `false` and the error.
+*/
+pub fn update(lua: &Lua) -> LuaResult<()> {
+    let finished = {
+        let mut assets = assets_mut(lua)?;
+        let mut finished = Vec::new();
+        let mut still_waiting = Vec::new();
+
+        for id in std::mem::take(&mut assets.waiting) {
+            // unloaded before it was done
+            let Some(entry) = assets.textures.get_mut(&id) else {
+                continue;
+            };
+
+            let mut decoded = None;
+            if let Some(receiver) = &entry.pending {
+                match receiver.try_recv() {
+                    Ok(Ok(image)) => decoded = Some(image),
+                    Ok(Err(err)) => entry.state = TextureState::Failed(err),
+                    Err(TryRecvError::Empty) => {
+                        still_waiting.push(id);
+                        continue;
+                    }
+                    Err(TryRecvError::Disconnected) => entry.state = TextureState::Failed("the loading thread stopped".into()),
+                }
+                entry.pending = None;
+                entry.notify = false;
+            } else if !std::mem::take(&mut entry.notify) {
+                continue;
+            }
+
+            let error = match &entry.state {
+                TextureState::Failed(err) => Some(err.clone()),
+                _ => None,
+            };
+            finished.push((entry.loaded.clone(), error));
+            if let Some(image) = decoded {
+                assets.finish(id, image);
+            }
+        }
+
+        assets.waiting = still_waiting;
+        finished
+    };
+
+    for (signal, error) in finished {
+        signal.fire(lua, (error.is_none(), error))?;
+    }
+
+    Ok(())
+}
+
+pub fn create_api(lua: &Lua) -> LuaResult<LuaTable<'_>> {
+    TableBuilder::new(lua)?
+        .with_function("LoadTexture", |lua, path: String| assets_mut(lua)?.load(&path))?
+        .with_function("LoadTextureAsync", |lua, path: String| Ok(assets_mut(lua)?.load_async(&path)))?
+        .with_function("GetTexture", |lua, path: String| Ok(assets_mut(lua)?.get(&path)))?
+        .build_readonly()
+}
+
+/**
+    A handle to a texture loaded through `Bee2D.Assets`.
+
+    Its size is 0 until it has finished loading. Every handle returned from a
+    load should be unloaded once, after which the texture is freed if nothing
+    else loaded it.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Texture {
+    id: u32,
+}
+
+impl Texture {
+    fn with<R>(&self, lua: &Lua, f: impl FnOnce(&TextureEntry) -> R) -> LuaResult<R> {
+        let assets = assets_mut(lua)?;
+        Ok(f(assets.entry(*self)?))
+    }
+
+    fn size(&self, lua: &Lua) -> LuaResult<(u32, u32)> {
+        self.with(lua, |entry| match entry.state {
+            TextureState::Loaded(info) => (info.width, info.height),
+            _ => (0, 0),
+        })
+    }
+}
+
+impl LuaUserData for Texture {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_field_method_get("Path", |lua, this| this.with(lua, |entry| entry.path.clone()));
+        fields.add_field_method_get("Width", |lua, this| Ok(this.size(lua)?.0));
+        fields.add_field_method_get("Height", |lua, this| Ok(this.size(lua)?.1));
+        fields.add_field_method_get("RefCount", |lua, this| {
+            Ok(assets_mut(lua)?.textures.get(&this.id).map_or(0, |entry| entry.refs))
+        });
+        fields.add_field_method_get("IsLoaded", |lua, this| {
+            Ok(assets_mut(lua)?.textures.get(&this.id).is_some_and(|entry| matches!(entry.state, TextureState::Loaded(_))))
+        });
+        fields.add_field_method_get("Status", |lua, this| {
+            Ok(match assets_mut(lua)?.textures.get(&this.id).map(|entry| &entry.state) {
+                Some(TextureState::Loading) => "Loading",
+                Some(TextureState::Loaded(_)) => "Loaded",
+                Some(TextureState::Failed(_)) => "Failed",
+                None => "Unloaded",
+            })
+        });
+        fields.add_field_method_get("Error", |lua, this| {
+            this.with(lua, |entry| match &entry.state {
+                TextureState::Failed(err) => Some(err.clone()),
+                _ => None,
+            })
+        });
+        fields.add_field_method_get("Loaded", |lua, this| this.with(lua, |entry| entry.loaded.clone()));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("Unload", |lua, this, ()| assets_mut(lua)?.unload(*this));
+
+        methods.add_meta_method(LuaMetaMethod::Eq, userdata_impl_eq);
+        methods.add_meta_method(LuaMetaMethod::ToString, userdata_impl_to_string);
+    }
+}
+
+impl fmt::Display for Texture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Texture")
+    }
+}
+
+/**
+    What a texture can be given as from Luau, a handle from `Bee2D.Assets`
+    or the path of a texture loaded some other way.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextureRef {
+    Path(String),
+    Texture(Texture),
+}
+
+impl<'lua> FromLua<'lua> for TextureRef {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<TextureRef> {
+        match value {
+            LuaValue::String(path) => Ok(TextureRef::Path(path.to_str()?.to_string())),
+            LuaValue::UserData(ud) if ud.is::<Texture>() => Ok(TextureRef::Texture(*ud.borrow::<Texture>()?)),
+            other => Err(LuaError::RuntimeError(format!("Expected a Texture or a path, got {}", other.type_name()))),
+        }
+    }
+}
+
+impl<'lua> IntoLua<'lua> for TextureRef {
+    fn into_lua(self, lua: &'lua Lua) -> LuaResult<LuaValue<'lua>> {
+        match self {
+            TextureRef::Path(path) => path.into_lua(lua),
+            TextureRef::Texture(texture) => texture.into_lua(lua),
+        }
+    }
+}
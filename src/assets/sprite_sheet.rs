@@ -0,0 +1,416 @@
+use core::fmt;
+use std::f32::consts::FRAC_PI_2;
+use std::path::Path;
+use std::rc::Rc;
+
+use mlua::prelude::*;
+
+use crate::lune::exports::LuaExportsTable;
+use crate::lune::table_builder::TableBuilder;
+use crate::lune::userdata::*;
+use crate::math::{Matrix3, Vector2};
+use crate::render::{DrawCommand, Region, Rgba, TextureInfo};
+
+use super::json::Json;
+use super::{assets_mut, TextureRef};
+
+/**
+    A named part of a texture, one frame of a sprite sheet.
+
+    Atlases may trim the empty border off a frame and store it rotated a
+    quarter turn clockwise to pack it tighter, regions still draw as the
+    whole upright frame with the trimmed part in its original place.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpriteRegion {
+    texture: TextureRef,
+    name: Option<String>,
+    // the area of the texture the frame is stored in
+    source: Region,
+    rotated: bool,
+    // the frame before it was trimmed, and where the trimmed part sits in it
+    size: (f32, f32),
+    offset: (f32, f32),
+    duration: Option<f64>,
+}
+
+impl SpriteRegion {
+    fn from_rect(texture: TextureRef, name: Option<String>, source: Region) -> SpriteRegion {
+        SpriteRegion {
+            texture,
+            name,
+            source,
+            rotated: false,
+            size: (source.2, source.3),
+            offset: (0.0, 0.0),
+            duration: None,
+        }
+    }
+
+    pub fn texture(&self) -> &TextureRef {
+        &self.texture
+    }
+
+    pub fn size(&self) -> (f32, f32) {
+        self.size
+    }
+
+    // how long the frame lasts in an animation, when the atlas says so
+    pub fn duration(&self) -> Option<f64> {
+        self.duration
+    }
+
+    // maps the coordinates of the untrimmed, upright frame to `source`
+    fn local_transform(&self) -> Matrix3 {
+        let offset = Matrix3::translation(self.offset.0, self.offset.1);
+        if self.rotated {
+            // the source is as wide as the frame is tall, its left edge is the bottom of the frame
+            offset * Matrix3::translation(0.0, self.source.2) * Matrix3::rotation_radians(-FRAC_PI_2)
+        } else {
+            offset
+        }
+    }
+
+    /**
+        Draws the region of `texture`, with `transform` placing the top left
+        corner of the untrimmed frame.
+    */
+    pub fn draw_command(&self, texture: TextureInfo, transform: Matrix3, color: Rgba) -> DrawCommand {
+        DrawCommand::Texture {
+            texture: texture.id,
+            source: self.source,
+            transform: transform * self.local_transform(),
+            color,
+        }
+    }
+}
+
+impl LuaUserData for SpriteRegion {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_field_method_get("Name", |_, this| Ok(this.name.clone()));
+        fields.add_field_method_get("Texture", |_, this| Ok(this.texture.clone()));
+        fields.add_field_method_get("Position", |_, this| Ok(Vector2::new(this.source.0, this.source.1)));
+        fields.add_field_method_get("Size", |_, this| Ok(Vector2::new(this.size.0, this.size.1)));
+        fields.add_field_method_get("Duration", |_, this| Ok(this.duration));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_meta_method(LuaMetaMethod::Eq, userdata_impl_eq);
+        methods.add_meta_method(LuaMetaMethod::ToString, userdata_impl_to_string);
+    }
+}
+
+impl fmt::Display for SpriteRegion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "SpriteRegion({})", name),
+            None => write!(f, "SpriteRegion"),
+        }
+    }
+}
+
+/**
+    A run of frames an atlas marks as one animation, `Aseprite` calls these tags.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameTag {
+    pub name: String,
+    pub from: usize,
+    pub to: usize,
+    // Forward, Reverse, PingPong or PingPongReverse
+    pub direction: &'static str,
+}
+
+struct SheetData {
+    texture: TextureRef,
+    regions: Vec<SpriteRegion>,
+    tags: Vec<FrameTag>,
+}
+
+/**
+    A texture cut into regions, either a grid of equally sized frames or the
+    frames of an atlas exported by a sprite tool.
+*/
+#[derive(Clone)]
+pub struct SpriteSheet {
+    data: Rc<SheetData>,
+}
+
+impl SpriteSheet {
+    pub fn tag(&self, name: &str) -> LuaResult<&FrameTag> {
+        self.data.tags.iter()
+            .find(|tag| tag.name == name)
+            .ok_or_else(|| LuaError::RuntimeError(format!("'{}' is not a tag of this sprite sheet", name)))
+    }
+
+    fn find(&self, key: &LuaValue) -> LuaResult<&SpriteRegion> {
+        let region = match key {
+            LuaValue::Integer(index) => self.data.regions.get((*index as usize).wrapping_sub(1)),
+            LuaValue::Number(index) => self.data.regions.get((*index as usize).wrapping_sub(1)),
+            LuaValue::String(name) => {
+                let name = name.to_str()?;
+                self.data.regions.iter().find(|region| region.name.as_deref() == Some(name))
+            }
+            other => {
+                return Err(LuaError::RuntimeError(format!("Expected a region name or index, got {}", other.type_name())));
+            }
+        };
+
+        region.ok_or_else(|| LuaError::RuntimeError(format!("The sprite sheet has no region {}", key.to_string().unwrap_or_default())))
+    }
+}
+
+impl PartialEq for SpriteSheet {
+    fn eq(&self, other: &SpriteSheet) -> bool {
+        Rc::ptr_eq(&self.data, &other.data)
+    }
+}
+
+/**
+    Options for `SpriteSheet.fromGrid`, `{ Margin = 1, Spacing = 2, Count = 10 }`.
+    Margin is the border around the grid and spacing the gap between frames.
+*/
+struct GridOptions {
+    margin: f32,
+    spacing: f32,
+    count: Option<usize>,
+}
+
+impl<'lua> FromLua<'lua> for GridOptions {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<GridOptions> {
+        match value {
+            LuaValue::Nil => Ok(GridOptions { margin: 0.0, spacing: 0.0, count: None }),
+            LuaValue::Table(table) => Ok(GridOptions {
+                margin: table.get::<_, Option<f32>>("Margin")?.unwrap_or(0.0),
+                spacing: table.get::<_, Option<f32>>("Spacing")?.unwrap_or(0.0),
+                count: table.get("Count")?,
+            }),
+            other => Err(LuaError::RuntimeError(format!("Expected an options table, got {}", other.type_name()))),
+        }
+    }
+}
+
+// frames in rows from the top left, as many as fit unless there is a count
+fn from_grid(lua: &Lua, (texture, width, height, options): (TextureRef, f32, f32, GridOptions)) -> LuaResult<SpriteSheet> {
+    if !(width > 0.0 && height > 0.0) {
+        return Err(LuaError::RuntimeError(format!("Frames must be larger than 0, got {}x{}", width, height)));
+    }
+    for (name, value) in [("Margin", options.margin), ("Spacing", options.spacing)] {
+        if !value.is_finite() || value < 0.0 {
+            return Err(LuaError::RuntimeError(format!("{} must be a finite number 0 or greater, got {}", name, value)));
+        }
+    }
+
+    let info = assets_mut(lua)?.resolve(&texture)
+        .ok_or_else(|| LuaError::RuntimeError("The texture has to be loaded before it can be sliced".into()))?;
+
+    let fit = |size: u32, frame: f32| {
+        ((size as f32 - 2.0 * options.margin + options.spacing) / (frame + options.spacing)).floor().max(0.0) as usize
+    };
+    let (columns, rows) = (fit(info.width, width), fit(info.height, height));
+
+    let fits = columns.checked_mul(rows)
+        .ok_or_else(|| LuaError::RuntimeError(format!("The texture fits too many frames, {}x{}", columns, rows)))?;
+
+    let count = options.count.unwrap_or(fits);
+    if count > fits {
+        return Err(LuaError::RuntimeError(format!("The texture only fits {} frames, {} were asked for", fits, count)));
+    }
+
+    let regions = (0..count)
+        .map(|index| {
+            let (column, row) = ((index % columns) as f32, (index / columns) as f32);
+            let x = options.margin + column * (width + options.spacing);
+            let y = options.margin + row * (height + options.spacing);
+            SpriteRegion::from_rect(texture.clone(), None, (x, y, width, height))
+        })
+        .collect();
+
+    Ok(SpriteSheet { data: Rc::new(SheetData { texture, regions, tags: Vec::new() }) })
+}
+
+fn number(json: &Json, key: &str) -> Result<f32, String> {
+    json.get(key)
+        .and_then(Json::as_f64)
+        .map(|number| number as f32)
+        .ok_or_else(|| format!("missing number '{}'", key))
+}
+
+fn parse_region(texture: &TextureRef, name: Option<String>, frame: &Json) -> Result<SpriteRegion, String> {
+    let rect = frame.get("frame").ok_or("a frame is missing its 'frame' rectangle")?;
+    let (x, y, w, h) = (number(rect, "x")?, number(rect, "y")?, number(rect, "w")?, number(rect, "h")?);
+    let rotated = frame.get("rotated").and_then(Json::as_bool).unwrap_or(false);
+
+    // the sizes are of the upright frame, a rotated one is stored the other way around
+    let mut region = SpriteRegion::from_rect(texture.clone(), name, if rotated { (x, y, h, w) } else { (x, y, w, h) });
+    region.rotated = rotated;
+    region.size = (w, h);
+
+    if frame.get("trimmed").and_then(Json::as_bool).unwrap_or(false) {
+        let trimmed = frame.get("spriteSourceSize").ok_or("a trimmed frame is missing 'spriteSourceSize'")?;
+        let source = frame.get("sourceSize").ok_or("a trimmed frame is missing 'sourceSize'")?;
+        region.offset = (number(trimmed, "x")?, number(trimmed, "y")?);
+        region.size = (number(source, "w")?, number(source, "h")?);
+    }
+
+    // milliseconds in the file
+    region.duration = frame.get("duration").and_then(Json::as_f64).map(|ms| ms / 1000.0);
+    Ok(region)
+}
+
+fn parse_tag(tag: &Json, frame_count: usize) -> Result<FrameTag, String> {
+    let name = tag.get("name").and_then(Json::as_str).ok_or("a frame tag is missing its name")?;
+    let from = number(tag, "from")? as usize;
+    let to = number(tag, "to")? as usize;
+    if from > to || to >= frame_count {
+        return Err(format!("frame tag '{}' goes past the last frame", name));
+    }
+
+    let direction = match tag.get("direction").and_then(Json::as_str).unwrap_or("forward") {
+        "forward" => "Forward",
+        "reverse" => "Reverse",
+        "pingpong" => "PingPong",
+        "pingpong_reverse" => "PingPongReverse",
+        other => return Err(format!("frame tag '{}' has an unknown direction '{}'", name, other)),
+    };
+
+    Ok(FrameTag { name: name.to_string(), from, to, direction })
+}
+
+// reads an atlas in the JSON hash or array format of TexturePacker and Aseprite
+fn parse_atlas(lua: &Lua, path: &Path, texture: Option<TextureRef>) -> Result<SheetData, String> {
+    let source = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let json = Json::parse(&source)?;
+
+    let texture = match texture {
+        Some(texture) => texture,
+        None => {
+            // the image is relative to the atlas
+            let image = json.get("meta")
+                .and_then(|meta| meta.get("image"))
+                .and_then(Json::as_str)
+                .ok_or("the atlas names no image, pass the texture instead")?;
+            let image = path.parent().unwrap_or(Path::new("")).join(image);
+
+            let texture = assets_mut(lua)
+                .and_then(|mut assets| assets.load(&image.to_string_lossy()))
+                .map_err(|err| err.to_string())?;
+            TextureRef::Texture(texture)
+        }
+    };
+
+    let regions = match json.get("frames") {
+        Some(Json::Array(frames)) => frames.iter()
+            .map(|frame| {
+                let name = frame.get("filename").and_then(Json::as_str).map(str::to_string);
+                parse_region(&texture, name, frame)
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        Some(Json::Object(frames)) => frames.iter()
+            .map(|(name, frame)| parse_region(&texture, Some(name.clone()), frame))
+            .collect::<Result<Vec<_>, _>>()?,
+        _ => return Err("the atlas has no frames".into()),
+    };
+
+    let tags = json.get("meta")
+        .and_then(|meta| meta.get("frameTags"))
+        .and_then(Json::as_array)
+        .unwrap_or_default()
+        .iter()
+        .map(|tag| parse_tag(tag, regions.len()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(SheetData { texture, regions, tags })
+}
+
+fn load(lua: &Lua, (path, texture): (String, Option<TextureRef>)) -> LuaResult<SpriteSheet> {
+    let data = parse_atlas(lua, Path::new(&path), texture)
+        .map_err(|err| LuaError::RuntimeError(format!("failed to load sprite sheet '{}': {}", path, err)))?;
+    Ok(SpriteSheet { data: Rc::new(data) })
+}
+
+impl LuaExportsTable<'_> for SpriteSheet {
+    const EXPORT_NAME: &'static str = "SpriteSheet";
+
+    fn create_exports_table(lua: &Lua) -> LuaResult<LuaTable<'_>> {
+        TableBuilder::new(lua)?
+            .with_function("fromGrid", from_grid)?
+            .with_function("load", load)?
+            .build_readonly()
+    }
+}
+
+impl LuaUserData for SpriteSheet {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_field_method_get("Texture", |_, this| Ok(this.data.texture.clone()));
+        fields.add_field_method_get("Count", |_, this| Ok(this.data.regions.len()));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("GetRegion", |_, this, key: LuaValue| Ok(this.find(&key)?.clone()));
+        methods.add_method("GetRegions", |_, this, ()| Ok(this.data.regions.clone()));
+        methods.add_method("GetTag", |lua, this, name: String| {
+            let tag = this.tag(&name)?;
+            TableBuilder::new(lua)?
+                .with_value("Name", tag.name.as_str())?
+                .with_value("Direction", tag.direction)?
+                .with_value("Frames", this.data.regions[tag.from..=tag.to].to_vec())?
+                .build()
+        });
+        methods.add_method("GetTagNames", |_, this, ()| {
+            Ok(this.data.tags.iter().map(|tag| tag.name.clone()).collect::<Vec<_>>())
+        });
+
+        methods.add_meta_method(LuaMetaMethod::Eq, userdata_impl_eq);
+        methods.add_meta_method(LuaMetaMethod::ToString, |_, _, ()| Ok("SpriteSheet"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> SheetData {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name);
+        parse_atlas(&Lua::new(), &path, Some(TextureRef::Path("sheet.png".into()))).unwrap()
+    }
+
+    #[test]
+    fn texture_packer_hash() {
+        let sheet = fixture("texturepacker.json");
+        let names = sheet.regions.iter().map(|region| region.name.as_deref().unwrap()).collect::<Vec<_>>();
+        assert_eq!(names, ["hero_idle.png", "hero_jump.png", "coin.png"]);
+
+        let idle = &sheet.regions[0];
+        assert_eq!(idle.source, (2.0, 2.0, 30.0, 40.0));
+        assert_eq!(idle.size, (32.0, 48.0));
+        assert_eq!(idle.offset, (1.0, 4.0));
+        assert!(!idle.rotated);
+
+        // stored a quarter turn clockwise, so the texture holds it 40 wide and 32 high
+        let jump = &sheet.regions[1];
+        assert!(jump.rotated);
+        assert_eq!(jump.source, (34.0, 2.0, 40.0, 32.0));
+        assert_eq!(jump.size, (32.0, 40.0));
+        assert_eq!(jump.offset, (0.0, 0.0));
+
+        assert_eq!(sheet.regions[2].duration, None);
+        assert!(sheet.tags.is_empty());
+    }
+
+    #[test]
+    fn aseprite_array() {
+        let sheet = fixture("aseprite.json");
+        assert_eq!(sheet.regions.len(), 3);
+        assert_eq!(sheet.regions[1].name.as_deref(), Some("slime 1.aseprite"));
+        assert_eq!(sheet.regions[1].source, (24.0, 0.0, 24.0, 16.0));
+
+        let durations = sheet.regions.iter().map(|region| region.duration).collect::<Vec<_>>();
+        assert_eq!(durations, [Some(0.1), Some(0.15), Some(0.1)]);
+
+        assert_eq!(sheet.tags, [
+            FrameTag { name: "idle".into(), from: 0, to: 0, direction: "Forward" },
+            FrameTag { name: "bounce".into(), from: 1, to: 2, direction: "PingPong" },
+        ]);
+    }
+}
@@ -1,16 +1,624 @@
-use mlua::prelude::*;
-use std::thread::sleep;
-use raylib::prelude::*;
-use std::rc::Rc;
-use std::cell::RefCell;
-use std::time::Duration;
 use std::time::Instant;
-use std::collections::HashMap;
 
+use mlua::prelude::*;
+use mlua::{AppDataRef, AppDataRefMut};
+
+use crate::assets::{self, Assets, SpriteRegion, TextureRef};
+use crate::callbacks::{self, Callbacks, Stage};
+use crate::engine::component::{self, script::{self, ScriptComponents}};
+use crate::engine::{camera, transform, Cameras, Scene, SpriteData, Sprites};
+use crate::input::{self, InputSource, InputState, NoInput};
+use crate::lune::table_builder::TableBuilder;
+use crate::math::Matrix3;
+use crate::physics::{self, Physics};
+use crate::render::{self, DrawCommand, DrawQueue, Framebuffer, Renderer, Rgba, SoftwareRenderer, ViewCanvas};
+use crate::scheduler::{self, Scheduler};
+use crate::tween::{self, Tweens};
+use crate::{engine, math};
+
+// fixed frame time used by `Bee2D::run` when there is no window to pace frames
+// and no target frame rate to take it from
+const HEADLESS_DELTA_TIME: f64 = 1.0 / 60.0;
+
+const DEFAULT_TARGET_FPS: u32 = 60;
+// how long `Bee2D.fps` averages frames over
+const FPS_SAMPLE_TIME: f64 = 1.0;
+
+const DEFAULT_FIXED_RATE: f64 = 60.0;
+// fixed updates run in a single frame at most, so a slow frame can't snowball into slower ones
+const DEFAULT_MAX_FIXED_STEPS: u32 = 5;
+// the largest texture most GPUs can hold, and so the largest window worth asking for
+const MAX_WINDOW_SIZE: i32 = 16384;
+
+pub struct Bee2DConfig {
+    pub width: i32,
+    pub height: i32,
+    pub title: String,
+    // renders into an in-memory framebuffer instead of a window,
+    // always the case when built without the `raylib` feature
+    pub headless: bool,
+}
+
+impl Default for Bee2DConfig {
+    fn default() -> Self {
+        Bee2DConfig {
+            width: 800,
+            height: 800,
+            title: String::from("Bee2D"),
+            headless: false,
+        }
+    }
+}
+
+// state shared between the engine and the functions it exposes to Luau,
+// stored in the app data of the Lua VM
+struct EngineState {
+    width: i32,
+    height: i32,
+    title: String,
+    target_fps: u32,
+    vsync: bool,
+    quit_requested: bool,
+
+    // `delta_time` is scaled by `time_scale`, and 0 while paused
+    delta_time: f64,
+    unscaled_delta_time: f64,
+    time_scale: f64,
+    paused: bool,
+    time: f64,
+    frame_count: u64,
+
+    fps: f64,
+    fps_frames: u32,
+    fps_elapsed: f64,
+
+    fixed_delta_time: f64,
+    max_fixed_steps: u32,
+    // time not yet consumed by fixed updates, always less than one fixed step after a frame
+    fixed_accumulator: f64,
+}
+
+impl EngineState {
+    fn new(config: &Bee2DConfig) -> EngineState {
+        EngineState {
+            width: config.width,
+            height: config.height,
+            title: config.title.clone(),
+            target_fps: DEFAULT_TARGET_FPS,
+            vsync: false,
+            quit_requested: false,
+            delta_time: 0.0,
+            unscaled_delta_time: 0.0,
+            time_scale: 1.0,
+            paused: false,
+            time: 0.0,
+            frame_count: 0,
+            fps: 0.0,
+            fps_frames: 0,
+            fps_elapsed: 0.0,
+            fixed_delta_time: 1.0 / DEFAULT_FIXED_RATE,
+            max_fixed_steps: DEFAULT_MAX_FIXED_STEPS,
+            fixed_accumulator: 0.0,
+        }
+    }
+
+    // starts a frame that took `dt` real seconds and returns how much game time it advances by
+    fn begin_frame(&mut self, dt: f64) -> f64 {
+        self.frame_count += 1;
+        self.unscaled_delta_time = dt;
+        self.delta_time = if self.paused { 0.0 } else { dt * self.time_scale };
+        self.time += self.delta_time;
+
+        self.fps_frames += 1;
+        self.fps_elapsed += dt;
+        if self.fps_elapsed >= FPS_SAMPLE_TIME {
+            self.fps = self.fps_frames as f64 / self.fps_elapsed;
+            self.fps_frames = 0;
+            self.fps_elapsed = 0.0;
+        }
+
+        self.delta_time
+    }
+
+    // adds `dt` to the accumulator and takes out the fixed updates that are due this frame
+    fn take_fixed_steps(&mut self, dt: f64) -> u32 {
+        self.fixed_accumulator += dt;
+
+        // a little slack, so that frames exactly one fixed step long don't drift into skipping one
+        let due = ((self.fixed_accumulator + 1e-9) / self.fixed_delta_time).floor() as u32;
+        self.fixed_accumulator = (self.fixed_accumulator - due as f64 * self.fixed_delta_time).max(0.0);
+
+        // time past the cap is dropped rather than caught up on later
+        due.min(self.max_fixed_steps)
+    }
+
+    // how far the current frame is between the last fixed update and the next, from 0 to 1
+    fn fixed_alpha(&self) -> f64 {
+        (self.fixed_accumulator / self.fixed_delta_time).min(1.0)
+    }
+}
+
+// app data is only missing when a function outlives the engine that created it
+fn app_data<T: 'static>(lua: &Lua) -> LuaResult<AppDataRef<'_, T>> {
+    lua.app_data_ref::<T>()
+        .ok_or_else(|| LuaError::RuntimeError("Bee2D has not been initialized".into()))
+}
+
+fn app_data_mut<T: 'static>(lua: &Lua) -> LuaResult<AppDataRefMut<'_, T>> {
+    lua.app_data_mut::<T>()
+        .ok_or_else(|| LuaError::RuntimeError("Bee2D has not been initialized".into()))
+}
+
+fn engine_state(lua: &Lua) -> LuaResult<AppDataRef<'_, EngineState>> {
+    app_data(lua)
+}
 
-struct bee2d {
+fn engine_state_mut(lua: &Lua) -> LuaResult<AppDataRefMut<'_, EngineState>> {
+    app_data_mut(lua)
+}
+
+// region, x, y, rotation, scale, color
+type DrawRegionArgs<'lua> = (LuaUserDataRef<'lua, SpriteRegion>, f32, f32, f32, f32, Rgba);
+
+// texture, x, y, rotation, scale, color
+type CreateSpriteArgs = (TextureRef, Option<f32>, Option<f32>, Option<f32>, Option<f32>, Option<Rgba>);
+
+fn window_size(name: &str, size: f64) -> LuaResult<i32> {
+    if size.fract() != 0.0 || !(1.0..=MAX_WINDOW_SIZE as f64).contains(&size) {
+        return Err(LuaError::RuntimeError(format!(
+            "The window {} must be a whole number from 1 to {}, got {}", name, MAX_WINDOW_SIZE, size
+        )));
+    }
+    Ok(size as i32)
+}
+
+fn create_api(lua: &Lua) -> LuaResult<LuaTable<'_>> {
+    // window settings and frame timings are read from the engine state on access,
+    // so scripts can't overwrite them, `timeScale` being the only one they can set
+    let properties = TableBuilder::new(lua)?
+        .with_function("__index", |lua, (_, key): (LuaTable, LuaString)| {
+            let state = engine_state(lua)?;
+            match key.to_str()? {
+                "width" => state.width.into_lua(lua),
+                "height" => state.height.into_lua(lua),
+                "title" => state.title.as_str().into_lua(lua),
+                "deltaTime" => state.delta_time.into_lua(lua),
+                "unscaledDeltaTime" => state.unscaled_delta_time.into_lua(lua),
+                "fixedDeltaTime" => state.fixed_delta_time.into_lua(lua),
+                "fixedAlpha" => state.fixed_alpha().into_lua(lua),
+                "timeScale" => state.time_scale.into_lua(lua),
+                "paused" => state.paused.into_lua(lua),
+                "time" => state.time.into_lua(lua),
+                "frameCount" => (state.frame_count as f64).into_lua(lua),
+                "fps" => state.fps.into_lua(lua),
+                _ => Ok(LuaNil),
+            }
+        })?
+        .with_function("__newindex", |lua, (_, key, value): (LuaTable, LuaString, LuaValue)| {
+            match key.to_str()? {
+                "timeScale" => {
+                    let scale = f64::from_lua(value, lua)?;
+                    if !scale.is_finite() || scale < 0.0 {
+                        return Err(LuaError::RuntimeError(format!("timeScale must be a finite number 0 or greater, got {}", scale)));
+                    }
+                    engine_state_mut(lua)?.time_scale = scale;
+                    Ok(())
+                }
+                key => Err(LuaError::RuntimeError(format!("Bee2D.{} can't be assigned to", key))),
+            }
+        })?
+        .build_readonly()?;
+
+    let scene_root = app_data::<Scene>(lua)?.root();
+
+    TableBuilder::new(lua)?
+        .with_value("GLOBAL_STORAGE", lua.create_table()?)?
+        .with_value("Scene", scene_root)?
+        .with_value("Input", input::create_api(lua)?)?
+        .with_value("Assets", assets::create_api(lua)?)?
+        .with_function("bindToStart", callbacks::bind_to_start)?
+        .with_function("bindToUpdate", callbacks::bind_to_update)?
+        .with_function("bindToFixedUpdate", callbacks::bind_to_fixed_update)?
+        .with_function("bindToDraw", callbacks::bind_to_draw)?
+        .with_function("registerComponent", script::register)?
+        .with_values(render::draw::create_functions(lua)?)?
+        .with_function("loadTexture", |lua, path: String| {
+            app_data_mut::<Assets>(lua)?.load(&path)
+        })?
+        .with_function("drawTexture", |lua, (texture, x, y, rotation, scale, color): (TextureRef, f32, f32, f32, f32, Rgba)| {
+            let assets = app_data::<Assets>(lua)?;
+            let mut queue = app_data_mut::<DrawQueue>(lua)?;
+
+            // textures that were never loaded are skipped, like before they finish loading
+            if let Some(texture) = assets.resolve(&texture) {
+                queue.push(DrawCommand::texture(texture, x, y, rotation, scale, color));
+            }
+            Ok(())
+        })?
+        .with_function("drawTextureRegion", |lua, (region, x, y, rotation, scale, color): DrawRegionArgs| {
+            let assets = app_data::<Assets>(lua)?;
+            let mut queue = app_data_mut::<DrawQueue>(lua)?;
+
+            if let Some(texture) = assets.resolve(region.texture()) {
+                queue.push(region.draw_command(texture, render::placement(x, y, rotation, scale), color));
+            }
+            Ok(())
+        })?
+        .with_function("createSprite", |lua, (texture, x, y, rotation, scale, color): CreateSpriteArgs| {
+            let mut sprites = app_data_mut::<Sprites>(lua)?;
+
+            Ok(sprites.insert(SpriteData {
+                texture,
+                x: x.unwrap_or(0.0),
+                y: y.unwrap_or(0.0),
+                rotation: rotation.unwrap_or(0.0),
+                scale: scale.unwrap_or(1.0),
+                color: color.unwrap_or(Rgba::WHITE),
+                visible: true,
+            }))
+        })?
+        .with_function("setHeight", |lua, height: f64| {
+            engine_state_mut(lua)?.height = window_size("height", height)?;
+            Ok(())
+        })?
+        .with_function("setWidth", |lua, width: f64| {
+            engine_state_mut(lua)?.width = window_size("width", width)?;
+            Ok(())
+        })?
+        .with_function("setTitle", |lua, title: String| {
+            engine_state_mut(lua)?.title = title;
+            Ok(())
+        })?
+        .with_function("setTargetFPS", |lua, fps: u32| {
+            engine_state_mut(lua)?.target_fps = fps;
+            Ok(())
+        })?
+        .with_function("setVSync", |lua, enabled: bool| {
+            engine_state_mut(lua)?.vsync = enabled;
+            Ok(())
+        })?
+        .with_function("pause", |lua, ()| {
+            engine_state_mut(lua)?.paused = true;
+            Ok(())
+        })?
+        .with_function("resume", |lua, ()| {
+            engine_state_mut(lua)?.paused = false;
+            Ok(())
+        })?
+        .with_function("setFixedRate", |lua, rate: f64| {
+            if !rate.is_finite() || rate <= 0.0 {
+                return Err(LuaError::RuntimeError(format!("Fixed rate must be a finite number greater than 0, got {}", rate)));
+            }
+            engine_state_mut(lua)?.fixed_delta_time = 1.0 / rate;
+            Ok(())
+        })?
+        .with_function("setMaxFixedSteps", |lua, steps: u32| {
+            engine_state_mut(lua)?.max_fixed_steps = steps.max(1);
+            Ok(())
+        })?
+        .with_function("quit", |lua, ()| {
+            engine_state_mut(lua)?.quit_requested = true;
+            Ok(())
+        })?
+        .with_metatable(properties)?
+        .build_readonly()
+}
+
+/**
+    The Bee2D engine.
+
+    Owns the Luau VM, the renderer and the per-frame draw queue, and hands
+    every texture loaded through `Bee2D.Assets` to the renderer. Scripts are loaded with [`Bee2D::load_script`] and
+    driven either frame by frame through [`Bee2D::step`] or until the window
+    closes through [`Bee2D::run`].
+*/
+pub struct Bee2D {
     lua: Lua,
-    raylib: RaylibHandle,
-    thread: RaylibThread,
+    renderer: Box<dyn Renderer>,
+    input_source: Box<dyn InputSource>,
+    headless: bool,
+    window_size: (i32, i32),
+    window_title: String,
+    // what the renderer was last told, 0 and off until the first frame applies the settings
+    target_fps: u32,
+    vsync: bool,
+}
+
+impl Bee2D {
+    pub fn new(config: Bee2DConfig) -> LuaResult<Bee2D> {
+        let headless = config.headless || cfg!(not(feature = "raylib"));
+        let renderer = create_renderer(&config, headless).map_err(LuaError::RuntimeError)?;
+        let input_source = create_input_source(headless);
+
+        let lua = Lua::new();
+        lua.set_app_data(Scheduler::new());
+        lua.set_app_data(EngineState::new(&config));
+        lua.set_app_data(Callbacks::default());
+        lua.set_app_data(Assets::default());
+        lua.set_app_data(Sprites::default());
+        lua.set_app_data(Scene::new());
+        lua.set_app_data(Cameras::new((config.width as f32, config.height as f32)));
+        lua.set_app_data(ScriptComponents::default());
+        lua.set_app_data(DrawQueue::default());
+        lua.set_app_data(InputState::default());
+        lua.set_app_data(Tweens::new(&lua)?);
+        lua.set_app_data(Physics::default());
+
+        {
+            let globals = lua.globals();
+
+            for pair in math::module(&lua)?.pairs::<LuaString, LuaTable>() {
+                let (key, value) = pair?;
+                globals.set(key, value)?;
+            }
+
+            for pair in engine::module(&lua)?.pairs::<LuaString, LuaTable>() {
+                let (key, value) = pair?;
+                globals.set(key, value)?;
+            }
+
+            let task = scheduler::module(&lua)?;
+            globals.set("wait", task.get::<_, LuaFunction>("wait")?)?;
+            globals.set("task", task)?;
+            globals.set("TweenService", tween::module(&lua)?)?;
+            globals.set("Physics", physics::create_api(&lua)?)?;
+
+            globals.set("Bee2D", create_api(&lua)?)?;
+        }
+
+        Ok(Bee2D {
+            lua,
+            renderer,
+            input_source,
+            headless,
+            window_size: (config.width, config.height),
+            window_title: config.title,
+            target_fps: 0,
+            vsync: false,
+        })
+    }
+
+    /**
+        Runs `source` as a new task. `name` is used as the chunk name in errors.
+    */
+    pub fn load_script(&self, name: &str, source: &str) -> LuaResult<()> {
+        let chunk = self.lua.load(source).set_name(name).into_function()?;
+        scheduler::spawn(&self.lua, LuaValue::Function(chunk), LuaMultiValue::new())?;
+        Ok(())
+    }
+
+    /**
+        Advances the engine by a single frame of `dt` seconds and draws it.
+
+        Everything but input goes by game time, which is `dt` scaled by
+        `Bee2D.timeScale` and stands still while the engine is paused.
+    */
+    pub fn step(&mut self, dt: f64) -> LuaResult<()> {
+        let dt = engine_state_mut(&self.lua)?.begin_frame(dt);
+
+        callbacks::start(&self.lua)?;
+
+        assets::update(&self.lua)?;
+        self.apply_window_settings()?;
+
+        input::begin_frame(&self.lua, &mut *self.input_source)?;
+
+        let args = dt.into_lua_multi(&self.lua)?;
+        callbacks::run_stage(&self.lua, Stage::PreUpdate, args.clone())?;
+        self.fixed_update(dt)?;
+        callbacks::run_stage(&self.lua, Stage::Update, args.clone())?;
+        component::update(&self.lua, dt)?;
+
+        // tweens move with the same clock as the threads resumed after them
+        tween::update(&self.lua, dt)?;
+        // resume every thread whose wait elapsed during this frame
+        scheduler::step(&self.lua, dt)?;
+        callbacks::run_stage(&self.lua, Stage::PostUpdate, args)?;
+
+        // every global matrix is up to date by the time anything is drawn
+        if let Some(mut scene) = self.lua.app_data_mut::<Scene>() {
+            transform::update_transforms(&mut scene);
+        }
+        camera::update(&self.lua, dt)?;
+
+        // the scene is drawn between the PreDraw and Draw callbacks, UI goes on top in screen space
+        let alpha = engine_state(&self.lua)?.fixed_alpha().into_lua_multi(&self.lua)?;
+        callbacks::run_stage(&self.lua, Stage::PreDraw, alpha.clone())?;
+        self.draw_scene()?;
+        callbacks::run_stage(&self.lua, Stage::Draw, alpha.clone())?;
+
+        self.set_screen_space(true);
+        callbacks::run_stage(&self.lua, Stage::UI, alpha)?;
+        self.set_screen_space(false);
+
+        self.render()
+    }
+
+    /**
+        Steps the engine until the window is closed or a script calls `Bee2D.quit`.
+
+        Frames are timed with the real time between them, except when headless,
+        where every frame advances by one frame at the target frame rate, or a
+        fixed 1/60th of a second without one.
+    */
+    pub fn run(&mut self) -> LuaResult<()> {
+        let mut last_time = Instant::now();
+
+        while !self.renderer.should_close() && !engine_state(&self.lua)?.quit_requested {
+            let current_time = Instant::now();
+            let delta_time = current_time.duration_since(last_time);
+            last_time = current_time;
+
+            if self.headless {
+                let target_fps = engine_state(&self.lua)?.target_fps;
+                self.step(if target_fps > 0 { 1.0 / target_fps as f64 } else { HEADLESS_DELTA_TIME })?;
+            } else {
+                self.step(delta_time.as_secs_f64())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /**
+        The last rendered frame, only available when running headless.
+    */
+    pub fn framebuffer(&self) -> Option<&Framebuffer> {
+        self.renderer.framebuffer()
+    }
+
+    // creates the textures loaded since the last frame and frees the unloaded ones
+    fn sync_textures(&mut self) -> LuaResult<()> {
+        let mut assets = app_data_mut::<Assets>(&self.lua)?;
+
+        // uploads go first, a texture unloaded right after loading still frees its id
+        for (id, path, image) in assets.take_uploads() {
+            self.renderer.create_texture(id, image)
+                .map_err(|err| LuaError::RuntimeError(format!("failed to load texture '{}': {}", path, err)))?;
+        }
+
+        for id in assets.take_unloads() {
+            self.renderer.unload_texture(id);
+        }
+
+        Ok(())
+    }
+
+    fn apply_window_settings(&mut self) -> LuaResult<()> {
+        let state = engine_state(&self.lua)?;
+
+        if self.window_size != (state.width, state.height) {
+            self.renderer.set_size(state.width, state.height).map_err(LuaError::RuntimeError)?;
+            self.window_size = (state.width, state.height);
+
+            if let Some(mut cameras) = self.lua.app_data_mut::<Cameras>() {
+                cameras.set_screen_size((state.width as f32, state.height as f32));
+            }
+        }
+
+        if self.window_title != state.title {
+            self.window_title = state.title.clone();
+            self.renderer.set_title(&state.title);
+        }
+
+        if self.target_fps != state.target_fps {
+            self.target_fps = state.target_fps;
+            self.renderer.set_target_fps(state.target_fps);
+        }
+
+        if self.vsync != state.vsync {
+            self.vsync = state.vsync;
+            self.renderer.set_vsync(state.vsync);
+        }
+
+        Ok(())
+    }
+
+    // runs as many fixed updates as are due for a frame of `dt` seconds
+    fn fixed_update(&self, dt: f64) -> LuaResult<()> {
+        let (steps, fixed_dt) = {
+            let mut state = engine_state_mut(&self.lua)?;
+            (state.take_fixed_steps(dt), state.fixed_delta_time)
+        };
+
+        // scripts push bodies around first, then physics moves them
+        for _ in 0..steps {
+            callbacks::run_stage(&self.lua, Stage::FixedUpdate, fixed_dt.into_lua_multi(&self.lua)?)?;
+            physics::step(&self.lua, fixed_dt)?;
+        }
+
+        Ok(())
+    }
+
+    fn set_screen_space(&self, screen_space: bool) {
+        if let Some(mut queue) = self.lua.app_data_mut::<DrawQueue>() {
+            queue.set_screen_space(screen_space);
+        }
+    }
+
+    fn draw_scene(&self) -> LuaResult<()> {
+        let texture = |texture: &TextureRef| {
+            self.lua.app_data_ref::<Assets>()?.resolve(texture)
+        };
+        let mut queue_command = |command| {
+            if let Some(mut queue) = self.lua.app_data_mut::<DrawQueue>() {
+                queue.push(command);
+            }
+        };
+
+        component::draw(&self.lua, &mut queue_command, &texture)
+    }
+
+    fn render(&mut self) -> LuaResult<()> {
+        self.sync_textures()?;
+
+        {
+            let assets = app_data::<Assets>(&self.lua)?;
+            let sprites = app_data::<Sprites>(&self.lua)?;
+            let queue = app_data::<DrawQueue>(&self.lua)?;
+
+            // the whole frame is drawn once per camera, or straight to the window without any
+            let views = match self.lua.app_data_ref::<Cameras>().map(|cameras| cameras.views()) {
+                Some(views) if !views.is_empty() => views.into_iter().map(|(view, clip)| (view, Some(clip))).collect(),
+                _ => vec![(Matrix3::identity(), None)],
+            };
+
+            // retained sprites go first, immediate draw calls are layered on top in call order
+            self.renderer.draw_frame(Rgba::BLACK, &mut |canvas| {
+                for &(view, clip) in &views {
+                    canvas.set_clip(clip);
+                    let canvas = &mut ViewCanvas::new(canvas, view);
+
+                    for sprite in sprites.iter().filter(|sprite| sprite.visible) {
+                        if let Some(texture) = assets.resolve(&sprite.texture) {
+                            DrawCommand::texture(texture, sprite.x, sprite.y, sprite.rotation, sprite.scale, sprite.color).draw(canvas);
+                        }
+                    }
+
+                    for command in queue.iter() {
+                        command.draw(canvas);
+                    }
+                }
+                canvas.set_clip(None);
+
+                for command in queue.iter_screen() {
+                    command.draw(canvas);
+                }
+            });
+        }
+
+        if let Some(mut queue) = self.lua.app_data_mut::<DrawQueue>() {
+            queue.clear();
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "raylib")]
+fn create_renderer(config: &Bee2DConfig, headless: bool) -> Result<Box<dyn Renderer>, String> {
+    if headless {
+        Ok(Box::new(SoftwareRenderer::new(config.width, config.height)?))
+    } else {
+        Ok(Box::new(crate::render::RaylibRenderer::new(config.width, config.height, &config.title)))
+    }
+}
+
+#[cfg(not(feature = "raylib"))]
+fn create_renderer(config: &Bee2DConfig, _headless: bool) -> Result<Box<dyn Renderer>, String> {
+    Ok(Box::new(SoftwareRenderer::new(config.width, config.height)?))
+}
+
+#[cfg(feature = "raylib")]
+fn create_input_source(headless: bool) -> Box<dyn InputSource> {
+    if headless {
+        Box::new(NoInput)
+    } else {
+        Box::new(input::RaylibInput)
+    }
+}
 
-}  
\ No newline at end of file
+#[cfg(not(feature = "raylib"))]
+fn create_input_source(_headless: bool) -> Box<dyn InputSource> {
+    Box::new(NoInput)
+}
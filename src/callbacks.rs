@@ -0,0 +1,221 @@
+use mlua::prelude::*;
+use mlua::AppDataRefMut;
+
+use crate::lune::signal::{Connection, Disconnect};
+use crate::scheduler;
+
+/**
+    The points in a frame where bound callbacks run, in the order they run.
+
+    Update stages are passed the delta time, `FixedUpdate` the fixed one and
+    draw stages how far the frame is between two fixed updates. `UI` is drawn
+    after every camera, straight to the window, so it stays in place however
+    the cameras move.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    Start,
+    PreUpdate,
+    FixedUpdate,
+    Update,
+    PostUpdate,
+    PreDraw,
+    Draw,
+    UI,
+}
+
+// stages that can be picked from Luau, `Start` only through `bindToStart`
+const UPDATE_STAGES: &[(&str, Stage)] = &[
+    ("PreUpdate", Stage::PreUpdate),
+    ("Update", Stage::Update),
+    ("PostUpdate", Stage::PostUpdate),
+];
+
+const FIXED_UPDATE_STAGES: &[(&str, Stage)] = &[("FixedUpdate", Stage::FixedUpdate)];
+
+const DRAW_STAGES: &[(&str, Stage)] = &[
+    ("PreDraw", Stage::PreDraw),
+    ("Draw", Stage::Draw),
+    ("UI", Stage::UI),
+];
+
+struct BoundCallback {
+    id: u32,
+    stage: Stage,
+    priority: i32,
+    callback: LuaRegistryKey,
+}
+
+/**
+    Every callback bound through `Bee2D.bindTo*`, kept sorted by stage, then
+    priority, then the order they were bound in.
+*/
+#[derive(Default)]
+pub struct Callbacks {
+    next_id: u32,
+    bound: Vec<BoundCallback>,
+    // whether `Start` has run, it only ever runs once
+    started: bool,
+}
+
+impl Callbacks {
+    fn next_id(&mut self) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    fn bind(&mut self, stage: Stage, priority: i32, callback: LuaRegistryKey) -> u32 {
+        let id = self.next_id();
+
+        // after everything that sorts the same, so ties keep the order they were bound in
+        let index = self.bound.partition_point(|bound| (bound.stage, bound.priority) <= (stage, priority));
+        self.bound.insert(index, BoundCallback { id, stage, priority, callback });
+
+        id
+    }
+
+    fn is_bound(&self, id: u32) -> bool {
+        self.bound.iter().any(|bound| bound.id == id)
+    }
+}
+
+fn callbacks_mut(lua: &Lua) -> LuaResult<AppDataRefMut<'_, Callbacks>> {
+    lua.app_data_mut::<Callbacks>()
+        .ok_or_else(|| LuaError::RuntimeError("Bee2D has not been initialized".into()))
+}
+
+fn parse_stage(stages: &[(&str, Stage)], kind: &str, name: &str) -> LuaResult<Stage> {
+    stages.iter()
+        .find(|(n, _)| *n == name)
+        .map(|&(_, stage)| stage)
+        .ok_or_else(|| {
+            let names = stages.iter().map(|(n, _)| *n).collect::<Vec<_>>().join(", ");
+            LuaError::RuntimeError(format!("'{}' is not {} stage, expected one of {}", name, kind, names))
+        })
+}
+
+/**
+    Options for `bindToUpdate`, `bindToFixedUpdate` and `bindToDraw`, `{ Stage = "PostUpdate", Priority = 10 }`.
+    Lower priorities run first.
+*/
+pub struct BindOptions {
+    stage: Option<String>,
+    priority: i32,
+}
+
+impl<'lua> FromLua<'lua> for BindOptions {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<BindOptions> {
+        match value {
+            LuaValue::Nil => Ok(BindOptions { stage: None, priority: 0 }),
+            LuaValue::Table(table) => Ok(BindOptions {
+                stage: table.get("Stage")?,
+                priority: table.get::<_, Option<i32>>("Priority")?.unwrap_or(0),
+            }),
+            other => Err(LuaError::RuntimeError(format!("Expected an options table, got {}", other.type_name()))),
+        }
+    }
+}
+
+fn bind(lua: &Lua, stage: Stage, priority: i32, callback: LuaFunction) -> LuaResult<Connection> {
+    let key = lua.create_registry_value(callback)?;
+    let id = callbacks_mut(lua)?.bind(stage, priority, key);
+    Ok(Connection::new(BoundConnection { id }))
+}
+
+/**
+    Binds `callback` to run once before the first frame, or runs it right
+    away when that has passed. A callback that ran right away is never
+    connected, there is nothing left for it to be called by.
+*/
+pub fn bind_to_start(lua: &Lua, callback: LuaFunction) -> LuaResult<Connection> {
+    let mut callbacks = callbacks_mut(lua)?;
+    if !callbacks.started {
+        drop(callbacks);
+        return bind(lua, Stage::Start, 0, callback);
+    }
+
+    let id = callbacks.next_id();
+    drop(callbacks);
+    scheduler::spawn(lua, LuaValue::Function(callback), LuaMultiValue::new())?;
+    Ok(Connection::new(BoundConnection { id }))
+}
+
+pub fn bind_to_update(lua: &Lua, (callback, options): (LuaFunction, BindOptions)) -> LuaResult<Connection> {
+    let stage = match &options.stage {
+        Some(name) => parse_stage(UPDATE_STAGES, "an update", name)?,
+        None => Stage::Update,
+    };
+    bind(lua, stage, options.priority, callback)
+}
+
+pub fn bind_to_fixed_update(lua: &Lua, (callback, options): (LuaFunction, BindOptions)) -> LuaResult<Connection> {
+    let stage = match &options.stage {
+        Some(name) => parse_stage(FIXED_UPDATE_STAGES, "a fixed update", name)?,
+        None => Stage::FixedUpdate,
+    };
+    bind(lua, stage, options.priority, callback)
+}
+
+pub fn bind_to_draw(lua: &Lua, (callback, options): (LuaFunction, BindOptions)) -> LuaResult<Connection> {
+    let stage = match &options.stage {
+        Some(name) => parse_stage(DRAW_STAGES, "a draw", name)?,
+        None => Stage::Draw,
+    };
+    bind(lua, stage, options.priority, callback)
+}
+
+/**
+    Runs the `Start` stage the first time it is called and does nothing
+    after that. Callbacks bound to it from then on run as they are bound.
+*/
+pub fn start(lua: &Lua) -> LuaResult<()> {
+    let mut callbacks = callbacks_mut(lua)?;
+    if callbacks.started {
+        return Ok(());
+    }
+    callbacks.started = true;
+    drop(callbacks);
+
+    run_stage(lua, Stage::Start, LuaMultiValue::new())
+}
+
+/**
+    Runs every callback bound to `stage`, each in its own thread.
+
+    Callbacks bound while the stage runs wait for the next frame, while ones
+    disconnected by an earlier callback in the same stage don't run at all.
+*/
+pub fn run_stage<'lua>(lua: &'lua Lua, stage: Stage, args: LuaMultiValue<'lua>) -> LuaResult<()> {
+    let callbacks = {
+        let callbacks = callbacks_mut(lua)?;
+        callbacks.bound.iter()
+            .filter(|bound| bound.stage == stage)
+            .map(|bound| Ok((bound.id, lua.registry_value::<LuaFunction>(&bound.callback)?)))
+            .collect::<LuaResult<Vec<_>>>()?
+    };
+
+    for (id, callback) in callbacks {
+        if callbacks_mut(lua)?.is_bound(id) {
+            scheduler::spawn(lua, LuaValue::Function(callback), args.clone())?;
+        }
+    }
+
+    Ok(())
+}
+
+// what the connection returned from `Bee2D.bindTo*` disconnects
+struct BoundConnection {
+    id: u32,
+}
+
+impl Disconnect for BoundConnection {
+    fn is_connected(&self, lua: &Lua) -> LuaResult<bool> {
+        Ok(callbacks_mut(lua)?.is_bound(self.id))
+    }
+
+    fn disconnect(&self, lua: &Lua) -> LuaResult<()> {
+        callbacks_mut(lua)?.bound.retain(|bound| bound.id != self.id);
+        Ok(())
+    }
+}
@@ -0,0 +1,403 @@
+use core::fmt;
+use std::collections::BTreeMap;
+
+use mlua::prelude::*;
+use crate::lune::table_builder::*;
+use crate::lune::exports::*;
+use crate::lune::userdata::*;
+
+use crate::engine::component::Component;
+use crate::engine::gameobject::{scene_mut, GameObject};
+use crate::math::{Matrix3, Vector2};
+use crate::render::Region;
+
+/**
+    A view into the world, held by a `Camera2D` or by a Camera component,
+    which takes its position and rotation from its GameObject.
+*/
+#[derive(Clone)]
+pub struct CameraData {
+    pub position: (f32, f32),
+    pub zoom: f32,
+    // radians, clockwise on screen like the rotation of a Transform
+    pub rotation: f32,
+    // screen-space displacement of the view from the center of the viewport
+    pub offset: (f32, f32),
+    pub target: Option<GameObject>,
+    // how quickly the position catches up with the target, 0 snaps to it
+    pub follow_speed: f32,
+    // world-space area the view is kept inside of, as min and max corners
+    pub bounds: Option<((f32, f32), (f32, f32))>,
+    // part of the window drawn to, as fractions of its size
+    pub viewport: Region,
+    pub draw_order: i32,
+    pub enabled: bool,
+
+    shake_intensity: f32,
+    shake_duration: f32,
+    shake_remaining: f32,
+    shake_offset: (f32, f32),
+}
+
+impl CameraData {
+    pub fn new(position: (f32, f32)) -> CameraData {
+        CameraData {
+            position,
+            zoom: 1.0,
+            rotation: 0.0,
+            offset: (0.0, 0.0),
+            target: None,
+            follow_speed: 0.0,
+            bounds: None,
+            viewport: (0.0, 0.0, 1.0, 1.0),
+            draw_order: 0,
+            enabled: true,
+            shake_intensity: 0.0,
+            shake_duration: 0.0,
+            shake_remaining: 0.0,
+            shake_offset: (0.0, 0.0),
+        }
+    }
+
+    // the viewport in window pixels
+    pub fn viewport_rect(&self, screen_size: (f32, f32)) -> Region {
+        let (x, y, width, height) = self.viewport;
+        (x * screen_size.0, y * screen_size.1, width * screen_size.0, height * screen_size.1)
+    }
+
+    /**
+        Takes world space to window pixels, putting `position` at the center of
+        the viewport before the offset and any shake are applied.
+    */
+    pub fn view_matrix(&self, screen_size: (f32, f32)) -> Matrix3 {
+        let (x, y, width, height) = self.viewport_rect(screen_size);
+        let center_x = x + width / 2.0 + self.offset.0 + self.shake_offset.0;
+        let center_y = y + height / 2.0 + self.offset.1 + self.shake_offset.1;
+
+        Matrix3::translation(center_x, center_y)
+            * Matrix3::rotation_radians(-self.rotation)
+            * Matrix3::scaling(self.zoom, self.zoom)
+            * Matrix3::translation(-self.position.0, -self.position.1)
+    }
+
+    // moves the position so that the visible area stays inside the bounds, centering it when it can't
+    fn clamp_to_bounds(&mut self, screen_size: (f32, f32)) {
+        let Some(((min_x, min_y), (max_x, max_y))) = self.bounds else {
+            return;
+        };
+
+        let (_, _, width, height) = self.viewport_rect(screen_size);
+        let clamp = |value: f32, min: f32, max: f32, half_extent: f32| {
+            if max - min <= half_extent * 2.0 {
+                (min + max) / 2.0
+            } else {
+                value.clamp(min + half_extent, max - half_extent)
+            }
+        };
+
+        self.position = (
+            clamp(self.position.0, min_x, max_x, width / 2.0 / self.zoom),
+            clamp(self.position.1, min_y, max_y, height / 2.0 / self.zoom),
+        );
+    }
+}
+
+impl Default for CameraData {
+    // looks at the origin until something moves it
+    fn default() -> CameraData {
+        CameraData::new((0.0, 0.0))
+    }
+}
+
+/**
+    Every `Camera2D` and Camera component, along with the window size their
+    viewports are relative to.
+
+    The scene is drawn once through every enabled camera. Without any, it is
+    drawn straight to the window in pixels.
+*/
+pub struct Cameras {
+    next_id: u32,
+    cameras: BTreeMap<u32, CameraData>,
+    // the Camera components in the scene as of the last update, placed at their GameObject
+    attached: Vec<CameraData>,
+    screen_size: (f32, f32),
+    // xorshift state for screen shake, seeded so headless runs are reproducible
+    random_state: u32,
+}
+
+impl Cameras {
+    pub fn new(screen_size: (f32, f32)) -> Cameras {
+        Cameras {
+            next_id: 0,
+            cameras: BTreeMap::new(),
+            attached: Vec::new(),
+            screen_size,
+            random_state: 0x9e37_79b9,
+        }
+    }
+
+    pub fn insert(&mut self, data: CameraData) -> Camera2D {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.cameras.insert(id, data);
+        Camera2D { id }
+    }
+
+    pub fn set_screen_size(&mut self, screen_size: (f32, f32)) {
+        self.screen_size = screen_size;
+    }
+
+    /**
+        The view matrix and viewport of every enabled camera, in draw order.
+    */
+    pub fn views(&self) -> Vec<(Matrix3, Region)> {
+        let mut cameras = self.cameras.values()
+            .chain(&self.attached)
+            .filter(|camera| camera.enabled)
+            .collect::<Vec<_>>();
+        cameras.sort_by_key(|camera| camera.draw_order);
+
+        cameras.into_iter()
+            .map(|camera| (camera.view_matrix(self.screen_size), camera.viewport_rect(self.screen_size)))
+            .collect()
+    }
+
+    // uniformly distributed in [-1, 1]
+    fn random(&mut self) -> f32 {
+        let mut x = self.random_state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.random_state = x;
+        (x as f64 / u32::MAX as f64 * 2.0 - 1.0) as f32
+    }
+}
+
+/**
+    Moves every camera towards its target, keeps it inside its bounds and advances
+    screen shake. Camera components are moved to their GameObject instead.
+    Runs once global matrices are up to date for the frame.
+*/
+pub fn update(lua: &Lua, dt: f64) -> LuaResult<()> {
+    let dt = dt as f32;
+
+    // target positions are gathered first, the scene and the cameras can't be borrowed together
+    let (targets, attached) = {
+        let cameras = cameras(lua)?;
+        let scene = scene_mut(lua)?;
+
+        let root = scene.root();
+        let mut attached = Vec::new();
+        for object in [vec![root], scene.descendants(root)].concat() {
+            let Some(data) = scene.get(object) else {
+                continue;
+            };
+            let matrix = data.transform.cached_global_matrix();
+
+            for id in scene.component_ids(object) {
+                if let Some(Component::Camera(camera)) = scene.component(object, id) {
+                    let mut camera = camera.clone();
+                    camera.position = (matrix.m02, matrix.m12);
+                    camera.rotation = matrix.rotation();
+                    attached.push(camera);
+                }
+            }
+        }
+
+        let targets = cameras.cameras.iter()
+            .filter_map(|(&id, camera)| Some((id, camera.target?)))
+            .map(|(id, target)| {
+                let position = scene.get(target).map(|data| {
+                    let matrix = data.transform.cached_global_matrix();
+                    (matrix.m02, matrix.m12)
+                });
+                (id, position)
+            })
+            .collect::<Vec<_>>();
+
+        (targets, attached)
+    };
+
+    let mut cameras = cameras_mut(lua)?;
+    let screen_size = cameras.screen_size;
+
+    cameras.attached = attached;
+    for camera in &mut cameras.attached {
+        camera.clamp_to_bounds(screen_size);
+    }
+
+    for (id, position) in targets {
+        let camera = cameras.cameras.get_mut(&id).expect("camera was looked up above");
+        let Some((x, y)) = position else {
+            // the target has been destroyed
+            camera.target = None;
+            continue;
+        };
+
+        let t = if camera.follow_speed > 0.0 { 1.0 - (-camera.follow_speed * dt).exp() } else { 1.0 };
+        camera.position.0 += (x - camera.position.0) * t;
+        camera.position.1 += (y - camera.position.1) * t;
+    }
+
+    let ids = cameras.cameras.keys().copied().collect::<Vec<_>>();
+    for id in ids {
+        let (dx, dy) = (cameras.random(), cameras.random());
+        let camera = cameras.cameras.get_mut(&id).expect("ids were collected above");
+        camera.clamp_to_bounds(screen_size);
+
+        camera.shake_remaining = (camera.shake_remaining - dt).max(0.0);
+        // shake fades out linearly over its duration
+        let strength = if camera.shake_remaining > 0.0 {
+            camera.shake_intensity * camera.shake_remaining / camera.shake_duration
+        } else {
+            0.0
+        };
+        camera.shake_offset = (dx * strength, dy * strength);
+    }
+
+    Ok(())
+}
+
+// shared by `Camera2D` and the Camera component, both divide by the zoom
+pub(crate) fn check_zoom(zoom: f32) -> LuaResult<f32> {
+    if !zoom.is_finite() || zoom <= 0.0 {
+        return Err(LuaError::RuntimeError(format!("Zoom must be a finite number greater than 0, got {}", zoom)));
+    }
+    Ok(zoom)
+}
+
+fn cameras(lua: &Lua) -> LuaResult<mlua::AppDataRef<'_, Cameras>> {
+    lua.app_data_ref::<Cameras>()
+        .ok_or_else(|| LuaError::RuntimeError("Bee2D has not been initialized".into()))
+}
+
+fn cameras_mut(lua: &Lua) -> LuaResult<mlua::AppDataRefMut<'_, Cameras>> {
+    lua.app_data_mut::<Cameras>()
+        .ok_or_else(|| LuaError::RuntimeError("Bee2D has not been initialized".into()))
+}
+
+// handle to a camera stored in the `Cameras` app data
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera2D {
+    id: u32,
+}
+
+impl Camera2D {
+    fn with<R>(&self, lua: &Lua, f: impl FnOnce(&mut CameraData, (f32, f32)) -> R) -> LuaResult<R> {
+        let mut cameras = cameras_mut(lua)?;
+        let screen_size = cameras.screen_size;
+
+        cameras.cameras.get_mut(&self.id)
+            .map(|camera| f(camera, screen_size))
+            .ok_or_else(|| LuaError::RuntimeError("Camera2D has been destroyed".into()))
+    }
+}
+
+impl LuaExportsTable<'_> for Camera2D {
+    const EXPORT_NAME: &'static str = "Camera2D";
+
+    fn create_exports_table(lua: &Lua) -> LuaResult<LuaTable<'_>> {
+        // looks at the center of the window, the same view as drawing without a camera
+        let camera_new = |lua, ()| {
+            let mut cameras = cameras_mut(lua)?;
+            let (width, height) = cameras.screen_size;
+            Ok(cameras.insert(CameraData::new((width / 2.0, height / 2.0))))
+        };
+
+        TableBuilder::new(lua)?
+            .with_function("new", camera_new)?
+            .build_readonly()
+    }
+}
+
+impl LuaUserData for Camera2D {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_field_method_get("Position", |lua, this| this.with(lua, |c, _| Vector2::new(c.position.0, c.position.1)));
+        fields.add_field_method_get("Zoom", |lua, this| this.with(lua, |c, _| c.zoom));
+        fields.add_field_method_get("Rotation", |lua, this| this.with(lua, |c, _| c.rotation));
+        fields.add_field_method_get("Offset", |lua, this| this.with(lua, |c, _| Vector2::new(c.offset.0, c.offset.1)));
+        fields.add_field_method_get("Target", |lua, this| this.with(lua, |c, _| c.target));
+        fields.add_field_method_get("FollowSpeed", |lua, this| this.with(lua, |c, _| c.follow_speed));
+        fields.add_field_method_get("ViewportPosition", |lua, this| {
+            this.with(lua, |c, _| Vector2::new(c.viewport.0, c.viewport.1))
+        });
+        fields.add_field_method_get("ViewportSize", |lua, this| {
+            this.with(lua, |c, _| Vector2::new(c.viewport.2, c.viewport.3))
+        });
+        fields.add_field_method_get("DrawOrder", |lua, this| this.with(lua, |c, _| c.draw_order));
+        fields.add_field_method_get("Enabled", |lua, this| this.with(lua, |c, _| c.enabled));
+
+        fields.add_field_method_set("Position", |lua, this, v: LuaUserDataRef<Vector2>| {
+            this.with(lua, |c, _| c.position = (v.get_x(), v.get_y()))
+        });
+        fields.add_field_method_set("Zoom", |lua, this, zoom: f32| {
+            let zoom = check_zoom(zoom)?;
+            this.with(lua, |c, _| c.zoom = zoom)
+        });
+        fields.add_field_method_set("Rotation", |lua, this, rotation: f32| this.with(lua, |c, _| c.rotation = rotation));
+        fields.add_field_method_set("Offset", |lua, this, v: LuaUserDataRef<Vector2>| {
+            this.with(lua, |c, _| c.offset = (v.get_x(), v.get_y()))
+        });
+        fields.add_field_method_set("Target", |lua, this, target: Option<GameObject>| this.with(lua, |c, _| c.target = target));
+        fields.add_field_method_set("FollowSpeed", |lua, this, speed: f32| this.with(lua, |c, _| c.follow_speed = speed.max(0.0)));
+        fields.add_field_method_set("ViewportPosition", |lua, this, v: LuaUserDataRef<Vector2>| {
+            this.with(lua, |c, _| (c.viewport.0, c.viewport.1) = (v.get_x(), v.get_y()))
+        });
+        fields.add_field_method_set("ViewportSize", |lua, this, v: LuaUserDataRef<Vector2>| {
+            this.with(lua, |c, _| (c.viewport.2, c.viewport.3) = (v.get_x(), v.get_y()))
+        });
+        fields.add_field_method_set("DrawOrder", |lua, this, order: i32| this.with(lua, |c, _| c.draw_order = order));
+        fields.add_field_method_set("Enabled", |lua, this, enabled: bool| this.with(lua, |c, _| c.enabled = enabled));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("SetBounds", |lua, this, (min, max): (LuaUserDataRef<Vector2>, LuaUserDataRef<Vector2>)| {
+            this.with(lua, |c, screen_size| {
+                c.bounds = Some(((min.get_x(), min.get_y()), (max.get_x(), max.get_y())));
+                c.clamp_to_bounds(screen_size);
+            })
+        });
+
+        methods.add_method("ClearBounds", |lua, this, ()| this.with(lua, |c, _| c.bounds = None));
+
+        // shakes by up to `intensity` pixels, fading out over `duration` seconds
+        methods.add_method("Shake", |lua, this, (intensity, duration): (f32, f32)| {
+            this.with(lua, |c, _| {
+                c.shake_intensity = intensity;
+                c.shake_duration = duration;
+                c.shake_remaining = duration;
+            })
+        });
+
+        methods.add_method("WorldToScreen", |lua, this, v: LuaUserDataRef<Vector2>| {
+            this.with(lua, |c, screen_size| {
+                let (x, y) = c.view_matrix(screen_size).transform_point(v.get_x(), v.get_y());
+                Vector2::new(x, y)
+            })
+        });
+
+        methods.add_method("ScreenToWorld", |lua, this, v: LuaUserDataRef<Vector2>| {
+            let inverse = this.with(lua, |c, screen_size| c.view_matrix(screen_size).affine_inverse())?
+                .ok_or_else(|| LuaError::RuntimeError("Camera2D has a degenerate view".into()))?;
+            let (x, y) = inverse.transform_point(v.get_x(), v.get_y());
+            Ok(Vector2::new(x, y))
+        });
+
+        methods.add_method("Destroy", |lua, this, ()| {
+            if let Some(mut cameras) = lua.app_data_mut::<Cameras>() {
+                cameras.cameras.remove(&this.id);
+            }
+            Ok(())
+        });
+
+        methods.add_meta_method(LuaMetaMethod::Eq, userdata_impl_eq);
+        methods.add_meta_method(LuaMetaMethod::ToString, userdata_impl_to_string);
+    }
+}
+
+impl fmt::Display for Camera2D {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Camera2D {}", self.id)
+    }
+}
@@ -0,0 +1,469 @@
+use std::collections::{BTreeMap, HashMap};
+
+use mlua::prelude::*;
+
+use crate::assets::{SpriteRegion, TextureRef};
+use crate::engine::gameobject::{scene_mut, GameObject, Scene};
+use crate::lune::signal::Signal;
+use crate::math::Matrix3;
+use crate::render::{DrawCommand, TextureInfo};
+
+use super::sprite_renderer::SpriteRendererData;
+use super::{add_handle_fields, add_handle_methods, Component, ComponentHandle, ComponentKind};
+
+const DEFAULT_FRAME_DURATION: f64 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlayMode {
+    Loop,
+    // forwards then backwards, without showing the first and last frames twice
+    PingPong,
+    // stops on the last frame
+    Once,
+}
+
+impl PlayMode {
+    fn from_name(name: &str) -> LuaResult<PlayMode> {
+        match name {
+            "Loop" => Ok(PlayMode::Loop),
+            "PingPong" => Ok(PlayMode::PingPong),
+            "Once" => Ok(PlayMode::Once),
+            _ => Err(LuaError::RuntimeError(format!("'{}' is not a play mode, expected Loop, PingPong or Once", name))),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Frame {
+    region: SpriteRegion,
+    duration: f64,
+    // fired through `FrameEvent` whenever the frame is reached
+    event: Option<String>,
+}
+
+/**
+    A named animation, `{ Frames = { ... }, Mode = "Loop", FrameDuration = 0.1 }` in Luau.
+
+    Frames last `Durations[i]` if given, then however long the sprite sheet
+    says, then `FrameDuration`. `Events = { [3] = "Footstep" }` names frames
+    that fire `FrameEvent`. The tables returned from `SpriteSheet:GetTag` are
+    clips too, their `Direction` picks the frame order and default mode.
+*/
+#[derive(Clone)]
+pub struct Clip {
+    frames: Vec<Frame>,
+    mode: PlayMode,
+}
+
+impl<'lua> FromLua<'lua> for Clip {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<Clip> {
+        let LuaValue::Table(table) = value else {
+            return Err(LuaError::RuntimeError(format!("Expected a clip table, got {}", value.type_name())));
+        };
+
+        let mut regions = table.get::<_, Vec<LuaUserDataRef<SpriteRegion>>>("Frames")?
+            .iter()
+            .map(|region| (*region).clone())
+            .collect::<Vec<_>>();
+        if regions.is_empty() {
+            return Err(LuaError::RuntimeError("A clip needs at least one frame".into()));
+        }
+
+        let direction = table.get::<_, Option<String>>("Direction")?;
+        let (reversed, ping_pong) = match direction.as_deref() {
+            None | Some("Forward") => (false, false),
+            Some("Reverse") => (true, false),
+            Some("PingPong") => (false, true),
+            Some("PingPongReverse") => (true, true),
+            Some(other) => return Err(LuaError::RuntimeError(format!("'{}' is not a direction", other))),
+        };
+        if reversed {
+            regions.reverse();
+        }
+
+        let mode = match table.get::<_, Option<String>>("Mode")? {
+            Some(mode) => PlayMode::from_name(&mode)?,
+            None if ping_pong => PlayMode::PingPong,
+            None => PlayMode::Loop,
+        };
+
+        let frame_duration = table.get::<_, Option<f64>>("FrameDuration")?.unwrap_or(DEFAULT_FRAME_DURATION);
+        let durations = table.get::<_, Option<Vec<f64>>>("Durations")?.unwrap_or_default();
+        let mut events = table.get::<_, Option<HashMap<usize, String>>>("Events")?.unwrap_or_default();
+
+        let frames = regions.into_iter()
+            .enumerate()
+            .map(|(index, region)| {
+                let duration = durations.get(index).copied()
+                    .or(region.duration())
+                    .unwrap_or(frame_duration);
+                if !duration.is_finite() || duration <= 0.0 {
+                    return Err(LuaError::RuntimeError(format!("Frame {} must last longer than 0 seconds, got {}", index + 1, duration)));
+                }
+                Ok(Frame { region, duration, event: events.remove(&(index + 1)) })
+            })
+            .collect::<LuaResult<Vec<_>>>()?;
+
+        if let Some(frame) = events.keys().next() {
+            return Err(LuaError::RuntimeError(format!("The clip has no frame {} to put an event on", frame)));
+        }
+
+        Ok(Clip { frames, mode })
+    }
+}
+
+// what happened while advancing, fired once the scene is no longer borrowed
+pub enum AnimatorEvent {
+    Frame { signal: Signal, event: String, clip: String, frame: usize },
+    Ended { signal: Signal, clip: String },
+}
+
+impl AnimatorEvent {
+    pub fn fire(self, lua: &Lua) -> LuaResult<()> {
+        match self {
+            AnimatorEvent::Frame { signal, event, clip, frame } => signal.fire(lua, (event, clip, frame + 1)),
+            AnimatorEvent::Ended { signal, clip } => signal.fire(lua, clip),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Playback {
+    clip: String,
+    frame: usize,
+    // time spent on the current frame
+    elapsed: f64,
+    forward: bool,
+    finished: bool,
+    // the event of the first frame fires on the first update, not when the clip is played
+    entered: bool,
+}
+
+impl Playback {
+    fn new(clip: &str) -> Playback {
+        Playback { clip: clip.to_string(), frame: 0, elapsed: 0.0, forward: true, finished: false, entered: false }
+    }
+
+    fn next_frame(&mut self, clip: &Clip) -> Option<usize> {
+        let last = clip.frames.len() - 1;
+        match clip.mode {
+            PlayMode::Loop => Some(if self.frame == last { 0 } else { self.frame + 1 }),
+            PlayMode::Once => (self.frame < last).then_some(self.frame + 1),
+            PlayMode::PingPong if last == 0 => Some(0),
+            PlayMode::PingPong => {
+                if (self.forward && self.frame == last) || (!self.forward && self.frame == 0) {
+                    self.forward = !self.forward;
+                }
+                Some(if self.forward { self.frame + 1 } else { self.frame - 1 })
+            }
+        }
+    }
+
+    fn enter(&self, clip: &Clip, signal: &Signal, events: &mut Vec<AnimatorEvent>) {
+        if let Some(event) = &clip.frames[self.frame].event {
+            events.push(AnimatorEvent::Frame { signal: signal.clone(), event: event.clone(), clip: self.clip.clone(), frame: self.frame });
+        }
+    }
+
+    fn advance(&mut self, clip: &Clip, dt: f64, signals: (&Signal, &Signal), events: &mut Vec<AnimatorEvent>) {
+        let (ended, frame_event) = signals;
+        // the clip may have been replaced by a shorter one while it played
+        self.frame = self.frame.min(clip.frames.len() - 1);
+
+        if !self.entered {
+            self.entered = true;
+            self.enter(clip, frame_event, events);
+        }
+        if self.finished {
+            return;
+        }
+
+        self.elapsed += dt;
+        // twice through the frames is a whole cycle even ping-ponging, a longer step skips the rest of the time
+        for _ in 0..clip.frames.len() * 2 {
+            if self.elapsed < clip.frames[self.frame].duration {
+                return;
+            }
+            self.elapsed -= clip.frames[self.frame].duration;
+
+            match self.next_frame(clip) {
+                Some(frame) => {
+                    self.frame = frame;
+                    self.enter(clip, frame_event, events);
+                }
+                None => {
+                    self.finished = true;
+                    self.elapsed = 0.0;
+                    events.push(AnimatorEvent::Ended { signal: ended.clone(), clip: self.clip.clone() });
+                    return;
+                }
+            }
+        }
+        self.elapsed %= clip.frames[self.frame].duration;
+    }
+}
+
+struct Fade {
+    from: Playback,
+    elapsed: f64,
+    duration: f64,
+}
+
+/**
+    Plays clips of sprite sheet frames on the first SpriteRenderer of its
+    GameObject, advanced by the scaled delta time of every frame.
+
+    While crossfading, the last clip keeps playing and is drawn over the new
+    one, fading out.
+*/
+pub struct AnimatorData {
+    clips: BTreeMap<String, Clip>,
+    current: Option<Playback>,
+    fade: Option<Fade>,
+    playing: bool,
+    pub speed: f64,
+    animation_ended: Signal,
+    frame_event: Signal,
+}
+
+impl Default for AnimatorData {
+    fn default() -> AnimatorData {
+        AnimatorData {
+            clips: BTreeMap::new(),
+            current: None,
+            fade: None,
+            playing: false,
+            speed: 1.0,
+            animation_ended: Signal::new(),
+            frame_event: Signal::new(),
+        }
+    }
+}
+
+// a copy plays the same clips, but the functions connected to the original stay with it
+impl Clone for AnimatorData {
+    fn clone(&self) -> AnimatorData {
+        AnimatorData {
+            clips: self.clips.clone(),
+            current: self.current.clone(),
+            fade: None,
+            playing: self.playing,
+            speed: self.speed,
+            ..AnimatorData::default()
+        }
+    }
+}
+
+impl AnimatorData {
+    fn clip(&self, name: &str) -> LuaResult<&Clip> {
+        self.clips.get(name)
+            .ok_or_else(|| LuaError::RuntimeError(format!("'{}' is not a clip of this Animator", name)))
+    }
+
+    // restarts the clip, unless it is already playing and `restart` isn't set
+    fn play(&mut self, name: &str, restart: bool) -> LuaResult<()> {
+        self.clip(name)?;
+        let playing = self.playing && self.current.as_ref().is_some_and(|p| p.clip == name && !p.finished);
+        if !playing || restart {
+            self.current = Some(Playback::new(name));
+            self.fade = None;
+        }
+        self.playing = true;
+        Ok(())
+    }
+
+    fn crossfade(&mut self, name: &str, duration: f64) -> LuaResult<()> {
+        if !duration.is_finite() {
+            return Err(LuaError::RuntimeError(format!("Crossfade duration must be finite, got {}", duration)));
+        }
+        let from = self.current.clone();
+        self.play(name, true)?;
+
+        if let Some(from) = from.filter(|_| duration > 0.0) {
+            self.fade = Some(Fade { from, elapsed: 0.0, duration });
+        }
+        Ok(())
+    }
+
+    // replacing the clip that is playing starts it over, and stops fading it out
+    fn add_clip(&mut self, name: String, clip: Clip) {
+        if let Some(current) = self.current.as_mut().filter(|playback| playback.clip == name) {
+            *current = Playback::new(&name);
+        }
+        self.fade = self.fade.take().filter(|fade| fade.from.clip != name);
+        self.clips.insert(name, clip);
+    }
+
+    fn stop(&mut self) {
+        self.playing = false;
+        self.fade = None;
+    }
+
+    pub fn advance(&mut self, dt: f64, events: &mut Vec<AnimatorEvent>) {
+        if !self.playing {
+            return;
+        }
+
+        let dt = dt * self.speed;
+        let signals = (&self.animation_ended, &self.frame_event);
+
+        if let Some(fade) = &mut self.fade {
+            fade.elapsed += dt;
+            if fade.elapsed >= fade.duration {
+                self.fade = None;
+            } else if let Some(clip) = self.clips.get(&fade.from.clip) {
+                // the clip fading out doesn't fire events anymore
+                fade.from.advance(clip, dt, signals, &mut Vec::new());
+            }
+        }
+
+        if let Some(current) = &mut self.current {
+            if let Some(clip) = self.clips.get(&current.clip) {
+                current.advance(clip, dt, signals, events);
+            }
+        }
+    }
+
+    fn region(&self, playback: &Playback) -> Option<&SpriteRegion> {
+        let clip = self.clips.get(&playback.clip)?;
+        clip.frames.get(playback.frame).or(clip.frames.last()).map(|frame| &frame.region)
+    }
+
+    pub fn current_region(&self) -> Option<&SpriteRegion> {
+        self.region(self.current.as_ref()?)
+    }
+}
+
+/**
+    The frame of the clip an Animator of `object` is fading out, drawn like
+    the SpriteRenderer `sprite_id` would draw it but more transparent. Only
+    the first SpriteRenderer of an object is animated.
+*/
+pub fn fade_command(
+    scene: &Scene,
+    object: GameObject,
+    sprite_id: u32,
+    matrix: Matrix3,
+    texture: &dyn Fn(&TextureRef) -> Option<TextureInfo>,
+) -> Option<DrawCommand> {
+    let ids = scene.component_ids(object);
+    let first_sprite = ids.iter().find(|&&id| matches!(scene.component(object, id), Some(Component::SpriteRenderer(_))));
+    if first_sprite != Some(&sprite_id) {
+        return None;
+    }
+
+    let (animator, fade) = ids.iter().find_map(|&id| match scene.component(object, id) {
+        Some(Component::Animator(animator)) => Some((animator, animator.fade.as_ref()?)),
+        _ => None,
+    })?;
+    let Some(Component::SpriteRenderer(sprite)) = scene.component(object, sprite_id) else {
+        return None;
+    };
+
+    let mut sprite: SpriteRendererData = sprite.clone();
+    sprite.region = Some(animator.region(&fade.from)?.clone());
+    sprite.color.a = (sprite.color.a as f64 * (1.0 - fade.elapsed / fade.duration)).round() as u8;
+    sprite.draw_command(matrix, texture)
+}
+
+/**
+    Advances the Animators of `objects` and shows their current frames on
+    their SpriteRenderers, then fires the events that came up.
+*/
+pub fn update(lua: &Lua, objects: &[GameObject], dt: f64) -> LuaResult<()> {
+    let mut events = Vec::new();
+
+    {
+        let mut scene = scene_mut(lua)?;
+        for &object in objects {
+            let ids = scene.component_ids(object);
+
+            let mut region = None;
+            for &id in &ids {
+                if let Some(Component::Animator(animator)) = scene.component_mut(object, id) {
+                    animator.advance(dt, &mut events);
+                    region = animator.current_region().cloned();
+                }
+            }
+
+            let Some(region) = region else {
+                continue;
+            };
+            for &id in &ids {
+                if let Some(Component::SpriteRenderer(sprite)) = scene.component_mut(object, id) {
+                    sprite.region = Some(region);
+                    break;
+                }
+            }
+        }
+    }
+
+    for event in events {
+        event.fire(lua)?;
+    }
+
+    Ok(())
+}
+
+impl ComponentKind for AnimatorData {
+    const NAME: &'static str = "Animator";
+
+    fn get_mut(component: &mut Component) -> Option<&mut Self> {
+        match component {
+            Component::Animator(data) => Some(data),
+            _ => None,
+        }
+    }
+}
+
+pub type Animator = ComponentHandle<AnimatorData>;
+
+impl LuaUserData for Animator {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        add_handle_fields(fields);
+
+        fields.add_field_method_get("Speed", |lua, this| this.with(lua, |a| a.speed));
+        fields.add_field_method_get("IsPlaying", |lua, this| this.with(lua, |a| a.playing));
+        fields.add_field_method_get("CurrentClip", |lua, this| {
+            this.with(lua, |a| a.current.as_ref().map(|playback| playback.clip.clone()))
+        });
+        fields.add_field_method_get("Frame", |lua, this| {
+            this.with(lua, |a| a.current.as_ref().map(|playback| playback.frame + 1))
+        });
+        fields.add_field_method_get("AnimationEnded", |lua, this| this.with(lua, |a| a.animation_ended.clone()));
+        fields.add_field_method_get("FrameEvent", |lua, this| this.with(lua, |a| a.frame_event.clone()));
+
+        fields.add_field_method_set("Speed", |lua, this, speed: f64| {
+            if !speed.is_finite() || speed < 0.0 {
+                return Err(LuaError::RuntimeError(format!("Speed must be a finite number 0 or greater, got {}", speed)));
+            }
+            this.with(lua, |a| a.speed = speed)
+        });
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        add_handle_methods(methods);
+
+        methods.add_method("AddClip", |lua, this, (name, clip): (String, Clip)| {
+            this.with(lua, |a| a.add_clip(name, clip))
+        });
+        methods.add_method("RemoveClip", |lua, this, name: String| {
+            this.with(lua, |a| {
+                a.clips.remove(&name);
+                if a.current.as_ref().is_some_and(|playback| playback.clip == name) {
+                    a.current = None;
+                    a.playing = false;
+                }
+                a.fade = a.fade.take().filter(|fade| fade.from.clip != name);
+            })
+        });
+        methods.add_method("HasClip", |lua, this, name: String| this.with(lua, |a| a.clips.contains_key(&name)));
+        methods.add_method("Play", |lua, this, (name, restart): (String, Option<bool>)| {
+            this.with(lua, |a| a.play(&name, restart.unwrap_or(false)))?
+        });
+        methods.add_method("Crossfade", |lua, this, (name, duration): (String, f64)| {
+            this.with(lua, |a| a.crossfade(&name, duration))?
+        });
+        methods.add_method("Stop", |lua, this, ()| this.with(lua, |a| a.stop()));
+    }
+}
@@ -0,0 +1,67 @@
+use mlua::prelude::*;
+
+use super::{add_handle_fields, add_handle_methods, Component, ComponentHandle, ComponentKind};
+
+/**
+    Playback settings for a sound attached to a GameObject.
+
+    No renderer has an audio device yet, so `Playing` only tracks what
+    scripts asked for and nothing is heard.
+*/
+#[derive(Clone)]
+pub struct AudioSourceData {
+    pub clip: Option<String>,
+    pub volume: f32,
+    pub pitch: f32,
+    pub looped: bool,
+    pub playing: bool,
+}
+
+impl Default for AudioSourceData {
+    fn default() -> AudioSourceData {
+        AudioSourceData {
+            clip: None,
+            volume: 1.0,
+            pitch: 1.0,
+            looped: false,
+            playing: false,
+        }
+    }
+}
+
+impl ComponentKind for AudioSourceData {
+    const NAME: &'static str = "AudioSource";
+
+    fn get_mut(component: &mut Component) -> Option<&mut Self> {
+        match component {
+            Component::AudioSource(data) => Some(data),
+            _ => None,
+        }
+    }
+}
+
+pub type AudioSource = ComponentHandle<AudioSourceData>;
+
+impl LuaUserData for AudioSource {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        add_handle_fields(fields);
+
+        fields.add_field_method_get("Clip", |lua, this| this.with(lua, |a| a.clip.clone()));
+        fields.add_field_method_get("Volume", |lua, this| this.with(lua, |a| a.volume));
+        fields.add_field_method_get("Pitch", |lua, this| this.with(lua, |a| a.pitch));
+        fields.add_field_method_get("Looped", |lua, this| this.with(lua, |a| a.looped));
+        fields.add_field_method_get("Playing", |lua, this| this.with(lua, |a| a.playing));
+
+        fields.add_field_method_set("Clip", |lua, this, clip: Option<String>| this.with(lua, |a| a.clip = clip));
+        fields.add_field_method_set("Volume", |lua, this, volume: f32| this.with(lua, |a| a.volume = volume.max(0.0)));
+        fields.add_field_method_set("Pitch", |lua, this, pitch: f32| this.with(lua, |a| a.pitch = pitch.max(0.0)));
+        fields.add_field_method_set("Looped", |lua, this, looped: bool| this.with(lua, |a| a.looped = looped));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        add_handle_methods(methods);
+
+        methods.add_method("Play", |lua, this, ()| this.with(lua, |a| a.playing = a.clip.is_some()));
+        methods.add_method("Stop", |lua, this, ()| this.with(lua, |a| a.playing = false));
+    }
+}
@@ -0,0 +1,59 @@
+use mlua::prelude::*;
+
+use crate::engine::camera::check_zoom;
+use crate::math::Vector2;
+
+use super::{add_handle_fields, add_handle_methods, Component, ComponentHandle, ComponentKind};
+
+// the same view a Camera2D has, positioned and rotated by the GameObject every frame
+pub use crate::engine::camera::CameraData;
+
+impl ComponentKind for CameraData {
+    const NAME: &'static str = "Camera";
+
+    fn get_mut(component: &mut Component) -> Option<&mut Self> {
+        match component {
+            Component::Camera(data) => Some(data),
+            _ => None,
+        }
+    }
+}
+
+pub type Camera = ComponentHandle<CameraData>;
+
+impl LuaUserData for Camera {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        add_handle_fields(fields);
+
+        fields.add_field_method_get("Zoom", |lua, this| this.with(lua, |c| c.zoom));
+        fields.add_field_method_get("Offset", |lua, this| this.with(lua, |c| Vector2::new(c.offset.0, c.offset.1)));
+        fields.add_field_method_get("ViewportPosition", |lua, this| {
+            this.with(lua, |c| Vector2::new(c.viewport.0, c.viewport.1))
+        });
+        fields.add_field_method_get("ViewportSize", |lua, this| {
+            this.with(lua, |c| Vector2::new(c.viewport.2, c.viewport.3))
+        });
+        fields.add_field_method_get("DrawOrder", |lua, this| this.with(lua, |c| c.draw_order));
+        fields.add_field_method_get("Enabled", |lua, this| this.with(lua, |c| c.enabled));
+
+        fields.add_field_method_set("Zoom", |lua, this, zoom: f32| {
+            let zoom = check_zoom(zoom)?;
+            this.with(lua, |c| c.zoom = zoom)
+        });
+        fields.add_field_method_set("Offset", |lua, this, offset: LuaUserDataRef<Vector2>| {
+            this.with(lua, |c| c.offset = (offset.get_x(), offset.get_y()))
+        });
+        fields.add_field_method_set("ViewportPosition", |lua, this, v: LuaUserDataRef<Vector2>| {
+            this.with(lua, |c| (c.viewport.0, c.viewport.1) = (v.get_x(), v.get_y()))
+        });
+        fields.add_field_method_set("ViewportSize", |lua, this, v: LuaUserDataRef<Vector2>| {
+            this.with(lua, |c| (c.viewport.2, c.viewport.3) = (v.get_x(), v.get_y()))
+        });
+        fields.add_field_method_set("DrawOrder", |lua, this, order: i32| this.with(lua, |c| c.draw_order = order));
+        fields.add_field_method_set("Enabled", |lua, this, enabled: bool| this.with(lua, |c| c.enabled = enabled));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        add_handle_methods(methods);
+    }
+}
@@ -0,0 +1,249 @@
+use mlua::prelude::*;
+
+use crate::lune::signal::Signal;
+use crate::math::{Matrix3, Vector2};
+use crate::physics::{self, Shape};
+
+use super::{add_handle_fields, add_handle_methods, Component, ComponentHandle, ComponentKind};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColliderShape {
+    Box,
+    // uses the x component of the size as its diameter
+    Circle,
+    // upright, as wide as the x component of the size and as tall as the y one, round ends included
+    Capsule,
+    // the convex hull of `points`
+    Polygon,
+}
+
+impl ColliderShape {
+    fn name(self) -> &'static str {
+        match self {
+            ColliderShape::Box => "Box",
+            ColliderShape::Circle => "Circle",
+            ColliderShape::Capsule => "Capsule",
+            ColliderShape::Polygon => "Polygon",
+        }
+    }
+
+    fn from_name(name: &str) -> LuaResult<ColliderShape> {
+        match name {
+            "Box" => Ok(ColliderShape::Box),
+            "Circle" => Ok(ColliderShape::Circle),
+            "Capsule" => Ok(ColliderShape::Capsule),
+            "Polygon" => Ok(ColliderShape::Polygon),
+            _ => Err(LuaError::RuntimeError(format!("'{}' is not a collider shape, expected Box, Circle, Capsule or Polygon", name))),
+        }
+    }
+}
+
+// counter-clockwise convex hull, by Andrew's monotone chain
+fn convex_hull(mut points: Vec<Vector2>) -> Vec<Vector2> {
+    points.sort_by(|a, b| a.get_x().total_cmp(&b.get_x()).then(a.get_y().total_cmp(&b.get_y())));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+
+    let half = |points: &mut dyn Iterator<Item = Vector2>| {
+        let mut hull: Vec<Vector2> = Vec::new();
+        for p in points {
+            while hull.len() >= 2 && (hull[hull.len() - 1] - hull[hull.len() - 2]).cross(p - hull[hull.len() - 2]) <= 0.0 {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        // the last point starts the other half
+        hull.pop();
+        hull
+    };
+
+    let mut hull = half(&mut points.iter().copied());
+    hull.extend(half(&mut points.iter().rev().copied()));
+    hull
+}
+
+/**
+    The area a GameObject occupies, centered on it and offset in local space.
+
+    Colliders on an object without a RigidBody never move, but still stop
+    bodies and fire `Touched` and `TouchEnded` with the other Collider.
+    Triggers only fire the events.
+
+    Each collider is on one of 32 layers and two colliders only touch when
+    the mask of each has the layer of the other.
+*/
+pub struct ColliderData {
+    pub shape: ColliderShape,
+    pub size: (f32, f32),
+    pub offset: (f32, f32),
+    pub points: Vec<Vector2>,
+    pub is_trigger: bool,
+    pub friction: f32,
+    pub restitution: f32,
+    pub layer: u32,
+    pub mask: u32,
+    pub touched: Signal,
+    pub touch_ended: Signal,
+}
+
+impl Default for ColliderData {
+    fn default() -> ColliderData {
+        ColliderData {
+            shape: ColliderShape::Box,
+            size: (100.0, 100.0),
+            offset: (0.0, 0.0),
+            points: Vec::new(),
+            is_trigger: false,
+            friction: 0.3,
+            restitution: 0.0,
+            layer: 0,
+            mask: u32::MAX,
+            touched: Signal::new(),
+            touch_ended: Signal::new(),
+        }
+    }
+}
+
+// a copy has the same shape, but the functions connected to the original stay with it
+impl Clone for ColliderData {
+    fn clone(&self) -> ColliderData {
+        ColliderData {
+            shape: self.shape,
+            size: self.size,
+            offset: self.offset,
+            points: self.points.clone(),
+            is_trigger: self.is_trigger,
+            friction: self.friction,
+            restitution: self.restitution,
+            layer: self.layer,
+            mask: self.mask,
+            ..ColliderData::default()
+        }
+    }
+}
+
+impl ColliderData {
+    // core points and radius in the local space of the object, see `Shape`
+    fn local_core(&self) -> (Vec<Vector2>, f32) {
+        let offset = Vector2::new(self.offset.0, self.offset.1);
+        let (half_width, half_height) = (self.size.0 / 2.0, self.size.1 / 2.0);
+
+        match self.shape {
+            ColliderShape::Box => {
+                let corners = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)];
+                let points = corners.iter().map(|&(x, y)| offset + Vector2::new(x * half_width, y * half_height)).collect();
+                (points, 0.0)
+            }
+            ColliderShape::Circle => (vec![offset], half_width.abs()),
+            ColliderShape::Capsule => {
+                let radius = half_width.abs();
+                let reach = (half_height.abs() - radius).max(0.0);
+                (vec![offset + Vector2::new(0.0, -reach), offset + Vector2::new(0.0, reach)], radius)
+            }
+            ColliderShape::Polygon => (self.points.iter().map(|&p| p + offset).collect(), 0.0),
+        }
+    }
+
+    /**
+        The collider placed in the world by the global matrix of its object,
+        `None` for a polygon without points.
+    */
+    pub fn world_shape(&self, matrix: &Matrix3) -> Option<Shape> {
+        let (points, radius) = self.local_core();
+        (!points.is_empty()).then(|| Shape::transformed(&points, radius, matrix))
+    }
+
+    // the layer as a bit, to check against masks
+    pub fn layer_bit(&self) -> u32 {
+        1 << self.layer
+    }
+}
+
+impl ComponentKind for ColliderData {
+    const NAME: &'static str = "Collider";
+
+    fn get_mut(component: &mut Component) -> Option<&mut Self> {
+        match component {
+            Component::Collider(data) => Some(data),
+            _ => None,
+        }
+    }
+}
+
+pub type Collider = ComponentHandle<ColliderData>;
+
+impl Collider {
+    // changes the collider and has physics queries see the change
+    fn set(&self, lua: &Lua, f: impl FnOnce(&mut ColliderData)) -> LuaResult<()> {
+        self.with(lua, f)?;
+        physics::invalidate_queries(lua);
+        Ok(())
+    }
+}
+
+impl LuaUserData for Collider {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        add_handle_fields(fields);
+
+        fields.add_field_method_get("Shape", |lua, this| this.with(lua, |c| c.shape.name()));
+        fields.add_field_method_get("Size", |lua, this| this.with(lua, |c| Vector2::new(c.size.0, c.size.1)));
+        fields.add_field_method_get("Offset", |lua, this| this.with(lua, |c| Vector2::new(c.offset.0, c.offset.1)));
+        fields.add_field_method_get("Points", |lua, this| this.with(lua, |c| c.points.clone()));
+        fields.add_field_method_get("IsTrigger", |lua, this| this.with(lua, |c| c.is_trigger));
+        fields.add_field_method_get("Friction", |lua, this| this.with(lua, |c| c.friction));
+        fields.add_field_method_get("Restitution", |lua, this| this.with(lua, |c| c.restitution));
+        fields.add_field_method_get("Layer", |lua, this| this.with(lua, |c| c.layer));
+        fields.add_field_method_get("Mask", |lua, this| this.with(lua, |c| c.mask));
+        fields.add_field_method_get("Touched", |lua, this| this.with(lua, |c| c.touched.clone()));
+        fields.add_field_method_get("TouchEnded", |lua, this| this.with(lua, |c| c.touch_ended.clone()));
+
+        fields.add_field_method_set("Shape", |lua, this, shape: String| {
+            let shape = ColliderShape::from_name(&shape)?;
+            this.set(lua, |c| c.shape = shape)
+        });
+        fields.add_field_method_set("Size", |lua, this, size: LuaUserDataRef<Vector2>| {
+            this.set(lua, |c| c.size = (size.get_x(), size.get_y()))
+        });
+        fields.add_field_method_set("Offset", |lua, this, offset: LuaUserDataRef<Vector2>| {
+            this.set(lua, |c| c.offset = (offset.get_x(), offset.get_y()))
+        });
+        // also turns the collider into a polygon
+        fields.add_field_method_set("Points", |lua, this, points: Vec<LuaUserDataRef<Vector2>>| {
+            let hull = convex_hull(points.iter().map(|p| **p).collect());
+            if hull.len() < 3 {
+                return Err(LuaError::RuntimeError("A polygon needs at least 3 points that aren't on one line".into()));
+            }
+            this.set(lua, |c| {
+                c.points = hull;
+                c.shape = ColliderShape::Polygon;
+            })
+        });
+        fields.add_field_method_set("IsTrigger", |lua, this, is_trigger: bool| this.set(lua, |c| c.is_trigger = is_trigger));
+        fields.add_field_method_set("Friction", |lua, this, friction: f32| {
+            if friction.is_nan() || friction < 0.0 {
+                return Err(LuaError::RuntimeError(format!("Friction must be 0 or greater, got {}", friction)));
+            }
+            this.set(lua, |c| c.friction = friction)
+        });
+        fields.add_field_method_set("Restitution", |lua, this, restitution: f32| {
+            if restitution.is_nan() || !(0.0..=1.0).contains(&restitution) {
+                return Err(LuaError::RuntimeError(format!("Restitution must be between 0 and 1, got {}", restitution)));
+            }
+            this.set(lua, |c| c.restitution = restitution)
+        });
+        fields.add_field_method_set("Layer", |lua, this, layer: u32| {
+            if layer > 31 {
+                return Err(LuaError::RuntimeError(format!("Layer must be between 0 and 31, got {}", layer)));
+            }
+            this.set(lua, |c| c.layer = layer)
+        });
+        // a bit for each layer to touch, all of them by default
+        fields.add_field_method_set("Mask", |lua, this, mask: u32| this.set(lua, |c| c.mask = mask));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        add_handle_methods(methods);
+    }
+}
@@ -0,0 +1,403 @@
+pub mod animator;
+pub mod audio_source;
+pub mod camera;
+pub mod collider;
+pub mod rigid_body;
+pub mod script;
+pub mod shape_renderer;
+pub mod sprite_renderer;
+
+use core::fmt;
+use std::marker::PhantomData;
+
+use mlua::prelude::*;
+
+use crate::assets::TextureRef;
+use crate::engine::gameobject::{scene_mut, GameObject, Scene};
+use crate::physics;
+use crate::render::{DrawCommand, TextureInfo};
+
+use animator::AnimatorData;
+use audio_source::AudioSourceData;
+use camera::CameraData;
+use collider::ColliderData;
+use rigid_body::RigidBodyData;
+use script::{ScriptComponent, ScriptRef};
+use shape_renderer::ShapeRendererData;
+use sprite_renderer::SpriteRendererData;
+
+/**
+    Behaviour attached to a GameObject, stored with the object in the scene arena.
+
+    Built-in components are plain Rust data handed to Luau as a [`ComponentHandle`],
+    script components are Luau tables created from a definition registered
+    with `Bee2D.registerComponent`.
+*/
+pub enum Component {
+    SpriteRenderer(SpriteRendererData),
+    ShapeRenderer(ShapeRendererData),
+    Camera(CameraData),
+    Collider(ColliderData),
+    RigidBody(RigidBodyData),
+    AudioSource(AudioSourceData),
+    Animator(AnimatorData),
+    Script(ScriptComponent),
+}
+
+impl Component {
+    fn built_in(type_name: &str) -> Option<Component> {
+        match type_name {
+            "SpriteRenderer" => Some(Component::SpriteRenderer(SpriteRendererData::default())),
+            "ShapeRenderer" => Some(Component::ShapeRenderer(ShapeRendererData::default())),
+            "Camera" => Some(Component::Camera(CameraData::default())),
+            "Collider" => Some(Component::Collider(ColliderData::default())),
+            "RigidBody" => Some(Component::RigidBody(RigidBodyData::default())),
+            "AudioSource" => Some(Component::AudioSource(AudioSourceData::default())),
+            "Animator" => Some(Component::Animator(AnimatorData::default())),
+            _ => None,
+        }
+    }
+
+    pub fn type_name(&self) -> &str {
+        match self {
+            Component::SpriteRenderer(_) => "SpriteRenderer",
+            Component::ShapeRenderer(_) => "ShapeRenderer",
+            Component::Camera(_) => "Camera",
+            Component::Collider(_) => "Collider",
+            Component::RigidBody(_) => "RigidBody",
+            Component::AudioSource(_) => "AudioSource",
+            Component::Animator(_) => "Animator",
+            Component::Script(script) => &script.name,
+        }
+    }
+
+    // script components can't be copied without the Lua VM, see `script::clone_instance`
+    pub fn clone_built_in(&self) -> Option<Component> {
+        match self {
+            Component::SpriteRenderer(data) => Some(Component::SpriteRenderer(data.clone())),
+            Component::ShapeRenderer(data) => Some(Component::ShapeRenderer(data.clone())),
+            Component::Camera(data) => Some(Component::Camera(data.clone())),
+            Component::Collider(data) => Some(Component::Collider(data.clone())),
+            Component::RigidBody(data) => Some(Component::RigidBody(data.clone())),
+            Component::AudioSource(data) => Some(Component::AudioSource(data.clone())),
+            Component::Animator(data) => Some(Component::Animator(data.clone())),
+            Component::Script(_) => None,
+        }
+    }
+
+    fn to_lua<'lua>(&self, lua: &'lua Lua, object: GameObject, id: u32) -> LuaResult<LuaValue<'lua>> {
+        match self {
+            Component::SpriteRenderer(_) => ComponentHandle::<SpriteRendererData>::new(object, id).into_lua(lua),
+            Component::ShapeRenderer(_) => ComponentHandle::<ShapeRendererData>::new(object, id).into_lua(lua),
+            Component::Camera(_) => ComponentHandle::<CameraData>::new(object, id).into_lua(lua),
+            Component::Collider(_) => ComponentHandle::<ColliderData>::new(object, id).into_lua(lua),
+            Component::RigidBody(_) => ComponentHandle::<RigidBodyData>::new(object, id).into_lua(lua),
+            Component::AudioSource(_) => ComponentHandle::<AudioSourceData>::new(object, id).into_lua(lua),
+            Component::Animator(_) => ComponentHandle::<AnimatorData>::new(object, id).into_lua(lua),
+            Component::Script(script) => lua.registry_value(&script.instance),
+        }
+    }
+}
+
+/**
+    Data of a built-in component, `NAME` is what scripts pass to `AddComponent`.
+*/
+pub trait ComponentKind: Sized + 'static {
+    const NAME: &'static str;
+
+    fn get_mut(component: &mut Component) -> Option<&mut Self>;
+}
+
+// handle to a built-in component stored in the `Scene` app data
+pub struct ComponentHandle<T> {
+    object: GameObject,
+    id: u32,
+    kind: PhantomData<T>,
+}
+
+impl<T> Clone for ComponentHandle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ComponentHandle<T> {}
+
+impl<T> PartialEq for ComponentHandle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.object == other.object && self.id == other.id
+    }
+}
+
+impl<T: ComponentKind> ComponentHandle<T> {
+    pub fn new(object: GameObject, id: u32) -> Self {
+        ComponentHandle { object, id, kind: PhantomData }
+    }
+
+    pub fn with<R>(&self, lua: &Lua, f: impl FnOnce(&mut T) -> R) -> LuaResult<R> {
+        scene_mut(lua)?
+            .component_mut(self.object, self.id)
+            .and_then(T::get_mut)
+            .map(f)
+            .ok_or_else(|| LuaError::RuntimeError(format!("{} has been removed", T::NAME)))
+    }
+}
+
+impl<T: ComponentKind> fmt::Display for ComponentHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", T::NAME)
+    }
+}
+
+// fields and metamethods every built-in component has
+fn add_handle_fields<'lua, T, F>(fields: &mut F)
+where
+    T: ComponentKind,
+    ComponentHandle<T>: LuaUserData,
+    F: LuaUserDataFields<'lua, ComponentHandle<T>>,
+{
+    fields.add_field_method_get("GameObject", |_, this| Ok(this.object));
+}
+
+fn add_handle_methods<'lua, T, M>(methods: &mut M)
+where
+    T: ComponentKind,
+    ComponentHandle<T>: LuaUserData,
+    M: LuaUserDataMethods<'lua, ComponentHandle<T>>,
+{
+    methods.add_meta_method(LuaMetaMethod::Eq, |_, this, other: LuaValue| {
+        Ok(matches!(other, LuaValue::UserData(other) if other.borrow::<ComponentHandle<T>>().is_ok_and(|other| *other == *this)))
+    });
+    methods.add_meta_method(LuaMetaMethod::ToString, |_, this, ()| Ok(this.to_string()));
+}
+
+// id of the component `value` refers to, if it is one of the components of `object`
+fn component_id(lua: &Lua, scene: &Scene, object: GameObject, value: &LuaValue) -> LuaResult<Option<u32>> {
+    fn handle_id<T: ComponentKind>(userdata: &LuaAnyUserData, object: GameObject) -> Option<u32>
+    where
+        ComponentHandle<T>: LuaUserData,
+    {
+        userdata.borrow::<ComponentHandle<T>>().ok()
+            .filter(|handle| handle.object == object)
+            .map(|handle| handle.id)
+    }
+
+    Ok(match value {
+        LuaValue::String(type_name) => {
+            let type_name = type_name.to_str()?;
+            scene.component_ids(object).into_iter()
+                .find(|&id| scene.component(object, id).is_some_and(|c| c.type_name() == type_name))
+        }
+        LuaValue::UserData(userdata) => handle_id::<SpriteRendererData>(userdata, object)
+            .or_else(|| handle_id::<ShapeRendererData>(userdata, object))
+            .or_else(|| handle_id::<CameraData>(userdata, object))
+            .or_else(|| handle_id::<ColliderData>(userdata, object))
+            .or_else(|| handle_id::<RigidBodyData>(userdata, object))
+            .or_else(|| handle_id::<AudioSourceData>(userdata, object))
+            .or_else(|| handle_id::<AnimatorData>(userdata, object)),
+        LuaValue::Table(table) => {
+            let mut found = None;
+            for id in scene.component_ids(object) {
+                if let Some(Component::Script(script)) = scene.component(object, id) {
+                    if lua.registry_value::<LuaTable>(&script.instance)? == *table {
+                        found = Some(id);
+                        break;
+                    }
+                }
+            }
+            found
+        }
+        _ => None,
+    })
+}
+
+/**
+    Creates a component of the given type on `object` and applies `props` to it,
+    as if every key of the table had been assigned on the component.
+*/
+pub fn add_component<'lua>(
+    lua: &'lua Lua,
+    object: GameObject,
+    type_name: &str,
+    props: Option<LuaTable<'lua>>,
+) -> LuaResult<LuaValue<'lua>> {
+    let component = match Component::built_in(type_name) {
+        Some(component) => component,
+        None => Component::Script(script::instantiate(lua, type_name, object)?),
+    };
+
+    let (id, value) = {
+        let mut scene = scene_mut(lua)?;
+        let id = scene.add_component(object, component)
+            .ok_or_else(|| LuaError::RuntimeError("GameObject has been destroyed".into()))?;
+        let component = scene.component(object, id).expect("component was just added");
+        (id, component.to_lua(lua, object, id)?)
+    };
+    physics::invalidate_queries(lua);
+
+    if let Some(props) = props {
+        // a component that couldn't be set up isn't left half-configured on the object
+        if let Err(err) = set_properties(lua, value.clone(), props) {
+            scene_mut(lua)?.remove_component(object, id);
+            return Err(err);
+        }
+    }
+
+    Ok(value)
+}
+
+// assigns through the component itself, so unknown or mistyped properties error like they would in Luau
+fn set_properties<'lua>(lua: &'lua Lua, component: LuaValue<'lua>, props: LuaTable<'lua>) -> LuaResult<()> {
+    const SET_PROPERTIES: &str = "local component, props = ...\nfor key, value in props do\n\tcomponent[key] = value\nend";
+
+    let set_properties: LuaFunction = match lua.named_registry_value("Bee2D.setProperties")? {
+        LuaValue::Function(function) => function,
+        _ => {
+            let function = lua.load(SET_PROPERTIES).set_name("setProperties").into_function()?;
+            lua.set_named_registry_value("Bee2D.setProperties", function.clone())?;
+            function
+        }
+    };
+
+    set_properties.call((component, props))
+}
+
+// the first component of the given type on `object`, or nil
+pub fn get_component<'lua>(lua: &'lua Lua, object: GameObject, type_name: &str) -> LuaResult<LuaValue<'lua>> {
+    let scene = scene_mut(lua)?;
+    let id = scene.component_ids(object).into_iter()
+        .find(|&id| scene.component(object, id).is_some_and(|c| c.type_name() == type_name));
+
+    match id.and_then(|id| scene.component(object, id).map(|c| (id, c))) {
+        Some((id, component)) => component.to_lua(lua, object, id),
+        None => Ok(LuaNil),
+    }
+}
+
+pub fn get_components(lua: &Lua, object: GameObject) -> LuaResult<Vec<LuaValue<'_>>> {
+    let scene = scene_mut(lua)?;
+    scene.component_ids(object).into_iter()
+        .filter_map(|id| scene.component(object, id).map(|component| component.to_lua(lua, object, id)))
+        .collect()
+}
+
+/**
+    Removes a component, given either the component itself or its type name,
+    calling `OnDestroy` if it is a script component.
+*/
+pub fn remove_component(lua: &Lua, object: GameObject, component: LuaValue) -> LuaResult<()> {
+    let removed = {
+        let mut scene = scene_mut(lua)?;
+        match component_id(lua, &scene, object, &component)? {
+            Some(id) => scene.remove_component(object, id),
+            None => None,
+        }
+    };
+    physics::invalidate_queries(lua);
+
+    if let Some(Component::Script(script)) = removed {
+        script::call_method(lua, &lua.registry_value(&script.instance)?, "OnDestroy", ())?;
+    }
+
+    Ok(())
+}
+
+// calls `OnDestroy` on the script components of objects that are about to be destroyed
+pub fn destroy_components(lua: &Lua, objects: &[GameObject]) -> LuaResult<()> {
+    for script in scripts(lua, objects)? {
+        script.call(lua, "OnDestroy", ())?;
+    }
+    Ok(())
+}
+
+// snapshot of the script components of `objects`, in order
+fn scripts<'lua>(lua: &'lua Lua, objects: &[GameObject]) -> LuaResult<Vec<ScriptRef<'lua>>> {
+    let scene = scene_mut(lua)?;
+    let mut scripts = Vec::new();
+
+    for &object in objects {
+        for id in scene.component_ids(object) {
+            if let Some(Component::Script(script)) = scene.component(object, id) {
+                scripts.push(ScriptRef::new(lua, object, id, script)?);
+            }
+        }
+    }
+
+    Ok(scripts)
+}
+
+// objects in the scene tree, parents before their children, detached objects don't run
+pub fn active_objects(lua: &Lua) -> LuaResult<Vec<GameObject>> {
+    let scene = scene_mut(lua)?;
+    let root = scene.root();
+    Ok([vec![root], scene.descendants(root)].concat())
+}
+
+/**
+    Advances Animators, then runs `Start` on script components that haven't
+    started yet and `Update(dt)` on all of them, in scene order.
+*/
+pub fn update(lua: &Lua, dt: f64) -> LuaResult<()> {
+    let objects = active_objects(lua)?;
+    animator::update(lua, &objects, dt)?;
+
+    for script in scripts(lua, &objects)? {
+        // an earlier script may have removed this one
+        if !script.is_attached(lua)? {
+            continue;
+        }
+
+        if script.start(lua)? {
+            script.call(lua, "Start", ())?;
+        }
+        script.call(lua, "Update", dt)?;
+    }
+
+    Ok(())
+}
+
+/**
+    Queues the built-in renderers and runs `Draw` on script components,
+    in scene order so that children are drawn over their parents.
+*/
+pub fn draw(lua: &Lua, queue_command: &mut dyn FnMut(DrawCommand), texture: &dyn Fn(&TextureRef) -> Option<TextureInfo>) -> LuaResult<()> {
+    for object in active_objects(lua)? {
+        let ids = scene_mut(lua)?.component_ids(object);
+
+        for id in ids {
+            let script = {
+                let scene = scene_mut(lua)?;
+                let Some(data) = scene.get(object) else {
+                    break;
+                };
+                let matrix = data.transform.cached_global_matrix();
+
+                match scene.component(object, id) {
+                    Some(Component::SpriteRenderer(sprite)) => {
+                        if let Some(command) = sprite.draw_command(matrix, texture) {
+                            queue_command(command);
+                        }
+                        if let Some(command) = animator::fade_command(&scene, object, id, matrix, texture) {
+                            queue_command(command);
+                        }
+                        None
+                    }
+                    Some(Component::ShapeRenderer(shape)) => {
+                        if let Some(command) = shape.draw_command(matrix) {
+                            queue_command(command);
+                        }
+                        None
+                    }
+                    Some(Component::Script(script)) => Some(ScriptRef::new(lua, object, id, script)?),
+                    _ => None,
+                }
+            };
+
+            if let Some(script) = script {
+                script.call(lua, "Draw", ())?;
+            }
+        }
+    }
+
+    Ok(())
+}
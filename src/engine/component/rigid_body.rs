@@ -0,0 +1,202 @@
+use mlua::prelude::*;
+
+use crate::engine::gameobject::scene_mut;
+use crate::math::Vector2;
+use crate::physics;
+
+use super::{add_handle_fields, add_handle_methods, Component, ComponentHandle, ComponentKind};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BodyType {
+    // moved by gravity, forces and collisions
+    Dynamic,
+    // moved only by its velocity, pushes dynamic bodies without being pushed back
+    Kinematic,
+    // never moves, like a collider without a body
+    Static,
+}
+
+impl BodyType {
+    fn name(self) -> &'static str {
+        match self {
+            BodyType::Dynamic => "Dynamic",
+            BodyType::Kinematic => "Kinematic",
+            BodyType::Static => "Static",
+        }
+    }
+
+    fn from_name(name: &str) -> LuaResult<BodyType> {
+        match name {
+            "Dynamic" => Ok(BodyType::Dynamic),
+            "Kinematic" => Ok(BodyType::Kinematic),
+            "Static" => Ok(BodyType::Static),
+            _ => Err(LuaError::RuntimeError(format!("'{}' is not a body type, expected Dynamic, Kinematic or Static", name))),
+        }
+    }
+}
+
+/**
+    Lets physics move a GameObject, colliding through the Colliders on the
+    same object. Bodies turn around the origin of their object.
+
+    Velocities are in pixels and radians per second, forces and torques
+    are applied over the next fixed update and then cleared.
+*/
+#[derive(Clone)]
+pub struct RigidBodyData {
+    pub body_type: BodyType,
+    pub mass: f32,
+    pub velocity: Vector2,
+    pub angular_velocity: f32,
+    pub gravity_scale: f32,
+    pub linear_damping: f32,
+    pub angular_damping: f32,
+    pub fixed_rotation: bool,
+    pub force: Vector2,
+    pub torque: f32,
+}
+
+impl Default for RigidBodyData {
+    fn default() -> RigidBodyData {
+        RigidBodyData {
+            body_type: BodyType::Dynamic,
+            mass: 1.0,
+            velocity: Vector2::new(0.0, 0.0),
+            angular_velocity: 0.0,
+            gravity_scale: 1.0,
+            linear_damping: 0.0,
+            angular_damping: 0.0,
+            fixed_rotation: false,
+            force: Vector2::new(0.0, 0.0),
+            torque: 0.0,
+        }
+    }
+}
+
+impl RigidBodyData {
+    // whether forces and impulses change its velocity
+    pub fn is_dynamic(&self) -> bool {
+        self.body_type == BodyType::Dynamic
+    }
+}
+
+impl ComponentKind for RigidBodyData {
+    const NAME: &'static str = "RigidBody";
+
+    fn get_mut(component: &mut Component) -> Option<&mut Self> {
+        match component {
+            Component::RigidBody(data) => Some(data),
+            _ => None,
+        }
+    }
+}
+
+pub type RigidBody = ComponentHandle<RigidBodyData>;
+
+impl RigidBody {
+    /**
+        Changes the velocity right away, as if `impulse` had been applied at
+        `point` in world space, which also spins the body if it is off center.
+    */
+    fn apply_impulse(&self, lua: &Lua, impulse: Vector2, point: Option<Vector2>) -> LuaResult<()> {
+        let mut scene = scene_mut(lua)?;
+        let (center, inverse_inertia) = physics::body_frame(&mut scene, self.object);
+
+        let body = scene.component_mut(self.object, self.id)
+            .and_then(RigidBodyData::get_mut)
+            .ok_or_else(|| LuaError::RuntimeError("RigidBody has been removed".into()))?;
+        if !body.is_dynamic() {
+            return Ok(());
+        }
+
+        body.velocity = body.velocity + impulse * (1.0 / body.mass);
+        if let Some(point) = point.filter(|_| !body.fixed_rotation) {
+            body.angular_velocity += (point - center).cross(impulse) * inverse_inertia * (1.0 / body.mass);
+        }
+        Ok(())
+    }
+}
+
+fn non_negative(name: &str, value: f32) -> LuaResult<f32> {
+    if value.is_nan() || value < 0.0 {
+        return Err(LuaError::RuntimeError(format!("{} must be 0 or greater, got {}", name, value)));
+    }
+    Ok(value)
+}
+
+impl LuaUserData for RigidBody {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        add_handle_fields(fields);
+
+        fields.add_field_method_get("BodyType", |lua, this| this.with(lua, |b| b.body_type.name()));
+        fields.add_field_method_get("Mass", |lua, this| this.with(lua, |b| b.mass));
+        fields.add_field_method_get("Velocity", |lua, this| this.with(lua, |b| b.velocity));
+        fields.add_field_method_get("AngularVelocity", |lua, this| this.with(lua, |b| b.angular_velocity));
+        fields.add_field_method_get("GravityScale", |lua, this| this.with(lua, |b| b.gravity_scale));
+        fields.add_field_method_get("LinearDamping", |lua, this| this.with(lua, |b| b.linear_damping));
+        fields.add_field_method_get("AngularDamping", |lua, this| this.with(lua, |b| b.angular_damping));
+        fields.add_field_method_get("FixedRotation", |lua, this| this.with(lua, |b| b.fixed_rotation));
+
+        fields.add_field_method_set("BodyType", |lua, this, body_type: String| {
+            let body_type = BodyType::from_name(&body_type)?;
+            this.with(lua, |b| b.body_type = body_type)
+        });
+        fields.add_field_method_set("Mass", |lua, this, mass: f32| {
+            if mass.is_nan() || mass <= 0.0 {
+                return Err(LuaError::RuntimeError(format!("Mass must be greater than 0, got {}", mass)));
+            }
+            this.with(lua, |b| b.mass = mass)
+        });
+        fields.add_field_method_set("Velocity", |lua, this, velocity: LuaUserDataRef<Vector2>| {
+            this.with(lua, |b| b.velocity = *velocity)
+        });
+        fields.add_field_method_set("AngularVelocity", |lua, this, angular_velocity: f32| {
+            this.with(lua, |b| b.angular_velocity = angular_velocity)
+        });
+        fields.add_field_method_set("GravityScale", |lua, this, gravity_scale: f32| {
+            this.with(lua, |b| b.gravity_scale = gravity_scale)
+        });
+        fields.add_field_method_set("LinearDamping", |lua, this, damping: f32| {
+            let damping = non_negative("LinearDamping", damping)?;
+            this.with(lua, |b| b.linear_damping = damping)
+        });
+        fields.add_field_method_set("AngularDamping", |lua, this, damping: f32| {
+            let damping = non_negative("AngularDamping", damping)?;
+            this.with(lua, |b| b.angular_damping = damping)
+        });
+        fields.add_field_method_set("FixedRotation", |lua, this, fixed_rotation: bool| {
+            this.with(lua, |b| {
+                b.fixed_rotation = fixed_rotation;
+                if fixed_rotation {
+                    b.angular_velocity = 0.0;
+                }
+            })
+        });
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        add_handle_methods(methods);
+
+        methods.add_method("ApplyForce", |lua, this, force: LuaUserDataRef<Vector2>| {
+            this.with(lua, |b| b.force = b.force + *force)
+        });
+        methods.add_method("ApplyTorque", |lua, this, torque: f32| {
+            this.with(lua, |b| b.torque += torque)
+        });
+        methods.add_method("ApplyImpulse", |lua, this, (impulse, point): (LuaUserDataRef<Vector2>, Option<LuaUserDataRef<Vector2>>)| {
+            this.apply_impulse(lua, *impulse, point.map(|point| *point))
+        });
+        methods.add_method("ApplyAngularImpulse", |lua, this, impulse: f32| {
+            let mut scene = scene_mut(lua)?;
+            let (_, inverse_inertia) = physics::body_frame(&mut scene, this.object);
+            let body = scene.component_mut(this.object, this.id)
+                .and_then(RigidBodyData::get_mut)
+                .ok_or_else(|| LuaError::RuntimeError("RigidBody has been removed".into()))?;
+
+            if body.is_dynamic() && !body.fixed_rotation {
+                body.angular_velocity += impulse * inverse_inertia * (1.0 / body.mass);
+            }
+            Ok(())
+        });
+    }
+}
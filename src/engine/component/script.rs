@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+use mlua::prelude::*;
+
+use crate::engine::gameobject::{scene_mut, GameObject};
+use crate::scheduler;
+
+use super::Component;
+
+/**
+    Component definitions registered from Luau with `Bee2D.registerComponent`.
+*/
+#[derive(Default)]
+pub struct ScriptComponents {
+    definitions: HashMap<String, LuaRegistryKey>,
+}
+
+/**
+    A Luau-defined component: a table whose metatable indexes into its definition,
+    so methods are shared and every instance has its own fields.
+*/
+pub struct ScriptComponent {
+    pub name: String,
+    pub instance: LuaRegistryKey,
+    started: bool,
+}
+
+pub fn register(lua: &Lua, (name, definition): (String, LuaTable)) -> LuaResult<()> {
+    if Component::built_in(&name).is_some() {
+        return Err(LuaError::RuntimeError(format!("{} is a built-in component and can't be redefined", name)));
+    }
+
+    let definition = lua.create_registry_value(definition)?;
+    lua.app_data_mut::<ScriptComponents>()
+        .ok_or_else(|| LuaError::RuntimeError("Bee2D has not been initialized".into()))?
+        .definitions
+        .insert(name, definition);
+    Ok(())
+}
+
+fn create_instance<'lua>(lua: &'lua Lua, name: &str, object: GameObject) -> LuaResult<LuaTable<'lua>> {
+    let definition: LuaTable = {
+        let components = lua.app_data_ref::<ScriptComponents>()
+            .ok_or_else(|| LuaError::RuntimeError("Bee2D has not been initialized".into()))?;
+        let key = components.definitions.get(name)
+            .ok_or_else(|| LuaError::RuntimeError(format!("{} is not a known component type", name)))?;
+        lua.registry_value(key)?
+    };
+
+    let metatable = lua.create_table()?;
+    metatable.set("__index", definition)?;
+
+    let instance = lua.create_table()?;
+    instance.set_metatable(Some(metatable));
+    instance.raw_set("GameObject", object)?;
+    Ok(instance)
+}
+
+pub fn instantiate(lua: &Lua, name: &str, object: GameObject) -> LuaResult<ScriptComponent> {
+    let instance = create_instance(lua, name, object)?;
+    Ok(ScriptComponent {
+        name: name.to_string(),
+        instance: lua.create_registry_value(instance)?,
+        started: false,
+    })
+}
+
+// a new instance for `object` with a shallow copy of the fields of `script`, it starts again
+pub fn clone_instance(lua: &Lua, script: &ScriptComponent, object: GameObject) -> LuaResult<ScriptComponent> {
+    let original: LuaTable = lua.registry_value(&script.instance)?;
+    let instance = create_instance(lua, &script.name, object)?;
+
+    for pair in original.pairs::<LuaValue, LuaValue>() {
+        let (key, value) = pair?;
+        if !matches!(&key, LuaValue::String(key) if key == "GameObject") {
+            instance.raw_set(key, value)?;
+        }
+    }
+
+    Ok(ScriptComponent {
+        name: script.name.clone(),
+        instance: lua.create_registry_value(instance)?,
+        started: false,
+    })
+}
+
+/**
+    Calls `instance:method(args)` in its own thread, if the component defines it.
+*/
+pub fn call_method<'lua>(lua: &'lua Lua, instance: &LuaTable<'lua>, method: &str, args: impl IntoLuaMulti<'lua>) -> LuaResult<()> {
+    let LuaValue::Function(function) = instance.get(method)? else {
+        return Ok(());
+    };
+
+    let mut args = args.into_lua_multi(lua)?;
+    args.push_front(LuaValue::Table(instance.clone()));
+    scheduler::spawn(lua, LuaValue::Function(function), args)?;
+    Ok(())
+}
+
+// a script component looked up without keeping the scene borrowed
+pub struct ScriptRef<'lua> {
+    object: GameObject,
+    id: u32,
+    pub instance: LuaTable<'lua>,
+}
+
+impl<'lua> ScriptRef<'lua> {
+    pub fn new(lua: &'lua Lua, object: GameObject, id: u32, script: &ScriptComponent) -> LuaResult<ScriptRef<'lua>> {
+        Ok(ScriptRef { object, id, instance: lua.registry_value(&script.instance)? })
+    }
+
+    fn with<R>(&self, lua: &Lua, f: impl FnOnce(&mut ScriptComponent) -> R) -> LuaResult<Option<R>> {
+        Ok(match scene_mut(lua)?.component_mut(self.object, self.id) {
+            Some(Component::Script(script)) => Some(f(script)),
+            _ => None,
+        })
+    }
+
+    // false once an earlier callback has removed the component or destroyed its object
+    pub fn is_attached(&self, lua: &Lua) -> LuaResult<bool> {
+        Ok(self.with(lua, |_| ())?.is_some())
+    }
+
+    // marks the component as started, true the first time only
+    pub fn start(&self, lua: &Lua) -> LuaResult<bool> {
+        Ok(self.with(lua, |script| !std::mem::replace(&mut script.started, true))?.unwrap_or(false))
+    }
+
+    pub fn call(&self, lua: &'lua Lua, method: &str, args: impl IntoLuaMulti<'lua>) -> LuaResult<()> {
+        call_method(lua, &self.instance, method, args)
+    }
+}
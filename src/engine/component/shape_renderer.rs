@@ -0,0 +1,118 @@
+use mlua::prelude::*;
+
+use crate::math::{Matrix3, Vector2};
+use crate::render::{shapes, DrawCommand, Rgba, Style};
+
+use super::{add_handle_fields, add_handle_methods, Component, ComponentHandle, ComponentKind};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shape {
+    Rectangle,
+    // the ellipse inscribed in the size of the renderer
+    Circle,
+}
+
+impl Shape {
+    fn name(self) -> &'static str {
+        match self {
+            Shape::Rectangle => "Rectangle",
+            Shape::Circle => "Circle",
+        }
+    }
+
+    fn from_name(name: &str) -> LuaResult<Shape> {
+        match name {
+            "Rectangle" => Ok(Shape::Rectangle),
+            "Circle" => Ok(Shape::Circle),
+            _ => Err(LuaError::RuntimeError(format!("'{}' is not a shape, expected Rectangle or Circle", name))),
+        }
+    }
+}
+
+/**
+    Draws a shape centered on its GameObject, transformed by its global matrix.
+*/
+#[derive(Clone)]
+pub struct ShapeRendererData {
+    pub shape: Shape,
+    pub size: (f32, f32),
+    pub color: Rgba,
+    // outlines are drawn instead of fills when set
+    pub thickness: Option<f32>,
+    pub visible: bool,
+}
+
+impl Default for ShapeRendererData {
+    fn default() -> ShapeRendererData {
+        ShapeRendererData {
+            shape: Shape::Rectangle,
+            size: (100.0, 100.0),
+            color: Rgba::WHITE,
+            thickness: None,
+            visible: true,
+        }
+    }
+}
+
+impl ShapeRendererData {
+    pub fn draw_command(&self, matrix: Matrix3) -> Option<DrawCommand> {
+        if !self.visible {
+            return None;
+        }
+
+        let (width, height) = self.size;
+        let outline = match self.shape {
+            Shape::Rectangle => shapes::rectangle(-width / 2.0, -height / 2.0, width, height),
+            Shape::Circle => shapes::ellipse((0.0, 0.0), width / 2.0, height / 2.0),
+        };
+
+        Some(DrawCommand::Polygon {
+            points: outline.into_iter().map(|(x, y)| matrix.transform_point(x, y)).collect(),
+            style: match self.thickness {
+                Some(thickness) => Style::Outline { thickness },
+                None => Style::Fill,
+            },
+            color: self.color,
+        })
+    }
+}
+
+impl ComponentKind for ShapeRendererData {
+    const NAME: &'static str = "ShapeRenderer";
+
+    fn get_mut(component: &mut Component) -> Option<&mut Self> {
+        match component {
+            Component::ShapeRenderer(data) => Some(data),
+            _ => None,
+        }
+    }
+}
+
+pub type ShapeRenderer = ComponentHandle<ShapeRendererData>;
+
+impl LuaUserData for ShapeRenderer {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        add_handle_fields(fields);
+
+        fields.add_field_method_get("Shape", |lua, this| this.with(lua, |s| s.shape.name()));
+        fields.add_field_method_get("Size", |lua, this| this.with(lua, |s| Vector2::new(s.size.0, s.size.1)));
+        fields.add_field_method_get("Color", |lua, this| this.with(lua, |s| s.color));
+        fields.add_field_method_get("Thickness", |lua, this| this.with(lua, |s| s.thickness));
+        fields.add_field_method_get("Visible", |lua, this| this.with(lua, |s| s.visible));
+
+        fields.add_field_method_set("Shape", |lua, this, shape: String| {
+            let shape = Shape::from_name(&shape)?;
+            this.with(lua, |s| s.shape = shape)
+        });
+        fields.add_field_method_set("Size", |lua, this, size: LuaUserDataRef<Vector2>| {
+            this.with(lua, |s| s.size = (size.get_x(), size.get_y()))
+        });
+        fields.add_field_method_set("Color", |lua, this, color: Rgba| this.with(lua, |s| s.color = color));
+        fields.add_field_method_set("Thickness", |lua, this, thickness: Option<f32>| this.with(lua, |s| s.thickness = thickness));
+        fields.add_field_method_set("Visible", |lua, this, visible: bool| this.with(lua, |s| s.visible = visible));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        add_handle_methods(methods);
+    }
+}
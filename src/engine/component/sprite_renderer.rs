@@ -0,0 +1,144 @@
+use mlua::prelude::*;
+
+use crate::assets::{SpriteRegion, TextureRef};
+use crate::math::{Matrix3, Vector2};
+use crate::render::{DrawCommand, Rgba, TextureInfo};
+
+use super::{add_handle_fields, add_handle_methods, Component, ComponentHandle, ComponentKind};
+
+/**
+    Draws a texture through the global matrix of its GameObject, so it follows
+    the position, rotation, scale and any shear inherited from its parents.
+
+    The pivot is the point of the texture that sits at the object's position,
+    from (0, 0) at the top left to (1, 1) at the bottom right. Flipping mirrors
+    the texture around the pivot.
+
+    The texture is a handle from `Bee2D.Assets` or the path of a loaded
+    texture, nothing is drawn until it has finished loading. A region of a
+    sprite sheet replaces both the texture and the source rectangle.
+*/
+#[derive(Clone)]
+pub struct SpriteRendererData {
+    pub texture: Option<TextureRef>,
+    pub color: Rgba,
+    pub visible: bool,
+    pub pivot: (f32, f32),
+    pub flip_x: bool,
+    pub flip_y: bool,
+    // part of the texture to draw in pixels, the whole texture when there's no size
+    pub source_offset: (f32, f32),
+    pub source_size: Option<(f32, f32)>,
+    pub region: Option<SpriteRegion>,
+}
+
+impl Default for SpriteRendererData {
+    fn default() -> SpriteRendererData {
+        SpriteRendererData {
+            texture: None,
+            color: Rgba::WHITE,
+            visible: true,
+            pivot: (0.5, 0.5),
+            flip_x: false,
+            flip_y: false,
+            source_offset: (0.0, 0.0),
+            source_size: None,
+            region: None,
+        }
+    }
+}
+
+impl SpriteRendererData {
+    pub fn draw_command(&self, matrix: Matrix3, texture: &dyn Fn(&TextureRef) -> Option<TextureInfo>) -> Option<DrawCommand> {
+        if !self.visible {
+            return None;
+        }
+
+        if let Some(region) = &self.region {
+            let texture = texture(region.texture())?;
+            let (width, height) = region.size();
+            return Some(region.draw_command(texture, matrix * self.pivot_transform(width, height), self.color));
+        }
+
+        let texture = texture(self.texture.as_ref()?)?;
+        let (x, y) = self.source_offset;
+        let (width, height) = self.source_size
+            .unwrap_or((texture.width as f32 - x, texture.height as f32 - y));
+        if width <= 0.0 || height <= 0.0 {
+            return None;
+        }
+
+        Some(DrawCommand::Texture {
+            texture: texture.id,
+            source: (x, y, width, height),
+            transform: matrix * self.pivot_transform(width, height),
+            color: self.color,
+        })
+    }
+
+    // moves the pivot of a `width` by `height` image to the origin and flips it around there
+    fn pivot_transform(&self, width: f32, height: f32) -> Matrix3 {
+        let flip = Matrix3::scaling(
+            if self.flip_x { -1.0 } else { 1.0 },
+            if self.flip_y { -1.0 } else { 1.0 },
+        );
+        let pivot = Matrix3::translation(-self.pivot.0 * width, -self.pivot.1 * height);
+
+        flip * pivot
+    }
+}
+
+impl ComponentKind for SpriteRendererData {
+    const NAME: &'static str = "SpriteRenderer";
+
+    fn get_mut(component: &mut Component) -> Option<&mut Self> {
+        match component {
+            Component::SpriteRenderer(data) => Some(data),
+            _ => None,
+        }
+    }
+}
+
+pub type SpriteRenderer = ComponentHandle<SpriteRendererData>;
+
+impl LuaUserData for SpriteRenderer {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        add_handle_fields(fields);
+
+        fields.add_field_method_get("Texture", |lua, this| this.with(lua, |s| s.texture.clone()));
+        fields.add_field_method_get("Color", |lua, this| this.with(lua, |s| s.color));
+        fields.add_field_method_get("Visible", |lua, this| this.with(lua, |s| s.visible));
+        fields.add_field_method_get("Pivot", |lua, this| this.with(lua, |s| Vector2::new(s.pivot.0, s.pivot.1)));
+        fields.add_field_method_get("FlipX", |lua, this| this.with(lua, |s| s.flip_x));
+        fields.add_field_method_get("FlipY", |lua, this| this.with(lua, |s| s.flip_y));
+        fields.add_field_method_get("SourceOffset", |lua, this| {
+            this.with(lua, |s| Vector2::new(s.source_offset.0, s.source_offset.1))
+        });
+        fields.add_field_method_get("SourceSize", |lua, this| {
+            this.with(lua, |s| s.source_size.map(|(width, height)| Vector2::new(width, height)))
+        });
+        fields.add_field_method_get("Region", |lua, this| this.with(lua, |s| s.region.clone()));
+
+        fields.add_field_method_set("Texture", |lua, this, texture: Option<TextureRef>| this.with(lua, |s| s.texture = texture));
+        fields.add_field_method_set("Color", |lua, this, color: Rgba| this.with(lua, |s| s.color = color));
+        fields.add_field_method_set("Visible", |lua, this, visible: bool| this.with(lua, |s| s.visible = visible));
+        fields.add_field_method_set("Pivot", |lua, this, v: LuaUserDataRef<Vector2>| {
+            this.with(lua, |s| s.pivot = (v.get_x(), v.get_y()))
+        });
+        fields.add_field_method_set("FlipX", |lua, this, flip: bool| this.with(lua, |s| s.flip_x = flip));
+        fields.add_field_method_set("FlipY", |lua, this, flip: bool| this.with(lua, |s| s.flip_y = flip));
+        fields.add_field_method_set("SourceOffset", |lua, this, v: LuaUserDataRef<Vector2>| {
+            this.with(lua, |s| s.source_offset = (v.get_x(), v.get_y()))
+        });
+        fields.add_field_method_set("SourceSize", |lua, this, v: Option<LuaUserDataRef<Vector2>>| {
+            this.with(lua, |s| s.source_size = v.map(|v| (v.get_x(), v.get_y())))
+        });
+        fields.add_field_method_set("Region", |lua, this, region: Option<LuaUserDataRef<SpriteRegion>>| {
+            this.with(lua, |s| s.region = region.map(|region| region.clone()))
+        });
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        add_handle_methods(methods);
+    }
+}
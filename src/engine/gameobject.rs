@@ -1,56 +1,446 @@
-use core::fmt;
-use std::ops;
+use std::collections::HashMap;
 
 use mlua::prelude::*;
-use crate::lune::table_builder::*;
 use crate::lune::exports::*;
+use crate::lune::signal::Signal;
+use crate::lune::table_builder::*;
 use crate::lune::userdata::*;
 
-use crate::engine::transform::Transform;
+use crate::engine::component::{self, script, Component};
+use crate::engine::transform::{self, Transform, TransformData};
+use crate::physics;
+
+pub struct GameObjectData {
+    pub name: String,
+    pub transform: TransformData,
+    parent: Option<u32>,
+    children: Vec<u32>,
+    components: Vec<(u32, Component)>,
+    child_added: Signal,
+    child_removed: Signal,
+}
+
+impl GameObjectData {
+    fn new(name: String, transform: TransformData) -> GameObjectData {
+        GameObjectData {
+            name,
+            transform,
+            parent: None,
+            children: Vec::new(),
+            components: Vec::new(),
+            child_added: Signal::new(),
+            child_removed: Signal::new(),
+        }
+    }
+}
+
+/**
+    Every `GameObject` lives here, Luau only ever holds handles into it, so
+    two handles to the same object always see the same name, parent and children.
+
+    Objects stay alive until they are destroyed, whether or not they are
+    parented anywhere under the scene root.
+*/
+pub struct Scene {
+    next_id: u32,
+    next_component_id: u32,
+    root: u32,
+    objects: HashMap<u32, GameObjectData>,
+}
+
+impl Default for Scene {
+    fn default() -> Scene {
+        Scene::new()
+    }
+}
+
+impl Scene {
+    pub fn new() -> Scene {
+        let mut scene = Scene {
+            next_id: 0,
+            next_component_id: 0,
+            root: 0,
+            objects: HashMap::new(),
+        };
+        scene.root = scene.insert(GameObjectData::new("Scene".into(), TransformData::new())).id;
+        scene
+    }
+
+    pub fn root(&self) -> GameObject {
+        GameObject { id: self.root }
+    }
+
+    fn insert(&mut self, data: GameObjectData) -> GameObject {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.objects.insert(id, data);
+        GameObject { id }
+    }
+
+    pub fn get(&self, object: GameObject) -> Option<&GameObjectData> {
+        self.objects.get(&object.id)
+    }
+
+    pub fn get_mut(&mut self, object: GameObject) -> Option<&mut GameObjectData> {
+        self.objects.get_mut(&object.id)
+    }
 
-use std::rc::Rc;
-use std::cell::RefCell;
+    pub fn parent(&self, object: GameObject) -> Option<GameObject> {
+        self.get(object)?.parent.map(|id| GameObject { id })
+    }
 
+    // objects without a parent, the scene root among them
+    pub fn roots(&self) -> Vec<GameObject> {
+        self.objects.iter()
+            .filter(|(_, data)| data.parent.is_none())
+            .map(|(&id, _)| GameObject { id })
+            .collect()
+    }
+
+    pub fn children(&self, object: GameObject) -> Vec<GameObject> {
+        self.get(object)
+            .map(|data| data.children.iter().map(|&id| GameObject { id }).collect())
+            .unwrap_or_default()
+    }
 
+    // depth-first, every object comes before its own descendants
+    pub fn descendants(&self, object: GameObject) -> Vec<GameObject> {
+        let mut descendants = Vec::new();
+        let mut stack: Vec<GameObject> = self.children(object).into_iter().rev().collect();
+
+        while let Some(next) = stack.pop() {
+            descendants.push(next);
+            stack.extend(self.children(next).into_iter().rev());
+        }
+
+        descendants
+    }
+
+    pub fn is_descendant_of(&self, object: GameObject, ancestor: GameObject) -> bool {
+        let mut current = self.get(object).and_then(|data| data.parent);
+        while let Some(id) = current {
+            if id == ancestor.id {
+                return true;
+            }
+            current = self.objects.get(&id).and_then(|data| data.parent);
+        }
+        false
+    }
+
+    fn detach(&mut self, object: GameObject) -> Option<GameObject> {
+        let parent = self.get_mut(object)?.parent.take()?;
+        if let Some(data) = self.objects.get_mut(&parent) {
+            data.children.retain(|&id| id != object.id);
+        }
+        Some(GameObject { id: parent })
+    }
+
+    fn attach(&mut self, object: GameObject, parent: GameObject) {
+        if let Some(data) = self.get_mut(object) {
+            data.parent = Some(parent.id);
+        }
+        if let Some(data) = self.get_mut(parent) {
+            data.children.push(object.id);
+        }
+    }
+
+    pub fn add_component(&mut self, object: GameObject, component: Component) -> Option<u32> {
+        let id = self.next_component_id;
+        let data = self.objects.get_mut(&object.id)?;
+        data.components.push((id, component));
+        self.next_component_id += 1;
+        Some(id)
+    }
+
+    pub fn remove_component(&mut self, object: GameObject, id: u32) -> Option<Component> {
+        let components = &mut self.get_mut(object)?.components;
+        let index = components.iter().position(|(component_id, _)| *component_id == id)?;
+        Some(components.remove(index).1)
+    }
+
+    pub fn component(&self, object: GameObject, id: u32) -> Option<&Component> {
+        self.get(object)?.components.iter()
+            .find(|(component_id, _)| *component_id == id)
+            .map(|(_, component)| component)
+    }
+
+    pub fn component_mut(&mut self, object: GameObject, id: u32) -> Option<&mut Component> {
+        self.get_mut(object)?.components.iter_mut()
+            .find(|(component_id, _)| *component_id == id)
+            .map(|(_, component)| component)
+    }
+
+    // in the order they were added
+    pub fn component_ids(&self, object: GameObject) -> Vec<u32> {
+        self.get(object)
+            .map(|data| data.components.iter().map(|(id, _)| *id).collect())
+            .unwrap_or_default()
+    }
+
+    /**
+        Copies the object and all of its descendants, the copy has no parent.
+
+        Returns every original object paired with its copy, the copied root
+        first. Script components are left for the caller to copy.
+    */
+    fn clone_tree(&mut self, object: GameObject) -> Vec<(GameObject, GameObject)> {
+        let Some(data) = self.get(object) else {
+            return Vec::new();
+        };
+
+        let copy = GameObjectData::new(data.name.clone(), data.transform.clone());
+        let components: Vec<Component> = data.components.iter()
+            .filter_map(|(_, component)| component.clone_built_in())
+            .collect();
+
+        let copy = self.insert(copy);
+        transform::mark_dirty(self, copy);
+        for component in components {
+            self.add_component(copy, component);
+        }
+
+        let mut copies = vec![(object, copy)];
+        for child in self.children(object) {
+            let child_copies = self.clone_tree(child);
+            if let Some(&(_, child_copy)) = child_copies.first() {
+                self.attach(child_copy, copy);
+            }
+            copies.extend(child_copies);
+        }
+
+        copies
+    }
+}
 
+pub(crate) fn scene_mut(lua: &Lua) -> LuaResult<mlua::AppDataRefMut<'_, Scene>> {
+    lua.app_data_mut::<Scene>()
+        .ok_or_else(|| LuaError::RuntimeError("Bee2D has not been initialized".into()))
+}
 
+// handle to an object stored in the `Scene` app data, ordered by creation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct GameObject {
-    pub name: String,
-    pub transform: Transform,
-    
+    id: u32,
+}
+
+impl GameObject {
+    fn with<R>(&self, lua: &Lua, f: impl FnOnce(&mut GameObjectData) -> R) -> LuaResult<R> {
+        scene_mut(lua)?
+            .get_mut(*self)
+            .map(f)
+            .ok_or_else(|| LuaError::RuntimeError("GameObject has been destroyed".into()))
+    }
+
+    /**
+        Moves the object under `parent`, or out of the tree when `parent` is nil,
+        then fires `ChildRemoved` on the old parent and `ChildAdded` on the new one.
+    */
+    pub fn set_parent(&self, lua: &Lua, parent: Option<GameObject>) -> LuaResult<()> {
+        let (removed_from, added_to) = {
+            let mut scene = scene_mut(lua)?;
+            let data = scene.get(*self)
+                .ok_or_else(|| LuaError::RuntimeError("GameObject has been destroyed".into()))?;
+            let name = data.name.clone();
+
+            if data.parent == parent.map(|parent| parent.id) {
+                return Ok(());
+            }
+
+            if *self == scene.root() {
+                return Err(LuaError::RuntimeError("the parent of the scene root can't be changed".into()));
+            }
+
+            if let Some(parent) = parent {
+                let parent_data = scene.get(parent)
+                    .ok_or_else(|| LuaError::RuntimeError("new parent has been destroyed".into()))?;
+
+                if parent == *self || scene.is_descendant_of(parent, *self) {
+                    return Err(LuaError::RuntimeError(format!(
+                        "attempt to set parent of {} to {} would result in a circular reference",
+                        name, parent_data.name
+                    )));
+                }
+            }
+
+            let removed_from = scene.detach(*self);
+            if let Some(parent) = parent {
+                scene.attach(*self, parent);
+            }
+            transform::mark_dirty(&mut scene, *self);
+            physics::invalidate_queries(lua);
+
+            let signal = |object: Option<GameObject>, f: fn(&GameObjectData) -> &Signal| {
+                object.and_then(|object| scene.get(object)).map(|data| f(data).clone())
+            };
+            (signal(removed_from, |data| &data.child_removed), signal(parent, |data| &data.child_added))
+        };
+
+        if let Some(signal) = removed_from {
+            signal.fire(lua, *self)?;
+        }
+        if let Some(signal) = added_to {
+            signal.fire(lua, *self)?;
+        }
 
+        Ok(())
+    }
+
+    pub fn destroy(&self, lua: &Lua) -> LuaResult<()> {
+        let objects = {
+            let scene = scene_mut(lua)?;
+            if *self == scene.root() {
+                return Err(LuaError::RuntimeError("the scene root can't be destroyed".into()));
+            }
+            if scene.get(*self).is_none() {
+                return Ok(());
+            }
+            [vec![*self], scene.descendants(*self)].concat()
+        };
+
+        component::destroy_components(lua, &objects)?;
+        self.set_parent(lua, None)?;
+
+        let mut scene = scene_mut(lua)?;
+        for object in scene.descendants(*self) {
+            scene.objects.remove(&object.id);
+        }
+        scene.objects.remove(&self.id);
+
+        Ok(())
+    }
 }
 
 impl LuaExportsTable<'_> for GameObject {
     const EXPORT_NAME: &'static str = "GameObject";
 
-    fn create_exports_table(lua: &Lua) -> LuaResult<LuaTable> {
-        let gameobject_new = lua.create_function(|lua_ctx, name: String| {
-            let gameobject = GameObject {
-                name: name,
-                transform: Transform::new()
-            };
+    fn create_exports_table(lua: &Lua) -> LuaResult<LuaTable<'_>> {
+        let gameobject_new = |lua, (name, parent): (Option<String>, Option<GameObject>)| {
+            let data = GameObjectData::new(name.unwrap_or_else(|| "GameObject".into()), TransformData::new());
+            let object = scene_mut(lua)?.insert(data);
 
-            Ok(gameobject)
-        })?;
+            if parent.is_some() {
+                object.set_parent(lua, parent)?;
+            }
 
-        let exports = lua.create_table()?;
-        exports.set("new", gameobject_new)?;
+            Ok(object)
+        };
 
-        Ok(exports)
+        TableBuilder::new(lua)?
+            .with_function("new", gameobject_new)?
+            .build_readonly()
     }
 }
 
-impl LuaUserData for GameObject {
+impl<'lua> FromLua<'lua> for GameObject {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<GameObject> {
+        if let LuaValue::UserData(userdata) = &value {
+            if let Ok(object) = userdata.borrow::<GameObject>() {
+                return Ok(*object);
+            }
+        }
+
+        Err(LuaError::FromLuaConversionError {
+            from: value.type_name(),
+            to: "GameObject",
+            message: Some(format!("Expected a GameObject, got {}", value.type_name())),
+        })
+    }
+}
 
+impl LuaUserData for GameObject {
     fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
-        fields.add_field_method_get("Name", |_, this| Ok(this.name.clone()));
-        fields.add_field_method_set("Name", |_, this, name: String| {
-            this.name = name;
-            Ok(())
+        fields.add_field_method_get("Name", |lua, this| this.with(lua, |data| data.name.clone()));
+        fields.add_field_method_set("Name", |lua, this, name: String| this.with(lua, |data| data.name = name));
+
+        fields.add_field_method_get("Parent", |lua, this| {
+            this.with(lua, |data| data.parent.map(|id| GameObject { id }))
         });
+        fields.add_field_method_set("Parent", |lua, this, parent: Option<GameObject>| this.set_parent(lua, parent));
+
+        fields.add_field_method_get("ChildAdded", |lua, this| this.with(lua, |data| data.child_added.clone()));
+        fields.add_field_method_get("ChildRemoved", |lua, this| this.with(lua, |data| data.child_removed.clone()));
 
-        fields.add_field_method_get("Transform", |_, this| Ok(this.transform.clone()));
+        fields.add_field_method_get("Transform", |lua, this| this.with(lua, |_| Transform::of(*this)));
     }
 
-}
\ No newline at end of file
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("GetChildren", |lua, this, ()| {
+            this.with(lua, |_| ())?;
+            Ok(scene_mut(lua)?.children(*this))
+        });
+
+        methods.add_method("GetDescendants", |lua, this, ()| {
+            this.with(lua, |_| ())?;
+            Ok(scene_mut(lua)?.descendants(*this))
+        });
+
+        methods.add_method("FindFirstChild", |lua, this, (name, recursive): (String, Option<bool>)| {
+            this.with(lua, |_| ())?;
+            let scene = scene_mut(lua)?;
+            let candidates = if recursive.unwrap_or(false) {
+                scene.descendants(*this)
+            } else {
+                scene.children(*this)
+            };
+
+            Ok(candidates.into_iter().find(|&object| scene.get(object).is_some_and(|data| data.name == name)))
+        });
+
+        methods.add_method("IsDescendantOf", |lua, this, ancestor: GameObject| {
+            this.with(lua, |_| ())?;
+            Ok(scene_mut(lua)?.is_descendant_of(*this, ancestor))
+        });
+
+        methods.add_method("Clone", |lua, this, ()| {
+            let copies = scene_mut(lua)?.clone_tree(*this);
+            let &(_, copy) = copies.first()
+                .ok_or_else(|| LuaError::RuntimeError("GameObject has been destroyed".into()))?;
+
+            for (original, object_copy) in copies {
+                let scripts = {
+                    let scene = scene_mut(lua)?;
+                    scene.component_ids(original).into_iter()
+                        .filter_map(|id| match scene.component(original, id) {
+                            Some(Component::Script(script)) => Some(script::clone_instance(lua, script, object_copy)),
+                            _ => None,
+                        })
+                        .collect::<LuaResult<Vec<_>>>()?
+                };
+
+                let mut scene = scene_mut(lua)?;
+                for script in scripts {
+                    scene.add_component(object_copy, Component::Script(script));
+                }
+            }
+
+            Ok(copy)
+        });
+
+        methods.add_method("AddComponent", |lua, this, (type_name, props): (String, Option<LuaTable>)| {
+            component::add_component(lua, *this, &type_name, props)
+        });
+
+        methods.add_method("GetComponent", |lua, this, type_name: String| {
+            this.with(lua, |_| ())?;
+            component::get_component(lua, *this, &type_name)
+        });
+
+        methods.add_method("GetComponents", |lua, this, ()| {
+            this.with(lua, |_| ())?;
+            component::get_components(lua, *this)
+        });
+
+        methods.add_method("RemoveComponent", |lua, this, component: LuaValue| {
+            this.with(lua, |_| ())?;
+            component::remove_component(lua, *this, component)
+        });
+
+        methods.add_method("Destroy", |lua, this, ()| this.destroy(lua));
+
+        methods.add_meta_method(LuaMetaMethod::Eq, userdata_impl_eq);
+        methods.add_meta_method(LuaMetaMethod::ToString, |lua, this, ()| {
+            // destroyed objects still need to print, so this can't fail
+            Ok(this.with(lua, |data| data.name.clone()).unwrap_or_else(|_| "GameObject (destroyed)".into()))
+        });
+    }
+}
@@ -1,24 +1,37 @@
+pub mod camera;
+pub use camera::{Camera2D, Cameras};
+
+pub mod component;
+
 pub mod gameobject;
-pub use gameobject::GameObject;
+pub use gameobject::{GameObject, Scene};
 
 pub mod transform;
 pub use transform::Transform;
 
+pub mod sprite;
+pub use sprite::{SpriteData, Sprites};
+
 
 use mlua::prelude::*;
 
 use crate::lune::table_builder::TableBuilder;
 use crate::lune::exports::export;
+use crate::lune::signal::Signal;
+use crate::assets::SpriteSheet;
 
-fn create_all_exports(lua: &Lua) -> LuaResult<Vec<(&'static str, LuaValue)>> {
+fn create_all_exports(lua: &Lua) -> LuaResult<Vec<(&'static str, LuaValue<'_>)>> {
 
     Ok(vec![
         export::<GameObject>(lua)?,
         export::<Transform>(lua)?,
+        export::<Camera2D>(lua)?,
+        export::<Signal>(lua)?,
+        export::<SpriteSheet>(lua)?,
     ])
 }
 
-pub fn module(lua: &Lua) -> LuaResult<LuaTable> {
+pub fn module(lua: &Lua) -> LuaResult<LuaTable<'_>> {
     let exports = create_all_exports(lua)?;
     TableBuilder::new(lua)?
         .with_values(exports)?
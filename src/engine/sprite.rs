@@ -0,0 +1,98 @@
+use core::fmt;
+use std::collections::BTreeMap;
+
+use mlua::prelude::*;
+use crate::lune::userdata::*;
+
+use crate::assets::TextureRef;
+use crate::render::Rgba;
+
+pub struct SpriteData {
+    pub texture: TextureRef,
+    pub x: f32,
+    pub y: f32,
+    pub rotation: f32,
+    pub scale: f32,
+    pub color: Rgba,
+    pub visible: bool,
+}
+
+/**
+    Retained-mode sprites created through `Bee2D.createSprite`.
+
+    Unlike the immediate draw calls, these are drawn every frame, in creation
+    order, until they are destroyed.
+*/
+#[derive(Default)]
+pub struct Sprites {
+    next_id: u32,
+    sprites: BTreeMap<u32, SpriteData>,
+}
+
+impl Sprites {
+    pub fn insert(&mut self, data: SpriteData) -> Sprite {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.sprites.insert(id, data);
+        Sprite { id }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &SpriteData> {
+        self.sprites.values()
+    }
+}
+
+// handle to a sprite stored in the `Sprites` app data
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprite {
+    id: u32,
+}
+
+impl Sprite {
+    fn with<R>(&self, lua: &Lua, f: impl FnOnce(&mut SpriteData) -> R) -> LuaResult<R> {
+        let mut sprites = lua.app_data_mut::<Sprites>()
+            .ok_or_else(|| LuaError::RuntimeError("Bee2D has not been initialized".into()))?;
+
+        sprites.sprites.get_mut(&self.id)
+            .map(f)
+            .ok_or_else(|| LuaError::RuntimeError("sprite has been destroyed".into()))
+    }
+}
+
+impl LuaUserData for Sprite {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_field_method_get("Texture", |lua, this| this.with(lua, |s| s.texture.clone()));
+        fields.add_field_method_get("X", |lua, this| this.with(lua, |s| s.x));
+        fields.add_field_method_get("Y", |lua, this| this.with(lua, |s| s.y));
+        fields.add_field_method_get("Rotation", |lua, this| this.with(lua, |s| s.rotation));
+        fields.add_field_method_get("Scale", |lua, this| this.with(lua, |s| s.scale));
+        fields.add_field_method_get("Color", |lua, this| this.with(lua, |s| s.color));
+        fields.add_field_method_get("Visible", |lua, this| this.with(lua, |s| s.visible));
+
+        fields.add_field_method_set("Texture", |lua, this, texture: TextureRef| this.with(lua, |s| s.texture = texture));
+        fields.add_field_method_set("X", |lua, this, x: f32| this.with(lua, |s| s.x = x));
+        fields.add_field_method_set("Y", |lua, this, y: f32| this.with(lua, |s| s.y = y));
+        fields.add_field_method_set("Rotation", |lua, this, rotation: f32| this.with(lua, |s| s.rotation = rotation));
+        fields.add_field_method_set("Scale", |lua, this, scale: f32| this.with(lua, |s| s.scale = scale));
+        fields.add_field_method_set("Color", |lua, this, color: Rgba| this.with(lua, |s| s.color = color));
+        fields.add_field_method_set("Visible", |lua, this, visible: bool| this.with(lua, |s| s.visible = visible));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("Destroy", |lua, this, ()| {
+            if let Some(mut sprites) = lua.app_data_mut::<Sprites>() {
+                sprites.sprites.remove(&this.id);
+            }
+            Ok(())
+        });
+
+        methods.add_meta_method(LuaMetaMethod::Eq, userdata_impl_eq);
+        methods.add_meta_method(LuaMetaMethod::ToString, userdata_impl_to_string);
+    }
+}
+
+impl fmt::Display for Sprite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Sprite {}", self.id)
+    }
+}
@@ -1,53 +1,172 @@
-use core::fmt;
-use std::ops;
-
-use std::rc::Rc;
-use std::cell::RefCell;
-
 use mlua::prelude::*;
 use crate::lune::table_builder::*;
 use crate::lune::exports::*;
 use crate::lune::userdata::*;
 
+use crate::engine::gameobject::{scene_mut, GameObject, Scene};
 use crate::math::matrix3::Matrix3;
 use crate::math::vector2::Vector2;
-
-use crate::engine::gameobject::GameObject;
+use crate::physics;
 
 #[derive(Clone)]
-pub struct Transform {
-    parent: Option<Rc<RefCell<Transform>>>,
-    children: Vec<Rc<RefCell<Transform>>>,
+pub struct TransformData {
     local_matrix: Matrix3,
     global_matrix: Matrix3,
     local_rotation: Matrix3,
     local_rotation_angle: f32,
     local_translation: Matrix3,
     local_scale: Matrix3,
+    // set when the global matrix is stale, which is then also true of every descendant
+    dirty: bool,
 }
 
-impl Transform {
-    pub fn new() -> Transform {
-        Transform {
-            parent: None,
-            children: Vec::new(),
+impl TransformData {
+    pub fn new() -> TransformData {
+        TransformData {
             local_matrix: Matrix3::identity(),
             global_matrix: Matrix3::identity(),
             local_rotation: Matrix3::identity(),
             local_rotation_angle: 0.0,
             local_translation: Matrix3::identity(),
             local_scale: Matrix3::identity(),
+            dirty: false,
         }
     }
 
-    pub fn update_transform(&mut self) {
-        self.local_matrix = self.local_rotation * self.local_translation * self.local_scale;
+    fn update_local_matrix(&mut self) {
+        self.local_matrix = self.local_translation * self.local_rotation * self.local_scale;
+    }
 
-        if let Some(parent) = &self.parent {
-            self.global_matrix = parent.borrow().global_matrix * self.local_matrix;
-        } else {
-            self.global_matrix = self.local_matrix;
-        }
+    // only up to date once `update_transforms` has run for the frame
+    pub fn cached_global_matrix(&self) -> Matrix3 {
+        self.global_matrix
+    }
+}
+
+impl Default for TransformData {
+    fn default() -> TransformData {
+        TransformData::new()
+    }
+}
+
+/**
+    Marks the global matrix of `object` and all of its descendants as stale.
+
+    Descendants of an object that is already dirty are dirty too, so this stops there.
+*/
+pub fn mark_dirty(scene: &mut Scene, object: GameObject) {
+    let Some(data) = scene.get_mut(object) else {
+        return;
+    };
+    if data.transform.dirty {
+        return;
+    }
+    data.transform.dirty = true;
+
+    for child in scene.children(object) {
+        mark_dirty(scene, child);
+    }
+}
+
+/**
+    The global matrix of `object`, recomputing it and any stale ancestors first.
+*/
+pub fn global_matrix(scene: &mut Scene, object: GameObject) -> Matrix3 {
+    let Some(data) = scene.get(object) else {
+        return Matrix3::identity();
+    };
+    if !data.transform.dirty {
+        return data.transform.global_matrix;
+    }
+
+    let parent_matrix = scene.parent(object).map(|parent| global_matrix(scene, parent));
+
+    let transform = &mut scene.get_mut(object).expect("object was looked up above").transform;
+    transform.global_matrix = match parent_matrix {
+        Some(parent_matrix) => parent_matrix * transform.local_matrix,
+        None => transform.local_matrix,
+    };
+    transform.dirty = false;
+    transform.global_matrix
+}
+
+/**
+    Moves and turns `object` so that it ends up at `position` with a global
+    rotation of `angle` radians, keeping its local scale.
+*/
+pub fn set_global_pose(scene: &mut Scene, object: GameObject, position: Vector2, angle: f32) {
+    let parent_matrix = scene.parent(object)
+        .map(|parent| global_matrix(scene, parent))
+        .unwrap_or_else(Matrix3::identity);
+    let inverse = parent_matrix.affine_inverse().unwrap_or_else(Matrix3::identity);
+    let (x, y) = inverse.transform_point(position.get_x(), position.get_y());
+    let local_angle = angle - parent_matrix.rotation();
+
+    let Some(data) = scene.get_mut(object) else {
+        return;
+    };
+    data.transform.local_translation = Matrix3::translation(x, y);
+    data.transform.local_rotation = Matrix3::rotation_radians(local_angle);
+    data.transform.local_rotation_angle = local_angle;
+    data.transform.update_local_matrix();
+    mark_dirty(scene, object);
+}
+
+/**
+    Refreshes the global matrix of `object` and its descendants, only
+    recomputing the ones that are dirty.
+*/
+pub fn update_transform(scene: &mut Scene, object: GameObject) {
+    global_matrix(scene, object);
+
+    for child in scene.children(object) {
+        update_transform(scene, child);
+    }
+}
+
+// refreshes every transform in the arena, parented under the scene root or not
+pub fn update_transforms(scene: &mut Scene) {
+    for root in scene.roots() {
+        update_transform(scene, root);
+    }
+}
+
+// handle to the transform of a GameObject, parenting a transform parents its object
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    object: GameObject,
+}
+
+impl Transform {
+    pub fn of(object: GameObject) -> Transform {
+        Transform { object }
+    }
+
+    fn with<R>(&self, lua: &Lua, f: impl FnOnce(&Scene, &TransformData) -> R) -> LuaResult<R> {
+        let scene = scene_mut(lua)?;
+        let data = scene.get(self.object)
+            .ok_or_else(|| LuaError::RuntimeError("GameObject has been destroyed".into()))?;
+
+        Ok(f(&scene, &data.transform))
+    }
+
+    // applies a change to the local transform and marks the hierarchy below it as stale
+    fn set_local(&self, lua: &Lua, f: impl FnOnce(&mut TransformData)) -> LuaResult<()> {
+        let mut scene = scene_mut(lua)?;
+        let data = scene.get_mut(self.object)
+            .ok_or_else(|| LuaError::RuntimeError("GameObject has been destroyed".into()))?;
+
+        f(&mut data.transform);
+        data.transform.update_local_matrix();
+        mark_dirty(&mut scene, self.object);
+        physics::invalidate_queries(lua);
+        Ok(())
+    }
+
+    fn global_matrix(&self, lua: &Lua) -> LuaResult<Matrix3> {
+        self.with(lua, |_, _| ())?;
+        let mut scene = scene_mut(lua)?;
+        Ok(global_matrix(&mut scene, self.object))
     }
 }
 
@@ -56,7 +175,7 @@ impl LuaExportsTable<'_> for Transform {
 
 
 
-    fn create_exports_table(lua: &Lua) -> LuaResult<LuaTable> {
+    fn create_exports_table(lua: &Lua) -> LuaResult<LuaTable<'_>> {
         TableBuilder::new(lua)?
             .build_readonly()
     }
@@ -64,78 +183,70 @@ impl LuaExportsTable<'_> for Transform {
 
 impl LuaUserData for Transform {
     fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
-        fields.add_field_method_get("Parent", |_, this| Ok(this.parent.clone()));
-        fields.add_field_method_get("Children", |_, this| Ok(this.children.clone()));
-        fields.add_field_method_get("LocalMatrix", |_, this| Ok(this.local_matrix));
-        fields.add_field_method_get("GlobalMatrix", |_, this| Ok(this.global_matrix));
-        fields.add_field_method_get("LocalRotationAngle", |_, this| Ok(this.local_rotation_angle));
-
-        fields.add_field_method_set("Parent", |_, this, parent: LuaUserDataRef<Transform>| {
-            this.parent = Some(Rc::new(RefCell::new(parent.clone())));
-            this.update_transform();
-            Ok(())
+        fields.add_field_method_get("GameObject", |_, this| Ok(this.object));
+        fields.add_field_method_get("Parent", |lua, this| {
+            this.with(lua, |scene, _| scene.parent(this.object).map(Transform::of))
         });
+        fields.add_field_method_get("Children", |lua, this| {
+            this.with(lua, |scene, _| scene.children(this.object).into_iter().map(Transform::of).collect::<Vec<_>>())
+        });
+        fields.add_field_method_get("LocalMatrix", |lua, this| this.with(lua, |_, t| t.local_matrix));
+        fields.add_field_method_get("GlobalMatrix", |lua, this| this.global_matrix(lua));
+        fields.add_field_method_get("LocalRotationAngle", |lua, this| this.with(lua, |_, t| t.local_rotation_angle));
 
-        fields.add_field_method_get("LocalRotation", |_, this|
-            Ok(this.local_rotation)
-        );
-        fields.add_field_method_get("LocalPosition", |_, this| {
-            Ok(Vector2::new(this.local_translation.m02, this.local_translation.m12))
-            }
-        );
-        fields.add_field_method_get("LocalScale", |_, this| {
-            Ok(Vector2::new(this.local_scale.m00, this.local_scale.m11))
+        fields.add_field_method_set("Parent", |lua, this, parent: Option<LuaUserDataRef<Transform>>| {
+            this.object.set_parent(lua, parent.map(|parent| parent.object))
         });
 
-        fields.add_field_method_get("GlobalRotation", |_, this| {
-            Ok(this.global_matrix.m10.atan2(this.global_matrix.m00))
+        fields.add_field_method_get("LocalRotation", |lua, this| this.with(lua, |_, t| t.local_rotation));
+        fields.add_field_method_get("LocalPosition", |lua, this| {
+            this.with(lua, |_, t| Vector2::new(t.local_translation.m02, t.local_translation.m12))
+        });
+        fields.add_field_method_get("LocalScale", |lua, this| {
+            this.with(lua, |_, t| Vector2::new(t.local_scale.m00, t.local_scale.m11))
         });
 
-        fields.add_field_method_get("GlobalPosition", |_, this| {
-            Ok(Vector2::new(this.global_matrix.m02, this.global_matrix.m12))
+        fields.add_field_method_get("GlobalRotation", |lua, this| Ok(this.global_matrix(lua)?.rotation()));
+
+        fields.add_field_method_get("GlobalPosition", |lua, this| {
+            let m = this.global_matrix(lua)?;
+            Ok(Vector2::new(m.m02, m.m12))
         });
 
-        fields.add_field_method_get("GlobalScale", |_, this| {
-            Ok(Vector2::new(this.global_matrix.m00, this.global_matrix.m11))
+        fields.add_field_method_get("GlobalScale", |lua, this| {
+            let (x, y) = this.global_matrix(lua)?.scale();
+            Ok(Vector2::new(x, y))
         });
 
-        fields.add_field_method_set("LocalRotation", |_, this, m: LuaUserDataRef<Matrix3>| {
-            this.local_rotation = *m;
-            this.local_rotation_angle = this.local_rotation.m10.atan2(this.local_rotation.m00);
-            this.update_transform();
-            Ok(())
+        fields.add_field_method_set("LocalRotation", |lua, this, m: LuaUserDataRef<Matrix3>| {
+            this.set_local(lua, |t| {
+                t.local_rotation = *m;
+                t.local_rotation_angle = t.local_rotation.m10.atan2(t.local_rotation.m00);
+            })
         });
 
-        fields.add_field_method_set("LocalPosition", |_, this, v : LuaUserDataRef<Vector2>| {
-            this.local_translation = Matrix3 {
-                m00: 1.0, m01: 0.0, m02: v.get_x(),
-                m10: 0.0, m11: 1.0, m12: v.get_y(),
-                m20: 0.0, m21: 0.0, m22: 1.0,
-            };
-            this.update_transform();
-            Ok(())
+        fields.add_field_method_set("LocalPosition", |lua, this, v : LuaUserDataRef<Vector2>| {
+            this.set_local(lua, |t| {
+                t.local_translation = Matrix3::translation(v.get_x(), v.get_y());
+            })
         });
 
-        fields.add_field_method_set("LocalScale", |_, this, v : LuaUserDataRef<Vector2>| {
-            this.local_scale = Matrix3 {
-                m00: v.get_x(), m01: 0.0, m02: 0.0,
-                m10: 0.0, m11: v.get_y(), m12: 0.0,
-                m20: 0.0, m21: 0.0, m22: 1.0,
-            };
-            this.update_transform();
-            Ok(())
+        fields.add_field_method_set("LocalScale", |lua, this, v : LuaUserDataRef<Vector2>| {
+            this.set_local(lua, |t| {
+                t.local_scale = Matrix3::scaling(v.get_x(), v.get_y());
+            })
         });
-        
-    }
 
-    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
-        methods.add_meta_method(LuaMetaMethod::ToString, userdata_impl_to_string);
     }
-}
 
-impl fmt::Display for Transform {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "LocalMatrix: {},\nGlobalMatrix: {},\nLocalRotationAngle: {}", self.local_matrix, self.global_matrix, self.local_rotation_angle)
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_meta_method(LuaMetaMethod::Eq, userdata_impl_eq);
+        methods.add_meta_method(LuaMetaMethod::ToString, |lua, this, ()| {
+            let global_matrix = this.global_matrix(lua)?;
+            this.with(lua, |_, t| format!(
+                "LocalMatrix: {},\nGlobalMatrix: {},\nLocalRotationAngle: {}",
+                t.local_matrix, global_matrix, t.local_rotation_angle
+            ))
+        });
     }
 }
-
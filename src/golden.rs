@@ -0,0 +1,166 @@
+use std::path::{Path, PathBuf};
+
+use mlua::prelude::*;
+
+use crate::bee2d::{Bee2D, Bee2DConfig};
+use crate::render::Framebuffer;
+
+const USAGE: &str = "usage: bee2d test <script> --reference <png> [--frames <n>] [--dt <seconds>] \
+    [--tolerance <0-255>] [--output <png>] [--diff <png>] [--update]";
+
+/**
+    A golden-image test: runs a script headless for a fixed number of frames
+    with a fixed delta time, then compares the final frame against a reference PNG.
+*/
+pub struct GoldenTest {
+    pub script_path: String,
+    pub reference: PathBuf,
+    pub frames: u32,
+    pub delta_time: f64,
+    // largest difference allowed in any channel of a pixel before it counts as a mismatch
+    pub tolerance: u8,
+    // where the captured frame is written, if anywhere
+    pub output: Option<PathBuf>,
+    // where the diff image is written on failure, defaults to `<reference>.diff.png`
+    pub diff: Option<PathBuf>,
+    // overwrites the reference with the captured frame instead of comparing
+    pub update: bool,
+}
+
+pub enum Outcome {
+    Passed,
+    Updated,
+    Failed(String),
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} expects a value\n{}", flag, USAGE))?;
+    value.parse().map_err(|_| format!("invalid value '{}' for {}\n{}", value, flag, USAGE))
+}
+
+impl GoldenTest {
+    pub fn from_args(mut args: impl Iterator<Item = String>) -> Result<GoldenTest, String> {
+        let mut script_path = None;
+        let mut reference = None;
+        let mut test = GoldenTest {
+            script_path: String::new(),
+            reference: PathBuf::new(),
+            frames: 60,
+            delta_time: 1.0 / 60.0,
+            tolerance: 0,
+            output: None,
+            diff: None,
+            update: false,
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--reference" => reference = Some(parse_value::<PathBuf>(&arg, args.next())?),
+                "--frames" => test.frames = parse_value(&arg, args.next())?,
+                "--dt" => test.delta_time = parse_value(&arg, args.next())?,
+                "--tolerance" => test.tolerance = parse_value(&arg, args.next())?,
+                "--output" => test.output = Some(parse_value(&arg, args.next())?),
+                "--diff" => test.diff = Some(parse_value(&arg, args.next())?),
+                "--update" => test.update = true,
+                _ if arg.starts_with("--") => return Err(format!("unknown option '{}'\n{}", arg, USAGE)),
+                _ => script_path = Some(arg),
+            }
+        }
+
+        test.script_path = script_path.ok_or_else(|| format!("missing script path\n{}", USAGE))?;
+        test.reference = reference.ok_or_else(|| format!("missing --reference\n{}", USAGE))?;
+
+        Ok(test)
+    }
+
+    pub fn run(&self) -> LuaResult<Outcome> {
+        let source = std::fs::read_to_string(&self.script_path)?;
+
+        let mut bee2d = Bee2D::new(Bee2DConfig {
+            headless: true,
+            ..Bee2DConfig::default()
+        })?;
+        bee2d.load_script(&self.script_path, &source)?;
+
+        for _ in 0..self.frames {
+            bee2d.step(self.delta_time)?;
+        }
+
+        let actual = bee2d.framebuffer()
+            .ok_or_else(|| LuaError::RuntimeError("the renderer did not keep a framebuffer to capture".into()))?;
+
+        if let Some(output) = &self.output {
+            save(actual, output)?;
+        }
+
+        if self.update {
+            save(actual, &self.reference)?;
+            return Ok(Outcome::Updated);
+        }
+
+        if !self.reference.exists() {
+            return Ok(Outcome::Failed(format!(
+                "reference image {} does not exist, run again with --update to create it",
+                self.reference.display()
+            )));
+        }
+
+        let expected = Framebuffer::load_png(&self.reference)
+            .map_err(|err| LuaError::RuntimeError(format!("failed to load {}: {}", self.reference.display(), err)))?;
+
+        if (actual.width(), actual.height()) != (expected.width(), expected.height()) {
+            return Ok(Outcome::Failed(format!(
+                "frame is {}x{} but the reference is {}x{}",
+                actual.width(), actual.height(), expected.width(), expected.height()
+            )));
+        }
+
+        let (mismatched, diff) = compare(actual, &expected, self.tolerance);
+        if mismatched == 0 {
+            return Ok(Outcome::Passed);
+        }
+
+        let diff_path = self.diff.clone().unwrap_or_else(|| self.reference.with_extension("diff.png"));
+        save(&diff, &diff_path)?;
+
+        Ok(Outcome::Failed(format!(
+            "{} pixel(s) differ from {} by more than {}, diff written to {}",
+            mismatched,
+            self.reference.display(),
+            self.tolerance,
+            diff_path.display()
+        )))
+    }
+}
+
+fn save(framebuffer: &Framebuffer, path: &Path) -> LuaResult<()> {
+    framebuffer.save_png(path)
+        .map_err(|err| LuaError::RuntimeError(format!("failed to write {}: {}", path.display(), err)))
+}
+
+/**
+    Counts the pixels of `actual` that differ from `expected` by more than
+    `tolerance` in any channel. Both framebuffers must have the same size.
+
+    The returned diff image shows mismatched pixels in red over a faded
+    grayscale copy of the reference.
+*/
+pub fn compare(actual: &Framebuffer, expected: &Framebuffer, tolerance: u8) -> (usize, Framebuffer) {
+    let mut mismatched = 0;
+    let mut diff = Vec::with_capacity(expected.pixels().len());
+
+    for (a, e) in actual.pixels().chunks_exact(4).zip(expected.pixels().chunks_exact(4)) {
+        let differs = a.iter().zip(e).any(|(a, e)| a.abs_diff(*e) > tolerance);
+
+        if differs {
+            mismatched += 1;
+            diff.extend_from_slice(&[255, 0, 0, 255]);
+        } else {
+            let luma = ((e[0] as u32 * 299 + e[1] as u32 * 587 + e[2] as u32 * 114) / 1000) as u8;
+            let faded = luma / 4;
+            diff.extend_from_slice(&[faded, faded, faded, 255]);
+        }
+    }
+
+    (mismatched, Framebuffer::from_pixels(expected.width(), expected.height(), diff))
+}
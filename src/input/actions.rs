@@ -0,0 +1,299 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use mlua::prelude::*;
+
+use crate::lune::table_builder::TableBuilder;
+
+use super::codes::{self, GAMEPAD_AXES, GAMEPAD_BUTTONS, KEYS, MOUSE_BUTTONS};
+use super::{RawInput, MAX_GAMEPADS};
+
+const DEFAULT_DEAD_ZONE: f32 = 0.2;
+
+#[derive(Clone, Copy)]
+enum BindingSource {
+    Key(i32),
+    MouseButton(i32),
+    GamepadButton(i32),
+    GamepadAxis(i32),
+}
+
+impl BindingSource {
+    // field of a binding table that names the source, and the name of the code in it
+    fn field(self) -> (&'static str, &'static str) {
+        match self {
+            BindingSource::Key(key) => ("Key", codes::name(KEYS, key)),
+            BindingSource::MouseButton(button) => ("MouseButton", codes::name(MOUSE_BUTTONS, button)),
+            BindingSource::GamepadButton(button) => ("GamepadButton", codes::name(GAMEPAD_BUTTONS, button)),
+            BindingSource::GamepadAxis(axis) => ("GamepadAxis", codes::name(GAMEPAD_AXES, axis)),
+        }
+    }
+}
+
+// field of a binding table, the names it takes, what those are called in errors and the source they make
+type SourceField = (&'static str, &'static [(&'static str, i32)], &'static str, fn(i32) -> BindingSource);
+
+const SOURCE_FIELDS: [SourceField; 4] = [
+    ("Key", KEYS, "key", BindingSource::Key),
+    ("MouseButton", MOUSE_BUTTONS, "mouse button", BindingSource::MouseButton),
+    ("GamepadButton", GAMEPAD_BUTTONS, "gamepad button", BindingSource::GamepadButton),
+    ("GamepadAxis", GAMEPAD_AXES, "gamepad axis", BindingSource::GamepadAxis),
+];
+
+/**
+    One input an action listens to, like `{ Key = "A", Scale = -1 }`.
+
+    Buttons count as 0 or 1 and axes go from -1 to 1, either way multiplied by the scale.
+    Gamepad bindings read every connected gamepad unless a `Gamepad` is given.
+*/
+#[derive(Clone)]
+pub struct Binding {
+    source: BindingSource,
+    scale: f32,
+    gamepad: Option<usize>,
+}
+
+impl Binding {
+    fn value(&self, input: &RawInput, dead_zone: f32) -> f32 {
+        let gamepads = input.gamepads.iter()
+            .enumerate()
+            .filter(|(index, gamepad)| gamepad.connected && self.gamepad.is_none_or(|g| g == *index))
+            .map(|(_, gamepad)| gamepad);
+
+        let value = match self.source {
+            BindingSource::Key(key) => input.keys.contains(&key) as u8 as f32,
+            BindingSource::MouseButton(button) => input.mouse_buttons.contains(&button) as u8 as f32,
+            BindingSource::GamepadButton(button) => gamepads.into_iter().any(|g| g.buttons.contains(&button)) as u8 as f32,
+            // the gamepad pushed furthest wins
+            BindingSource::GamepadAxis(axis) => gamepads
+                .map(|g| apply_dead_zone(g.axes[axis as usize], dead_zone))
+                .fold(0.0, |a: f32, b: f32| if b.abs() > a.abs() { b } else { a }),
+        };
+
+        value * self.scale
+    }
+
+    fn to_lua<'lua>(&self, lua: &'lua Lua) -> LuaResult<LuaTable<'lua>> {
+        let (field, name) = self.source.field();
+        let mut builder = TableBuilder::new(lua)?
+            .with_value(field, name)?
+            .with_value("Scale", self.scale)?;
+        if let Some(gamepad) = self.gamepad {
+            builder = builder.with_value("Gamepad", gamepad + 1)?;
+        }
+        builder.build()
+    }
+
+    fn to_luau(&self) -> String {
+        let (field, name) = self.source.field();
+        let mut source = format!("{{ {} = {}", field, luau_string(name));
+        if self.scale != 1.0 {
+            write!(source, ", Scale = {}", self.scale).unwrap();
+        }
+        if let Some(gamepad) = self.gamepad {
+            write!(source, ", Gamepad = {}", gamepad + 1).unwrap();
+        }
+        source + " }"
+    }
+}
+
+impl<'lua> FromLua<'lua> for Binding {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<Binding> {
+        let LuaValue::Table(table) = value else {
+            return Err(LuaError::RuntimeError(format!("Expected a binding table, got {}", value.type_name())));
+        };
+
+        let mut source = None;
+        for (field, names, kind, constructor) in SOURCE_FIELDS {
+            if let Some(name) = table.get::<_, Option<String>>(field)? {
+                if source.is_some() {
+                    return Err(LuaError::RuntimeError("A binding can only have one input".into()));
+                }
+                source = Some(constructor(codes::code(names, kind, &name)?));
+            }
+        }
+
+        let source = source.ok_or_else(|| LuaError::RuntimeError(
+            "A binding needs one of Key, MouseButton, GamepadButton or GamepadAxis".into()
+        ))?;
+
+        let gamepad = match table.get::<_, Option<usize>>("Gamepad")? {
+            Some(gamepad) if (1..=MAX_GAMEPADS).contains(&gamepad) => Some(gamepad - 1),
+            Some(gamepad) => {
+                return Err(LuaError::RuntimeError(format!("Expected a gamepad from 1 to {}, got {}", MAX_GAMEPADS, gamepad)));
+            }
+            None => None,
+        };
+
+        // bindings are saved as Luau number literals, which can't be NaN or infinite
+        let scale = table.get::<_, Option<f32>>("Scale")?.unwrap_or(1.0);
+        if !scale.is_finite() {
+            return Err(LuaError::RuntimeError(format!("Scale must be a finite number, got {}", scale)));
+        }
+
+        Ok(Binding {
+            source,
+            scale,
+            gamepad,
+        })
+    }
+}
+
+// a Luau string literal for `value`, control characters escaped by byte
+fn luau_string(value: &str) -> String {
+    let mut literal = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            c if c.is_ascii_control() => write!(literal, "\\{:03}", c as u8).unwrap(),
+            c => literal.push(c),
+        }
+    }
+    literal + "\""
+}
+
+// rescales what is left past the dead zone back to the full 0 to 1 range
+fn apply_dead_zone(value: f32, dead_zone: f32) -> f32 {
+    if value.abs() <= dead_zone {
+        0.0
+    } else {
+        value.signum() * (value.abs() - dead_zone) / (1.0 - dead_zone)
+    }
+}
+
+/**
+    A named action, `{ DeadZone = 0.2, Bindings = { ... } }` in Luau.
+*/
+#[derive(Clone)]
+pub struct Action {
+    dead_zone: f32,
+    bindings: Vec<Binding>,
+}
+
+impl Action {
+    // the sum of every binding, clamped to -1 to 1
+    pub fn value(&self, input: &RawInput) -> f32 {
+        self.bindings.iter()
+            .map(|binding| binding.value(input, self.dead_zone))
+            .sum::<f32>()
+            .clamp(-1.0, 1.0)
+    }
+
+    pub fn is_down(&self, input: &RawInput) -> bool {
+        self.value(input) != 0.0
+    }
+}
+
+impl<'lua> FromLua<'lua> for Action {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<Action> {
+        let LuaValue::Table(table) = value else {
+            return Err(LuaError::RuntimeError(format!("Expected an action table, got {}", value.type_name())));
+        };
+
+        let dead_zone = table.get::<_, Option<f32>>("DeadZone")?.unwrap_or(DEFAULT_DEAD_ZONE);
+        if !(0.0..1.0).contains(&dead_zone) {
+            return Err(LuaError::RuntimeError(format!("DeadZone must be from 0 to less than 1, got {}", dead_zone)));
+        }
+
+        Ok(Action {
+            dead_zone,
+            bindings: table.get::<_, Option<Vec<Binding>>>("Bindings")?.unwrap_or_default(),
+        })
+    }
+}
+
+/**
+    Actions by name, defined from Luau or loaded from a bindings file.
+
+    Bindings files are Luau returning a table of actions, the same tables
+    `Input.DefineAction` takes, and run without access to any globals.
+*/
+#[derive(Default)]
+pub struct ActionMap {
+    actions: BTreeMap<String, Action>,
+}
+
+impl ActionMap {
+    pub fn define(&mut self, name: String, action: Action) {
+        self.actions.insert(name, action);
+    }
+
+    pub fn get(&self, name: &str) -> LuaResult<&Action> {
+        self.actions.get(name)
+            .ok_or_else(|| LuaError::RuntimeError(format!("'{}' is not a defined action", name)))
+    }
+
+    pub fn rebind(&mut self, name: &str, bindings: Vec<Binding>) -> LuaResult<()> {
+        self.actions.get_mut(name)
+            .ok_or_else(|| LuaError::RuntimeError(format!("'{}' is not a defined action", name)))?
+            .bindings = bindings;
+        Ok(())
+    }
+
+    pub fn bindings<'lua>(&self, lua: &'lua Lua, name: &str) -> LuaResult<Vec<LuaTable<'lua>>> {
+        self.get(name)?.bindings.iter().map(|binding| binding.to_lua(lua)).collect()
+    }
+
+    pub fn to_luau(&self) -> String {
+        let mut source = String::from("-- input bindings saved by Bee2D\nreturn {\n");
+        for (name, action) in &self.actions {
+            writeln!(source, "\t[{}] = {{", luau_string(name)).unwrap();
+            writeln!(source, "\t\tDeadZone = {},", action.dead_zone).unwrap();
+            writeln!(source, "\t\tBindings = {{").unwrap();
+            for binding in &action.bindings {
+                writeln!(source, "\t\t\t{},", binding.to_luau()).unwrap();
+            }
+            source.push_str("\t\t},\n\t},\n");
+        }
+        source + "}\n"
+    }
+}
+
+// runs a bindings file and returns the actions in it
+pub fn load_file(lua: &Lua, path: &str) -> LuaResult<Vec<(String, Action)>> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|err| LuaError::RuntimeError(format!("failed to load bindings from '{}': {}", path, err)))?;
+
+    let actions: LuaTable = lua.load(source)
+        .set_name(path)
+        .set_environment(lua.create_table()?)
+        .eval()?;
+
+    actions.pairs::<String, Action>().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_bindings_load_back() {
+        let lua = Lua::new();
+        let name = "jump \"high\"\\\n\tüber\u{1}";
+        let action: Action = lua.load(r#"{ DeadZone = 0.25, Bindings = { { Key = "Space", Scale = -0.5 } } }"#).eval().unwrap();
+
+        let mut actions = ActionMap::default();
+        actions.define(name.to_string(), action);
+
+        let saved: LuaTable = lua.load(actions.to_luau()).eval().unwrap();
+        let action: LuaTable = saved.get(name).unwrap();
+        assert_eq!(action.get::<_, f32>("DeadZone").unwrap(), 0.25);
+
+        let binding: LuaTable = action.get::<_, LuaTable>("Bindings").unwrap().get(1).unwrap();
+        assert_eq!(binding.get::<_, String>("Key").unwrap(), "Space");
+        assert_eq!(binding.get::<_, f32>("Scale").unwrap(), -0.5);
+    }
+
+    #[test]
+    fn bindings_need_a_finite_scale() {
+        let lua = Lua::new();
+        for scale in ["0/0", "math.huge", "-math.huge"] {
+            let binding = lua.load(format!("{{ Key = \"Space\", Scale = {} }}", scale)).eval::<Binding>();
+            assert!(binding.is_err(), "Scale = {} was accepted", scale);
+        }
+    }
+}
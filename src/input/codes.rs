@@ -0,0 +1,56 @@
+use mlua::prelude::*;
+
+// names scripts use for keys, with the key codes raylib (and GLFW) use for them
+pub const KEYS: &[(&str, i32)] = &[
+    ("Space", 32), ("Apostrophe", 39), ("Comma", 44), ("Minus", 45), ("Period", 46), ("Slash", 47),
+    ("Zero", 48), ("One", 49), ("Two", 50), ("Three", 51), ("Four", 52),
+    ("Five", 53), ("Six", 54), ("Seven", 55), ("Eight", 56), ("Nine", 57),
+    ("Semicolon", 59), ("Equals", 61),
+    ("A", 65), ("B", 66), ("C", 67), ("D", 68), ("E", 69), ("F", 70), ("G", 71), ("H", 72), ("I", 73),
+    ("J", 74), ("K", 75), ("L", 76), ("M", 77), ("N", 78), ("O", 79), ("P", 80), ("Q", 81), ("R", 82),
+    ("S", 83), ("T", 84), ("U", 85), ("V", 86), ("W", 87), ("X", 88), ("Y", 89), ("Z", 90),
+    ("LeftBracket", 91), ("Backslash", 92), ("RightBracket", 93), ("Backquote", 96),
+    ("Escape", 256), ("Return", 257), ("Tab", 258), ("Backspace", 259), ("Insert", 260), ("Delete", 261),
+    ("Right", 262), ("Left", 263), ("Down", 264), ("Up", 265),
+    ("PageUp", 266), ("PageDown", 267), ("Home", 268), ("End", 269),
+    ("CapsLock", 280), ("ScrollLock", 281), ("NumLock", 282), ("PrintScreen", 283), ("Pause", 284),
+    ("F1", 290), ("F2", 291), ("F3", 292), ("F4", 293), ("F5", 294), ("F6", 295),
+    ("F7", 296), ("F8", 297), ("F9", 298), ("F10", 299), ("F11", 300), ("F12", 301),
+    ("KeypadZero", 320), ("KeypadOne", 321), ("KeypadTwo", 322), ("KeypadThree", 323), ("KeypadFour", 324),
+    ("KeypadFive", 325), ("KeypadSix", 326), ("KeypadSeven", 327), ("KeypadEight", 328), ("KeypadNine", 329),
+    ("KeypadPeriod", 330), ("KeypadDivide", 331), ("KeypadMultiply", 332), ("KeypadMinus", 333),
+    ("KeypadPlus", 334), ("KeypadEnter", 335), ("KeypadEquals", 336),
+    ("LeftShift", 340), ("LeftControl", 341), ("LeftAlt", 342), ("LeftSuper", 343),
+    ("RightShift", 344), ("RightControl", 345), ("RightAlt", 346), ("RightSuper", 347), ("Menu", 348),
+];
+
+pub const MOUSE_BUTTONS: &[(&str, i32)] = &[("Left", 0), ("Right", 1), ("Middle", 2)];
+
+// named after the Xbox layout, the face buttons are in the same place on other controllers
+pub const GAMEPAD_BUTTONS: &[(&str, i32)] = &[
+    ("DPadUp", 1), ("DPadRight", 2), ("DPadDown", 3), ("DPadLeft", 4),
+    ("ButtonY", 5), ("ButtonB", 6), ("ButtonA", 7), ("ButtonX", 8),
+    ("ButtonL1", 9), ("ButtonL2", 10), ("ButtonR1", 11), ("ButtonR2", 12),
+    ("ButtonSelect", 13), ("ButtonGuide", 14), ("ButtonStart", 15), ("ButtonL3", 16), ("ButtonR3", 17),
+];
+
+pub const GAMEPAD_AXES: &[(&str, i32)] = &[
+    ("LeftX", 0), ("LeftY", 1), ("RightX", 2), ("RightY", 3), ("LeftTrigger", 4), ("RightTrigger", 5),
+];
+
+/**
+    Looks up the code of `name` in one of the tables above, `kind` names what
+    the table holds in the error.
+*/
+pub fn code(codes: &[(&str, i32)], kind: &str, name: &str) -> LuaResult<i32> {
+    codes.iter()
+        .find(|(n, _)| *n == name)
+        .map(|&(_, code)| code)
+        .ok_or_else(|| LuaError::RuntimeError(format!("'{}' is not a valid {}", name, kind)))
+}
+
+pub fn name(codes: &[(&'static str, i32)], code: i32) -> &'static str {
+    codes.iter()
+        .find(|&&(_, c)| c == code)
+        .map_or("Unknown", |&(name, _)| name)
+}
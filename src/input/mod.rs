@@ -0,0 +1,346 @@
+use std::collections::BTreeSet;
+
+use mlua::prelude::*;
+use mlua::{AppDataRef, AppDataRefMut};
+
+use crate::lune::signal::Signal;
+use crate::lune::table_builder::TableBuilder;
+use crate::math::Vector2;
+
+pub mod actions;
+use actions::{Action, ActionMap, Binding};
+
+pub mod codes;
+use codes::{GAMEPAD_AXES, GAMEPAD_BUTTONS, KEYS, MOUSE_BUTTONS};
+
+#[cfg(feature = "raylib")]
+pub mod raylib_source;
+#[cfg(feature = "raylib")]
+pub use raylib_source::RaylibInput;
+
+pub const MAX_GAMEPADS: usize = 4;
+
+#[derive(Clone, Default)]
+pub struct GamepadState {
+    pub connected: bool,
+    pub buttons: BTreeSet<i32>,
+    pub axes: [f32; 6],
+}
+
+/**
+    The state of every input device at one point in time, keys and buttons
+    are held as the codes in [`codes`].
+*/
+#[derive(Clone, Default)]
+pub struct RawInput {
+    pub keys: BTreeSet<i32>,
+    pub mouse_buttons: BTreeSet<i32>,
+    pub mouse_position: (f32, f32),
+    // scrolled since the previous frame
+    pub mouse_wheel: f32,
+    pub gamepads: [GamepadState; MAX_GAMEPADS],
+}
+
+/**
+    Where the engine reads input from at the start of every frame.
+
+    [`RaylibInput`] reads the devices of the window. Headless, there are no
+    devices and [`NoInput`] leaves everything to simulated input from scripts.
+*/
+pub trait InputSource {
+    // brings `input`, which still holds the previous frame, up to date with the devices
+    fn poll(&mut self, input: &mut RawInput);
+}
+
+pub struct NoInput;
+
+impl InputSource for NoInput {
+    fn poll(&mut self, _input: &mut RawInput) {}
+}
+
+// changes requested by scripts through `Input.Simulate*`, applied on top of the source
+enum SimulatedInput {
+    Key(i32, bool),
+    MouseButton(i32, bool),
+    MouseMove(f32, f32),
+    MouseWheel(f32),
+    GamepadButton(usize, i32, bool),
+    GamepadAxis(usize, i32, f32),
+}
+
+impl SimulatedInput {
+    fn apply(self, input: &mut RawInput) {
+        let set = |set: &mut BTreeSet<i32>, code, down| if down { set.insert(code) } else { set.remove(&code) };
+
+        match self {
+            SimulatedInput::Key(key, down) => { set(&mut input.keys, key, down); }
+            SimulatedInput::MouseButton(button, down) => { set(&mut input.mouse_buttons, button, down); }
+            SimulatedInput::MouseMove(x, y) => input.mouse_position = (x, y),
+            SimulatedInput::MouseWheel(delta) => input.mouse_wheel += delta,
+            SimulatedInput::GamepadButton(gamepad, button, down) => {
+                input.gamepads[gamepad].connected = true;
+                set(&mut input.gamepads[gamepad].buttons, button, down);
+            }
+            SimulatedInput::GamepadAxis(gamepad, axis, value) => {
+                input.gamepads[gamepad].connected = true;
+                input.gamepads[gamepad].axes[axis as usize] = value;
+            }
+        }
+    }
+}
+
+// a key or button that went down or up between two frames
+#[derive(Clone, Copy)]
+enum InputEvent {
+    Key(i32),
+    MouseButton(i32),
+    GamepadButton(usize, i32),
+}
+
+impl InputEvent {
+    // read-only table handed to `InputBegan` and `InputEnded` connections
+    fn to_lua<'lua>(self, lua: &'lua Lua, mouse_position: (f32, f32)) -> LuaResult<LuaTable<'lua>> {
+        let (input_type, key_code) = match self {
+            InputEvent::Key(key) => ("Keyboard", codes::name(KEYS, key)),
+            InputEvent::MouseButton(button) => ("MouseButton", codes::name(MOUSE_BUTTONS, button)),
+            InputEvent::GamepadButton(_, button) => ("Gamepad", codes::name(GAMEPAD_BUTTONS, button)),
+        };
+
+        let mut builder = TableBuilder::new(lua)?
+            .with_value("InputType", input_type)?
+            .with_value("KeyCode", key_code)?
+            .with_value("Position", Vector2::new(mouse_position.0, mouse_position.1))?;
+        if let InputEvent::GamepadButton(gamepad, _) = self {
+            builder = builder.with_value("Gamepad", gamepad + 1)?;
+        }
+        builder.build_readonly()
+    }
+}
+
+/**
+    Input for the current and the previous frame, which is what tells a key
+    that is held down apart from one that was just pressed.
+*/
+#[derive(Default)]
+pub struct InputState {
+    current: RawInput,
+    previous: RawInput,
+    simulated: Vec<SimulatedInput>,
+    actions: ActionMap,
+    began: Signal,
+    ended: Signal,
+}
+
+#[derive(Clone, Copy)]
+enum Query {
+    Down,
+    Pressed,
+    Released,
+}
+
+impl InputState {
+    fn query(&self, query: Query, is_down: impl Fn(&RawInput) -> bool) -> bool {
+        match query {
+            Query::Down => is_down(&self.current),
+            Query::Pressed => is_down(&self.current) && !is_down(&self.previous),
+            Query::Released => !is_down(&self.current) && is_down(&self.previous),
+        }
+    }
+
+    // everything that went down, then everything that went up, since the previous frame
+    fn changes(&self) -> (Vec<InputEvent>, Vec<InputEvent>) {
+        let mut began = Vec::new();
+        let mut ended = Vec::new();
+
+        let mut diff = |current: &BTreeSet<i32>, previous: &BTreeSet<i32>, event: &dyn Fn(i32) -> InputEvent| {
+            began.extend(current.difference(previous).map(|&code| event(code)));
+            ended.extend(previous.difference(current).map(|&code| event(code)));
+        };
+
+        diff(&self.current.keys, &self.previous.keys, &InputEvent::Key);
+        diff(&self.current.mouse_buttons, &self.previous.mouse_buttons, &InputEvent::MouseButton);
+        for (gamepad, (current, previous)) in self.current.gamepads.iter().zip(&self.previous.gamepads).enumerate() {
+            diff(&current.buttons, &previous.buttons, &|button| InputEvent::GamepadButton(gamepad, button));
+        }
+
+        (began, ended)
+    }
+}
+
+fn input_state(lua: &Lua) -> LuaResult<AppDataRef<'_, InputState>> {
+    lua.app_data_ref::<InputState>()
+        .ok_or_else(|| LuaError::RuntimeError("Bee2D has not been initialized".into()))
+}
+
+fn input_state_mut(lua: &Lua) -> LuaResult<AppDataRefMut<'_, InputState>> {
+    lua.app_data_mut::<InputState>()
+        .ok_or_else(|| LuaError::RuntimeError("Bee2D has not been initialized".into()))
+}
+
+/**
+    Polls `source` for the new frame, applies simulated input on top and fires
+    `InputBegan` and `InputEnded` for every key and button that changed.
+*/
+pub fn begin_frame(lua: &Lua, source: &mut dyn InputSource) -> LuaResult<()> {
+    let (began, ended, changes, mouse_position) = {
+        let mut state = input_state_mut(lua)?;
+
+        let mut input = state.current.clone();
+        input.mouse_wheel = 0.0;
+        source.poll(&mut input);
+        for simulated in std::mem::take(&mut state.simulated) {
+            simulated.apply(&mut input);
+        }
+
+        state.previous = std::mem::replace(&mut state.current, input);
+        (state.began.clone(), state.ended.clone(), state.changes(), state.current.mouse_position)
+    };
+
+    for event in changes.0 {
+        began.fire(lua, event.to_lua(lua, mouse_position)?)?;
+    }
+    for event in changes.1 {
+        ended.fire(lua, event.to_lua(lua, mouse_position)?)?;
+    }
+
+    Ok(())
+}
+
+// gamepads are numbered from 1 in Luau
+fn gamepad_index(gamepad: usize) -> LuaResult<usize> {
+    if (1..=MAX_GAMEPADS).contains(&gamepad) {
+        Ok(gamepad - 1)
+    } else {
+        Err(LuaError::RuntimeError(format!("Expected a gamepad from 1 to {}, got {}", MAX_GAMEPADS, gamepad)))
+    }
+}
+
+fn key_query(query: Query) -> impl Fn(&Lua, String) -> LuaResult<bool> {
+    move |lua, key| {
+        let key = codes::code(KEYS, "key", &key)?;
+        Ok(input_state(lua)?.query(query, |input| input.keys.contains(&key)))
+    }
+}
+
+fn mouse_button_query(query: Query) -> impl Fn(&Lua, String) -> LuaResult<bool> {
+    move |lua, button| {
+        let button = codes::code(MOUSE_BUTTONS, "mouse button", &button)?;
+        Ok(input_state(lua)?.query(query, |input| input.mouse_buttons.contains(&button)))
+    }
+}
+
+fn gamepad_button_query(query: Query) -> impl Fn(&Lua, (usize, String)) -> LuaResult<bool> {
+    move |lua, (gamepad, button)| {
+        let gamepad = gamepad_index(gamepad)?;
+        let button = codes::code(GAMEPAD_BUTTONS, "gamepad button", &button)?;
+        Ok(input_state(lua)?.query(query, |input| input.gamepads[gamepad].buttons.contains(&button)))
+    }
+}
+
+fn action_query(query: Query) -> impl Fn(&Lua, String) -> LuaResult<bool> {
+    move |lua, name| {
+        let state = input_state(lua)?;
+        let action = state.actions.get(&name)?;
+        Ok(state.query(query, |input| action.is_down(input)))
+    }
+}
+
+fn simulate(lua: &Lua, simulated: SimulatedInput) -> LuaResult<()> {
+    input_state_mut(lua)?.simulated.push(simulated);
+    Ok(())
+}
+
+/**
+    Creates the `Bee2D.Input` table.
+
+    Pressed and released are relative to the previous frame. Simulated input is
+    applied at the start of the next frame, over whatever the devices report.
+
+    Actions group bindings under a name, so scripts can ask for `"Jump"`
+    instead of a key and players can rebind it. Loading bindings replaces
+    the actions defined in the file and leaves the others alone.
+*/
+pub fn create_api(lua: &Lua) -> LuaResult<LuaTable<'_>> {
+    let (began, ended) = {
+        let state = input_state(lua)?;
+        (state.began.clone(), state.ended.clone())
+    };
+
+    TableBuilder::new(lua)?
+        .with_value("InputBegan", began)?
+        .with_value("InputEnded", ended)?
+        .with_function("IsKeyDown", key_query(Query::Down))?
+        .with_function("IsKeyPressed", key_query(Query::Pressed))?
+        .with_function("IsKeyReleased", key_query(Query::Released))?
+        .with_function("IsMouseButtonDown", mouse_button_query(Query::Down))?
+        .with_function("IsMouseButtonPressed", mouse_button_query(Query::Pressed))?
+        .with_function("IsMouseButtonReleased", mouse_button_query(Query::Released))?
+        .with_function("GetMousePosition", |lua, ()| {
+            let (x, y) = input_state(lua)?.current.mouse_position;
+            Ok(Vector2::new(x, y))
+        })?
+        .with_function("GetMouseWheel", |lua, ()| Ok(input_state(lua)?.current.mouse_wheel))?
+        .with_function("IsGamepadConnected", |lua, gamepad: usize| {
+            let gamepad = gamepad_index(gamepad)?;
+            Ok(input_state(lua)?.current.gamepads[gamepad].connected)
+        })?
+        .with_function("IsGamepadButtonDown", gamepad_button_query(Query::Down))?
+        .with_function("IsGamepadButtonPressed", gamepad_button_query(Query::Pressed))?
+        .with_function("IsGamepadButtonReleased", gamepad_button_query(Query::Released))?
+        .with_function("GetGamepadAxis", |lua, (gamepad, axis): (usize, String)| {
+            let gamepad = gamepad_index(gamepad)?;
+            let axis = codes::code(GAMEPAD_AXES, "gamepad axis", &axis)?;
+            Ok(input_state(lua)?.current.gamepads[gamepad].axes[axis as usize])
+        })?
+        .with_function("DefineAction", |lua, (name, action): (String, Action)| {
+            input_state_mut(lua)?.actions.define(name, action);
+            Ok(())
+        })?
+        .with_function("GetAction", action_query(Query::Down))?
+        .with_function("GetActionPressed", action_query(Query::Pressed))?
+        .with_function("GetActionReleased", action_query(Query::Released))?
+        .with_function("GetAxis", |lua, name: String| {
+            let state = input_state(lua)?;
+            Ok(state.actions.get(&name)?.value(&state.current))
+        })?
+        .with_function("GetBindings", |lua, name: String| input_state(lua)?.actions.bindings(lua, &name))?
+        .with_function("Rebind", |lua, (name, bindings): (String, Vec<Binding>)| {
+            input_state_mut(lua)?.actions.rebind(&name, bindings)
+        })?
+        .with_function("SaveBindings", |lua, path: String| {
+            let source = input_state(lua)?.actions.to_luau();
+            std::fs::write(&path, source)
+                .map_err(|err| LuaError::RuntimeError(format!("failed to save bindings to '{}': {}", path, err)))
+        })?
+        .with_function("LoadBindings", |lua, path: String| {
+            let actions = actions::load_file(lua, &path)?;
+            let mut state = input_state_mut(lua)?;
+            for (name, action) in actions {
+                state.actions.define(name, action);
+            }
+            Ok(())
+        })?
+        .with_function("SimulateKey", |lua, (key, down): (String, bool)| {
+            simulate(lua, SimulatedInput::Key(codes::code(KEYS, "key", &key)?, down))
+        })?
+        .with_function("SimulateMouseButton", |lua, (button, down): (String, bool)| {
+            simulate(lua, SimulatedInput::MouseButton(codes::code(MOUSE_BUTTONS, "mouse button", &button)?, down))
+        })?
+        .with_function("SimulateMouseMove", |lua, position: LuaUserDataRef<Vector2>| {
+            simulate(lua, SimulatedInput::MouseMove(position.get_x(), position.get_y()))
+        })?
+        .with_function("SimulateMouseWheel", |lua, delta: f32| {
+            simulate(lua, SimulatedInput::MouseWheel(delta))
+        })?
+        .with_function("SimulateGamepadButton", |lua, (gamepad, button, down): (usize, String, bool)| {
+            let gamepad = gamepad_index(gamepad)?;
+            let button = codes::code(GAMEPAD_BUTTONS, "gamepad button", &button)?;
+            simulate(lua, SimulatedInput::GamepadButton(gamepad, button, down))
+        })?
+        .with_function("SimulateGamepadAxis", |lua, (gamepad, axis, value): (usize, String, f32)| {
+            let gamepad = gamepad_index(gamepad)?;
+            let axis = codes::code(GAMEPAD_AXES, "gamepad axis", &axis)?;
+            simulate(lua, SimulatedInput::GamepadAxis(gamepad, axis, value.clamp(-1.0, 1.0)))
+        })?
+        .build_readonly()
+}
@@ -0,0 +1,44 @@
+use raylib::ffi;
+
+use super::codes::{GAMEPAD_AXES, GAMEPAD_BUTTONS, KEYS, MOUSE_BUTTONS};
+use super::{GamepadState, InputSource, RawInput};
+
+/**
+    Reads the keyboard, mouse and gamepads through raylib, which collects
+    their events for the window whenever a frame is presented.
+*/
+pub struct RaylibInput;
+
+impl InputSource for RaylibInput {
+    fn poll(&mut self, input: &mut RawInput) {
+        // SAFETY: only created for a windowed engine, after raylib has been initialized
+        unsafe {
+            input.keys = KEYS.iter().map(|&(_, key)| key).filter(|&key| ffi::IsKeyDown(key)).collect();
+            input.mouse_buttons = MOUSE_BUTTONS.iter()
+                .map(|&(_, button)| button)
+                .filter(|&button| ffi::IsMouseButtonDown(button))
+                .collect();
+
+            let position = ffi::GetMousePosition();
+            input.mouse_position = (position.x, position.y);
+            input.mouse_wheel += ffi::GetMouseWheelMove();
+
+            for (index, gamepad) in input.gamepads.iter_mut().enumerate() {
+                let index = index as i32;
+                if !ffi::IsGamepadAvailable(index) {
+                    *gamepad = GamepadState::default();
+                    continue;
+                }
+
+                gamepad.connected = true;
+                gamepad.buttons = GAMEPAD_BUTTONS.iter()
+                    .map(|&(_, button)| button)
+                    .filter(|&button| ffi::IsGamepadButtonDown(index, button))
+                    .collect();
+                for &(_, axis) in GAMEPAD_AXES {
+                    gamepad.axes[axis as usize] = ffi::GetGamepadAxisMovement(index, axis);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,16 @@
+mod assets;
+mod bee2d;
+mod callbacks;
+mod golden;
+mod input;
+mod math;
+mod lune;
+mod physics;
+mod engine;
+mod render;
+mod scheduler;
+mod tween;
+
+pub use bee2d::{Bee2D, Bee2DConfig};
+pub use golden::{GoldenTest, Outcome};
+pub use render::{Framebuffer, Rgba};
@@ -10,7 +10,7 @@ use mlua::prelude::*;
 
     ### Example usage
 
-    ```rs
+    ```ignore
     use mlua::prelude::*;
 
     struct MyType(usize);
@@ -53,7 +53,7 @@ pub trait LuaExportsTable<'lua> {
 
     ### Example usage
 
-    ```rs
+    ```ignore
     let lua: mlua::Lua::new();
 
     let (name1, table1) = export::<Type1>(lua)?;
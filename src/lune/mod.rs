@@ -1,3 +1,4 @@
 pub mod table_builder;
 pub mod exports;
-pub mod userdata;
\ No newline at end of file
+pub mod userdata;
+pub mod signal;
@@ -0,0 +1,222 @@
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+use mlua::prelude::*;
+
+use crate::lune::table_builder::TableBuilder;
+use crate::lune::exports::LuaExportsTable;
+use crate::lune::userdata::*;
+use crate::scheduler;
+
+// like `wait`, `Signal:Wait` has to yield from Luau, the Rust side only keeps the thread
+const WAIT_SOURCE: &str = r#"
+local addWaiter = ...
+
+return function(signal)
+    if not coroutine.isyieldable() then
+        error("Signal:Wait can only be called from inside a task", 2)
+    end
+
+    addWaiter(signal, coroutine.running())
+    return coroutine.yield()
+end
+"#;
+
+struct ConnectionEntry {
+    id: u32,
+    callback: LuaRegistryKey,
+    // disconnects itself the first time the signal fires
+    once: bool,
+}
+
+#[derive(Default)]
+struct SignalState {
+    next_id: u32,
+    connections: Vec<ConnectionEntry>,
+    // park ids of the threads suspended in `Signal:Wait`, see `scheduler::park`
+    waiting: Vec<u64>,
+}
+
+/**
+    An event that Luau code can connect functions to.
+
+    Cloning a signal gives another handle to the same connections, so the
+    same signal can be handed out to scripts any number of times.
+*/
+#[derive(Clone, Default)]
+pub struct Signal {
+    state: Rc<RefCell<SignalState>>,
+}
+
+impl Signal {
+    pub fn new() -> Signal {
+        Signal::default()
+    }
+
+    fn add_connection(&self, lua: &Lua, callback: LuaFunction, once: bool) -> LuaResult<Connection> {
+        let mut state = self.state.borrow_mut();
+        let id = state.next_id;
+        state.next_id += 1;
+        state.connections.push(ConnectionEntry { id, callback: lua.create_registry_value(callback)?, once });
+
+        Ok(Connection::new(SignalConnection { state: Rc::downgrade(&self.state), id }))
+    }
+
+    pub fn connect(&self, lua: &Lua, callback: LuaFunction) -> LuaResult<Connection> {
+        self.add_connection(lua, callback, false)
+    }
+
+    pub fn once(&self, lua: &Lua, callback: LuaFunction) -> LuaResult<Connection> {
+        self.add_connection(lua, callback, true)
+    }
+
+    /**
+        Calls every connected function with `args`, each one in its own thread
+        so that it can yield without holding up the others, then resumes every
+        thread waiting on the signal with the same arguments.
+
+        Functions connected and threads that start waiting while firing only
+        run the next time. One that errors doesn't stop the rest, the first
+        error is returned once all of them ran.
+    */
+    pub fn fire<'lua>(&self, lua: &'lua Lua, args: impl IntoLuaMulti<'lua>) -> LuaResult<()> {
+        let (callbacks, waiting) = {
+            let mut state = self.state.borrow_mut();
+            let callbacks = state.connections.iter()
+                .map(|entry| lua.registry_value::<LuaFunction>(&entry.callback))
+                .collect::<LuaResult<Vec<_>>>()?;
+            state.connections.retain(|entry| !entry.once);
+
+            (callbacks, std::mem::take(&mut state.waiting))
+        };
+
+        let args = args.into_lua_multi(lua)?;
+        let mut first_error = None;
+        for callback in callbacks {
+            if let Err(err) = scheduler::spawn(lua, LuaValue::Function(callback), args.clone()) {
+                first_error.get_or_insert(err);
+            }
+        }
+
+        for id in waiting {
+            // cancelled threads are gone from the scheduler, and the rest may have
+            // finished some other way while they waited
+            let resumed = scheduler::unpark(lua, id).and_then(|thread| match thread {
+                Some(thread) if thread.status() == LuaThreadStatus::Resumable => {
+                    scheduler::spawn(lua, LuaValue::Thread(thread), args.clone()).map(|_| ())
+                }
+                _ => Ok(()),
+            });
+            if let Err(err) = resumed {
+                first_error.get_or_insert(err);
+            }
+        }
+
+        first_error.map_or(Ok(()), Err)
+    }
+
+    fn add_waiter(&self, lua: &Lua, thread: LuaThread) -> LuaResult<()> {
+        let id = scheduler::park(lua, thread)?;
+        self.state.borrow_mut().waiting.push(id);
+        Ok(())
+    }
+}
+
+impl PartialEq for Signal {
+    fn eq(&self, other: &Signal) -> bool {
+        Rc::ptr_eq(&self.state, &other.state)
+    }
+}
+
+// the Luau half of `Signal:Wait`, created once per Lua state
+fn wait_function(lua: &Lua) -> LuaResult<LuaFunction<'_>> {
+    if let LuaValue::Function(function) = lua.named_registry_value("Bee2D.Signal.Wait")? {
+        return Ok(function);
+    }
+
+    let add_waiter = lua.create_function(|lua, (signal, thread): (LuaUserDataRef<Signal>, LuaThread)| {
+        signal.add_waiter(lua, thread)
+    })?;
+    let function: LuaFunction = lua.load(WAIT_SOURCE).set_name("Signal:Wait").call(add_waiter)?;
+    lua.set_named_registry_value("Bee2D.Signal.Wait", function.clone())?;
+    Ok(function)
+}
+
+impl LuaExportsTable<'_> for Signal {
+    const EXPORT_NAME: &'static str = "Signal";
+
+    fn create_exports_table(lua: &Lua) -> LuaResult<LuaTable<'_>> {
+        TableBuilder::new(lua)?
+            .with_function("new", |_, ()| Ok(Signal::new()))?
+            .build_readonly()
+    }
+}
+
+impl LuaUserData for Signal {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        // a Luau function rather than a method, so that it can yield
+        fields.add_field_function_get("Wait", |lua, _| wait_function(lua));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("Connect", |lua, this, callback: LuaFunction| this.connect(lua, callback));
+        methods.add_method("Once", |lua, this, callback: LuaFunction| this.once(lua, callback));
+        methods.add_method("Fire", |lua, this, args: LuaMultiValue| this.fire(lua, args));
+
+        methods.add_meta_method(LuaMetaMethod::Eq, userdata_impl_eq);
+        methods.add_meta_method(LuaMetaMethod::ToString, |_, _, ()| Ok("Signal"));
+    }
+}
+
+/**
+    What a [`Connection`] is connected to, so that signals and anything else
+    scripts connect functions to can hand out the same connection type.
+*/
+pub trait Disconnect {
+    fn is_connected(&self, lua: &Lua) -> LuaResult<bool>;
+
+    fn disconnect(&self, lua: &Lua) -> LuaResult<()>;
+}
+
+// keeps the signal itself alive only through its owner
+struct SignalConnection {
+    state: Weak<RefCell<SignalState>>,
+    id: u32,
+}
+
+impl Disconnect for SignalConnection {
+    fn is_connected(&self, _: &Lua) -> LuaResult<bool> {
+        Ok(self.state.upgrade()
+            .is_some_and(|state| state.borrow().connections.iter().any(|entry| entry.id == self.id)))
+    }
+
+    fn disconnect(&self, _: &Lua) -> LuaResult<()> {
+        if let Some(state) = self.state.upgrade() {
+            state.borrow_mut().connections.retain(|entry| entry.id != self.id);
+        }
+        Ok(())
+    }
+}
+
+// returned from `Signal:Connect` and `Bee2D.bindTo*`
+pub struct Connection {
+    target: Box<dyn Disconnect>,
+}
+
+impl Connection {
+    pub fn new(target: impl Disconnect + 'static) -> Connection {
+        Connection { target: Box::new(target) }
+    }
+}
+
+impl LuaUserData for Connection {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_field_method_get("Connected", |lua, this| this.target.is_connected(lua));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("Disconnect", |lua, this, ()| this.target.disconnect(lua));
+
+        methods.add_meta_method(LuaMetaMethod::ToString, |_, _, ()| Ok("Connection"));
+    }
+}
@@ -0,0 +1,140 @@
+use core::fmt;
+use std::f32::consts::PI;
+
+use mlua::prelude::*;
+use crate::lune::table_builder::*;
+use crate::lune::exports::*;
+use crate::lune::userdata::*;
+
+use super::geometry::{self, Geometry};
+use super::{Matrix3, Rect, Vector2};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Circle {
+    center: Vector2,
+    radius: f32,
+}
+
+impl Circle {
+    pub fn new(center: Vector2, radius: f32) -> Result<Circle, String> {
+        if radius.is_nan() || radius < 0.0 {
+            return Err(format!("Radius must be 0 or greater, got {}", radius));
+        }
+        Ok(Circle { center, radius })
+    }
+
+    pub fn center(&self) -> Vector2 {
+        self.center
+    }
+
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    pub fn contains(&self, point: Vector2) -> bool {
+        (point - self.center).length() <= self.radius
+    }
+
+    // pulled onto the edge from outside, points within the radius are already there
+    pub fn closest_point(&self, point: Vector2) -> Vector2 {
+        let offset = point - self.center;
+        if offset.length() <= self.radius {
+            return point;
+        }
+        self.center + offset.normalized() * self.radius
+    }
+
+    // stays a circle by growing with the larger of the two scales
+    pub fn transform(&self, matrix: &Matrix3) -> Circle {
+        let (scale_x, scale_y) = matrix.scale();
+        Circle {
+            center: geometry::transform_point(matrix, self.center),
+            radius: self.radius * scale_x.max(scale_y),
+        }
+    }
+
+    pub fn bounds(&self) -> Rect {
+        Rect::new(self.center, self.center).grow(self.radius)
+    }
+}
+
+impl LuaExportsTable<'_> for Circle {
+    const EXPORT_NAME: &'static str = "Circle";
+
+    fn create_exports_table(lua: &Lua) -> LuaResult<LuaTable<'_>> {
+        let circle_new = |_, (center, radius): (LuaUserDataRef<Vector2>, f32)| {
+            Circle::new(*center, radius).map_err(LuaError::RuntimeError)
+        };
+
+        TableBuilder::new(lua)?
+            .with_function("new", circle_new)?
+            .build_readonly()
+    }
+}
+
+impl LuaUserData for Circle {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_field_method_get("Center", |_, this| Ok(this.center));
+        fields.add_field_method_get("Radius", |_, this| Ok(this.radius));
+        fields.add_field_method_get("Area", |_, this| Ok(PI * this.radius * this.radius));
+        fields.add_field_method_get("Bounds", |_, this| Ok(this.bounds()));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("Contains", |_, this, point: LuaUserDataRef<Vector2>| Ok(this.contains(*point)));
+        methods.add_method("Intersects", |_, this, other: Geometry| Ok(Geometry::Circle(*this).intersects(&other)));
+        methods.add_method("ClosestPoint", |_, this, point: LuaUserDataRef<Vector2>| Ok(this.closest_point(*point)));
+        methods.add_method("Transform", |_, this, matrix: LuaUserDataRef<Matrix3>| Ok(this.transform(&matrix)));
+
+        methods.add_meta_method(LuaMetaMethod::Eq, userdata_impl_eq);
+        methods.add_meta_method(LuaMetaMethod::ToString, userdata_impl_to_string);
+    }
+}
+
+impl fmt::Display for Circle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, {}", self.center, self.radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn circle(x: f32, y: f32, radius: f32) -> Circle {
+        Circle::new(Vector2::new(x, y), radius).unwrap()
+    }
+
+    #[test]
+    fn radius_must_be_a_size() {
+        assert!(Circle::new(Vector2::new(0.0, 0.0), 0.0).is_ok());
+        assert!(Circle::new(Vector2::new(0.0, 0.0), -1.0).is_err());
+        assert!(Circle::new(Vector2::new(0.0, 0.0), f32::NAN).is_err());
+    }
+
+    #[test]
+    fn containment_and_closest_points() {
+        let circle = circle(10.0, 10.0, 5.0);
+        assert!(circle.contains(Vector2::new(13.0, 14.0)));
+        assert!(!circle.contains(Vector2::new(14.0, 14.0)));
+        assert_eq!(circle.closest_point(Vector2::new(11.0, 11.0)), Vector2::new(11.0, 11.0));
+        assert_eq!(circle.closest_point(Vector2::new(30.0, 10.0)), Vector2::new(15.0, 10.0));
+        assert_eq!(circle.bounds(), Rect::new(Vector2::new(5.0, 5.0), Vector2::new(15.0, 15.0)));
+    }
+
+    #[test]
+    fn intersections() {
+        let circle = Geometry::Circle(circle(0.0, 0.0, 5.0));
+        assert!(circle.intersects(&Geometry::Circle(self::circle(8.0, 0.0, 3.0))));
+        assert!(!circle.intersects(&Geometry::Circle(self::circle(8.0, 0.0, 2.9))));
+        assert!(circle.intersects(&Geometry::Rect(Rect::new(Vector2::new(-1.0, -1.0), Vector2::new(1.0, 1.0)))));
+        // the corner of the rectangle is further out than its sides
+        assert!(!circle.intersects(&Geometry::Rect(Rect::new(Vector2::new(4.0, 4.0), Vector2::new(8.0, 8.0)))));
+    }
+
+    #[test]
+    fn transforms_grow_with_the_larger_scale() {
+        let moved = circle(1.0, 0.0, 2.0).transform(&(Matrix3::translation(5.0, 5.0) * Matrix3::scaling(3.0, 2.0)));
+        assert_eq!(moved, circle(8.0, 5.0, 6.0));
+    }
+}
@@ -0,0 +1,313 @@
+use core::fmt;
+use std::ops;
+
+use mlua::prelude::*;
+use crate::lune::table_builder::*;
+use crate::lune::exports::*;
+use crate::lune::userdata::*;
+
+/**
+    An RGBA color with every channel in the range 0 to 1.
+
+    Arithmetic clamps its results back into that range, so colors can be
+    added, scaled and blended without ever going out of bounds.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    r: f32,
+    g: f32,
+    b: f32,
+    a: f32,
+}
+
+impl Color {
+    pub const WHITE: Color = Color { r: 1.0, g: 1.0, b: 1.0, a: 1.0 };
+    pub const BLACK: Color = Color { r: 0.0, g: 0.0, b: 0.0, a: 1.0 };
+
+    pub fn new(r: f32, g: f32, b: f32, a: f32) -> Color {
+        Color {
+            r: r.clamp(0.0, 1.0),
+            g: g.clamp(0.0, 1.0),
+            b: b.clamp(0.0, 1.0),
+            a: a.clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn from_rgb(r: f32, g: f32, b: f32, a: f32) -> Color {
+        Color::new(r / 255.0, g / 255.0, b / 255.0, a / 255.0)
+    }
+
+    // hue, saturation and value all range from 0 to 1, a hue of 1 wraps back around to red
+    pub fn from_hsv(h: f32, s: f32, v: f32, a: f32) -> Color {
+        let h = h.rem_euclid(1.0) * 6.0;
+        let (s, v) = (s.clamp(0.0, 1.0), v.clamp(0.0, 1.0));
+
+        let chroma = v * s;
+        let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+
+        let m = v - chroma;
+        Color::new(r + m, g + m, b + m, a)
+    }
+
+    // accepts `rgb`, `rgba`, `rrggbb` and `rrggbbaa`, with or without a leading `#`
+    pub fn from_hex(hex: &str) -> Result<Color, String> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        let invalid = || format!("'{}' is not a valid hex color", hex);
+
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+
+        let channel = |index: usize, width: usize| {
+            let value = u8::from_str_radix(&digits[index * width..(index + 1) * width], 16).unwrap_or(0);
+            // a single digit stands for itself repeated, so `f` is `ff`
+            if width == 1 { value * 17 } else { value }
+        };
+
+        let (width, channels) = match digits.len() {
+            3 => (1, 3),
+            4 => (1, 4),
+            6 => (2, 3),
+            8 => (2, 4),
+            _ => return Err(invalid()),
+        };
+
+        let alpha = if channels == 4 { channel(3, width) } else { 255 };
+        Ok(Color::from_rgb(channel(0, width) as f32, channel(1, width) as f32, channel(2, width) as f32, alpha as f32))
+    }
+
+    // channels scaled to 0 to 255 and rounded
+    pub fn to_rgb(self) -> [u8; 4] {
+        [self.r, self.g, self.b, self.a].map(|channel| (channel * 255.0).round() as u8)
+    }
+
+    pub fn to_hsv(self) -> (f32, f32, f32) {
+        let max = self.r.max(self.g).max(self.b);
+        let min = self.r.min(self.g).min(self.b);
+        let delta = max - min;
+
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == self.r {
+            ((self.g - self.b) / delta).rem_euclid(6.0)
+        } else if max == self.g {
+            (self.b - self.r) / delta + 2.0
+        } else {
+            (self.r - self.g) / delta + 4.0
+        };
+
+        let saturation = if max == 0.0 { 0.0 } else { delta / max };
+        (hue / 6.0, saturation, max)
+    }
+
+    // `#rrggbb`, or `#rrggbbaa` when the color isn't fully opaque
+    pub fn to_hex(self) -> String {
+        let [r, g, b, a] = self.to_rgb();
+        if a == 255 {
+            format!("#{:02x}{:02x}{:02x}", r, g, b)
+        } else {
+            format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+        }
+    }
+
+    pub fn lerp(self, other: Color, alpha: f32) -> Color {
+        let mix = |a: f32, b: f32| a + (b - a) * alpha;
+        Color::new(mix(self.r, other.r), mix(self.g, other.g), mix(self.b, other.b), mix(self.a, other.a))
+    }
+}
+
+impl LuaExportsTable<'_> for Color {
+    const EXPORT_NAME: &'static str = "Color";
+
+    fn create_exports_table(lua: &Lua) -> LuaResult<LuaTable<'_>> {
+        let color_new = |_, (r, g, b, a): (Option<f32>, Option<f32>, Option<f32>, Option<f32>)| {
+            Ok(Color::new(r.unwrap_or(0.0), g.unwrap_or(0.0), b.unwrap_or(0.0), a.unwrap_or(1.0)))
+        };
+
+        let color_from_rgb = |_, (r, g, b, a): (Option<f32>, Option<f32>, Option<f32>, Option<f32>)| {
+            Ok(Color::from_rgb(r.unwrap_or(0.0), g.unwrap_or(0.0), b.unwrap_or(0.0), a.unwrap_or(255.0)))
+        };
+
+        let color_from_hsv = |_, (h, s, v, a): (f32, f32, f32, Option<f32>)| {
+            Ok(Color::from_hsv(h, s, v, a.unwrap_or(1.0)))
+        };
+
+        let color_from_hex = |_, hex: String| {
+            Color::from_hex(&hex).map_err(LuaError::RuntimeError)
+        };
+
+        TableBuilder::new(lua)?
+            .with_function("new", color_new)?
+            .with_function("fromRGB", color_from_rgb)?
+            .with_function("fromHSV", color_from_hsv)?
+            .with_function("fromHex", color_from_hex)?
+            .with_value("white", Color::WHITE)?
+            .with_value("black", Color::BLACK)?
+            .with_value("gray", Color::new(0.5, 0.5, 0.5, 1.0))?
+            .with_value("red", Color::new(1.0, 0.0, 0.0, 1.0))?
+            .with_value("green", Color::new(0.0, 1.0, 0.0, 1.0))?
+            .with_value("blue", Color::new(0.0, 0.0, 1.0, 1.0))?
+            .with_value("yellow", Color::new(1.0, 1.0, 0.0, 1.0))?
+            .with_value("cyan", Color::new(0.0, 1.0, 1.0, 1.0))?
+            .with_value("magenta", Color::new(1.0, 0.0, 1.0, 1.0))?
+            .with_value("transparent", Color::new(0.0, 0.0, 0.0, 0.0))?
+            .build_readonly()
+    }
+}
+
+impl LuaUserData for Color {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_field_method_get("R", |_, this| Ok(this.r));
+        fields.add_field_method_get("G", |_, this| Ok(this.g));
+        fields.add_field_method_get("B", |_, this| Ok(this.b));
+        fields.add_field_method_get("A", |_, this| Ok(this.a));
+        fields.add_field_method_get("Hue", |_, this| Ok(this.to_hsv().0));
+        fields.add_field_method_get("Saturation", |_, this| Ok(this.to_hsv().1));
+        fields.add_field_method_get("Value", |_, this| Ok(this.to_hsv().2));
+        fields.add_field_method_get("Hex", |_, this| Ok(this.to_hex()));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("Lerp", |_, this, (color, alpha): (LuaUserDataRef<Color>, f32)| {
+            Ok(this.lerp(*color, alpha))
+        });
+
+        methods.add_method("ToRGB", |_, this, ()| {
+            let [r, g, b, a] = this.to_rgb();
+            Ok((r, g, b, a))
+        });
+
+        methods.add_method("ToHSV", |_, this, ()| Ok(this.to_hsv()));
+
+        methods.add_meta_method(LuaMetaMethod::Eq, userdata_impl_eq);
+        methods.add_meta_method(LuaMetaMethod::ToString, userdata_impl_to_string);
+        methods.add_meta_method(LuaMetaMethod::Add, userdata_impl_add);
+        methods.add_meta_method(LuaMetaMethod::Sub, userdata_impl_sub);
+        methods.add_meta_method(LuaMetaMethod::Mul, userdata_impl_mul_f32);
+
+        methods.add_meta_method(LuaMetaMethod::Div, |_, this, divisor: f32| {
+            if divisor == 0.0 {
+                return Err(LuaError::RuntimeError("attempt to divide by zero".into()));
+            }
+            Ok(*this / divisor)
+        });
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, {}, {}, {}", self.r, self.g, self.b, self.a)
+    }
+}
+
+// adding and subtracting works on every channel, alpha included
+impl ops::Add for Color {
+    type Output = Color;
+
+    fn add(self, rhs: Color) -> Color {
+        Color::new(self.r + rhs.r, self.g + rhs.g, self.b + rhs.b, self.a + rhs.a)
+    }
+}
+
+impl ops::Sub for Color {
+    type Output = Color;
+
+    fn sub(self, rhs: Color) -> Color {
+        Color::new(self.r - rhs.r, self.g - rhs.g, self.b - rhs.b, self.a - rhs.a)
+    }
+}
+
+// multiplying two colors tints one with the other
+impl ops::Mul for Color {
+    type Output = Color;
+
+    fn mul(self, rhs: Color) -> Color {
+        Color::new(self.r * rhs.r, self.g * rhs.g, self.b * rhs.b, self.a * rhs.a)
+    }
+}
+
+// scaling by a number brightens or darkens, leaving alpha as it is
+impl ops::Mul<f32> for Color {
+    type Output = Color;
+
+    fn mul(self, rhs: f32) -> Color {
+        Color::new(self.r * rhs, self.g * rhs, self.b * rhs, self.a)
+    }
+}
+
+impl ops::Div<f32> for Color {
+    type Output = Color;
+
+    fn div(self, rhs: f32) -> Color {
+        Color::new(self.r / rhs, self.g / rhs, self.b / rhs, self.a)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn channels_are_clamped() {
+        assert_eq!(Color::new(2.0, -1.0, 0.5, 1.5), Color::new(1.0, 0.0, 0.5, 1.0));
+        assert_eq!(Color::from_rgb(255.0, 0.0, 510.0, 255.0), Color::new(1.0, 0.0, 1.0, 1.0));
+        assert_eq!(Color::from_rgb(51.0, 102.0, 204.0, 255.0).to_rgb(), [51, 102, 204, 255]);
+    }
+
+    #[test]
+    fn hex_round_trips() {
+        assert_eq!(Color::from_hex("#ff8000").unwrap().to_rgb(), [255, 128, 0, 255]);
+        assert_eq!(Color::from_hex("f80").unwrap().to_rgb(), [255, 136, 0, 255]);
+        assert_eq!(Color::from_hex("#f808").unwrap().to_rgb(), [255, 136, 0, 136]);
+        assert_eq!(Color::from_hex("336699cc").unwrap().to_hex(), "#336699cc");
+        assert_eq!(Color::from_hex("#336699ff").unwrap().to_hex(), "#336699");
+
+        for hex in ["", "#", "ff800", "#ff80000", "#gg0000", "#ff 000", "#+f0000", "#ff80€"] {
+            assert!(Color::from_hex(hex).is_err(), "'{}' was accepted", hex);
+        }
+    }
+
+    #[test]
+    fn hsv_conversion() {
+        assert_eq!(Color::from_hsv(0.0, 1.0, 1.0, 1.0).to_rgb(), [255, 0, 0, 255]);
+        assert_eq!(Color::from_hsv(1.0 / 3.0, 1.0, 1.0, 1.0).to_rgb(), [0, 255, 0, 255]);
+        assert_eq!(Color::from_hsv(2.0 / 3.0, 1.0, 0.5, 1.0).to_rgb(), [0, 0, 128, 255]);
+        // a hue of 1 wraps back around to red
+        assert_eq!(Color::from_hsv(1.0, 1.0, 1.0, 1.0), Color::from_hsv(0.0, 1.0, 1.0, 1.0));
+        assert_eq!(Color::from_hsv(0.3, 0.0, 0.25, 1.0).to_rgb(), [64, 64, 64, 255]);
+
+        let (h, s, v) = Color::from_rgb(255.0, 128.0, 0.0, 255.0).to_hsv();
+        assert!(close(h, 128.0 / 255.0 / 6.0) && close(s, 1.0) && close(v, 1.0), "{} {} {}", h, s, v);
+        let (h, s, v) = Color::from_hsv(0.8, 0.5, 0.75, 1.0).to_hsv();
+        assert!(close(h, 0.8) && close(s, 0.5) && close(v, 0.75), "{} {} {}", h, s, v);
+        assert_eq!(Color::BLACK.to_hsv(), (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn lerp_and_arithmetic() {
+        let grey = Color::new(0.5, 0.5, 0.5, 1.0);
+        assert_eq!(Color::BLACK.lerp(Color::WHITE, 0.5), grey);
+        assert_eq!(Color::BLACK.lerp(Color::WHITE, 0.0), Color::BLACK);
+        assert_eq!(Color::BLACK.lerp(Color::WHITE, 2.0), Color::WHITE);
+
+        assert_eq!(grey + grey, Color::WHITE);
+        assert_eq!(Color::WHITE + Color::WHITE, Color::WHITE);
+        assert_eq!(Color::WHITE - grey, Color::new(0.5, 0.5, 0.5, 0.0));
+        assert_eq!(grey * Color::new(1.0, 0.0, 0.5, 0.5), Color::new(0.5, 0.0, 0.25, 0.5));
+        // scaling leaves alpha alone
+        assert_eq!(Color::new(0.5, 0.5, 0.5, 0.5) * 4.0, Color::new(1.0, 1.0, 1.0, 0.5));
+        assert_eq!(Color::new(0.5, 0.5, 0.5, 0.5) / 2.0, Color::new(0.25, 0.25, 0.25, 0.5));
+    }
+}
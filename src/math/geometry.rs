@@ -0,0 +1,115 @@
+use mlua::prelude::*;
+
+use super::{Circle, Matrix3, Polygon, Rect, Segment, Vector2};
+
+// how far off a point can be and still count as on a line
+pub const EPSILON: f32 = 1e-4;
+
+pub fn transform_point(matrix: &Matrix3, point: Vector2) -> Vector2 {
+    let (x, y) = matrix.transform_point(point.get_x(), point.get_y());
+    Vector2::new(x, y)
+}
+
+/**
+    Any of the geometry types, for methods that work with whichever one
+    they are given.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub enum Geometry {
+    Rect(Rect),
+    Circle(Circle),
+    Segment(Segment),
+    Polygon(Polygon),
+}
+
+impl Geometry {
+    pub fn closest_point(&self, point: Vector2) -> Vector2 {
+        match self {
+            Geometry::Rect(rect) => rect.closest_point(point),
+            Geometry::Circle(circle) => circle.closest_point(point),
+            Geometry::Segment(segment) => segment.closest_point(point),
+            Geometry::Polygon(polygon) => polygon.closest_point(point),
+        }
+    }
+
+    // the corners of the shape, which circles don't have
+    fn outline(&self) -> Vec<Vector2> {
+        match self {
+            Geometry::Rect(rect) => rect.corners().to_vec(),
+            Geometry::Circle(_) => Vec::new(),
+            Geometry::Segment(segment) => vec![segment.a(), segment.b()],
+            Geometry::Polygon(polygon) => polygon.points().to_vec(),
+        }
+    }
+
+    // whether the two overlap or touch, every shape counting as filled in
+    pub fn intersects(&self, other: &Geometry) -> bool {
+        match (self, other) {
+            (Geometry::Rect(a), Geometry::Rect(b)) => a.intersects(b),
+            (Geometry::Circle(a), Geometry::Circle(b)) => (a.center() - b.center()).length() <= a.radius() + b.radius(),
+            (Geometry::Circle(circle), shape) | (shape, Geometry::Circle(circle)) => {
+                (shape.closest_point(circle.center()) - circle.center()).length() <= circle.radius()
+            }
+            (Geometry::Segment(a), Geometry::Segment(b)) => a.intersection(b).is_some(),
+            (a, b) => !separated(&a.outline(), &b.outline()),
+        }
+    }
+}
+
+// positive when the outline goes counter-clockwise in math terms
+pub fn signed_area(outline: &[Vector2]) -> f32 {
+    (0..outline.len())
+        .map(|i| outline[i].cross(outline[(i + 1) % outline.len()]))
+        .sum::<f32>() / 2.0
+}
+
+/**
+    The unit directions a convex outline could be separated from another
+    along, one per edge and for a segment also past its ends.
+*/
+pub fn axes(outline: &[Vector2]) -> Vec<Vector2> {
+    match outline.len() {
+        0 | 1 => Vec::new(),
+        2 => {
+            let direction = (outline[1] - outline[0]).normalized();
+            vec![-direction.perp(), direction]
+        }
+        n => (0..n).map(|i| -(outline[(i + 1) % n] - outline[i]).normalized().perp()).collect(),
+    }
+}
+
+fn project(outline: &[Vector2], axis: Vector2) -> (f32, f32) {
+    outline.iter().fold((f32::MAX, f32::MIN), |(min, max), p| (min.min(p.dot(axis)), max.max(p.dot(axis))))
+}
+
+// how far the outlines would have to move apart along `axis` to stop overlapping, negative when there is a gap
+pub fn overlap(a: &[Vector2], b: &[Vector2], axis: Vector2) -> f32 {
+    let (min_a, max_a) = project(a, axis);
+    let (min_b, max_b) = project(b, axis);
+    (max_a - min_b).min(max_b - min_a)
+}
+
+// separating axis test for two convex outlines
+fn separated(a: &[Vector2], b: &[Vector2]) -> bool {
+    axes(a).into_iter().chain(axes(b)).any(|axis| overlap(a, b, axis) < 0.0)
+}
+
+impl<'lua> FromLua<'lua> for Geometry {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<Geometry> {
+        if let LuaValue::UserData(ud) = &value {
+            if let Ok(rect) = ud.borrow::<Rect>() {
+                return Ok(Geometry::Rect(*rect));
+            }
+            if let Ok(circle) = ud.borrow::<Circle>() {
+                return Ok(Geometry::Circle(*circle));
+            }
+            if let Ok(segment) = ud.borrow::<Segment>() {
+                return Ok(Geometry::Segment(*segment));
+            }
+            if let Ok(polygon) = ud.borrow::<Polygon>() {
+                return Ok(Geometry::Polygon(polygon.clone()));
+            }
+        }
+        Err(LuaError::RuntimeError(format!("Expected a Rect, Circle, Segment or Polygon, got {}", value.type_name())))
+    }
+}
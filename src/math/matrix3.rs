@@ -22,7 +22,18 @@ impl Matrix3 {
         }
     }
 
+    // blends every element on its own, so rotations in between aren't rotations anymore
+    pub fn lerp(self, b: Matrix3, t: f32) -> Matrix3 {
+        let a = self;
+        Matrix3 {
+            m00: a.m00 + (b.m00 - a.m00) * t, m01: a.m01 + (b.m01 - a.m01) * t, m02: a.m02 + (b.m02 - a.m02) * t,
+            m10: a.m10 + (b.m10 - a.m10) * t, m11: a.m11 + (b.m11 - a.m11) * t, m12: a.m12 + (b.m12 - a.m12) * t,
+            m20: a.m20 + (b.m20 - a.m20) * t, m21: a.m21 + (b.m21 - a.m21) * t, m22: a.m22 + (b.m22 - a.m22) * t,
+        }
+    }
+
     // returns a base rotation matrix 
+    #[allow(dead_code)]
     pub fn get_rotation(&self) -> Matrix3 {
         Matrix3 {
             m00: 0.0, m01: 0.0, m02: 0.0,
@@ -31,13 +42,59 @@ impl Matrix3 {
 
         }
     }
-    
+
+    // applies the matrix to a point, treating it as (x, y, 1)
+    pub fn transform_point(&self, x: f32, y: f32) -> (f32, f32) {
+        (self.m00 * x + self.m01 * y + self.m02, self.m10 * x + self.m11 * y + self.m12)
+    }
+
+    // counter-clockwise angle of the x axis in radians, so clockwise on screen
+    pub fn rotation(&self) -> f32 {
+        self.m10.atan2(self.m00)
+    }
+
+    // inverse of the affine part, None when it collapses the plane onto a line
+    pub fn affine_inverse(&self) -> Option<Matrix3> {
+        let determinant = self.m00 * self.m11 - self.m01 * self.m10;
+        if determinant.abs() <= f32::EPSILON {
+            return None;
+        }
+
+        let (m00, m01) = (self.m11 / determinant, -self.m01 / determinant);
+        let (m10, m11) = (-self.m10 / determinant, self.m00 / determinant);
+
+        Some(Matrix3 {
+            m00, m01, m02: -(m00 * self.m02 + m01 * self.m12),
+            m10, m11, m12: -(m10 * self.m02 + m11 * self.m12),
+            m20: 0.0, m21: 0.0, m22: 1.0,
+        })
+    }
+
+    pub fn translation(x: f32, y: f32) -> Matrix3 {
+        Matrix3 { m02: x, m12: y, ..Matrix3::identity() }
+    }
+
+    // counter-clockwise in math terms, so clockwise on a y-down screen
+    pub fn rotation_radians(angle: f32) -> Matrix3 {
+        let (sin, cos) = angle.sin_cos();
+        Matrix3 { m00: cos, m01: -sin, m10: sin, m11: cos, ..Matrix3::identity() }
+    }
+
+    pub fn scaling(x: f32, y: f32) -> Matrix3 {
+        Matrix3 { m00: x, m11: y, ..Matrix3::identity() }
+    }
+
+    // lengths of the basis vectors, rotation doesn't leak into them
+    pub fn scale(&self) -> (f32, f32) {
+        ((self.m00 * self.m00 + self.m10 * self.m10).sqrt(), (self.m01 * self.m01 + self.m11 * self.m11).sqrt())
+    }
 }
 
 impl LuaExportsTable<'_> for Matrix3 {
     const EXPORT_NAME: &'static str = "Matrix3";
 
-    fn create_exports_table(lua: &Lua) -> LuaResult<LuaTable> {
+    fn create_exports_table(lua: &Lua) -> LuaResult<LuaTable<'_>> {
+        #[allow(clippy::type_complexity)]
         let matrix3_new = |_, (m00, m01, m02, m10, m11, m12, m20, m21, m22): (Option<f32>, Option<f32>, Option<f32>, Option<f32>, Option<f32>, Option<f32>, Option<f32>, Option<f32>, Option<f32>)| {
             Ok(Matrix3 {
                 m00: m00.unwrap_or(0.0), m01: m01.unwrap_or(0.0), m02: m02.unwrap_or(0.0),
@@ -72,11 +129,7 @@ impl LuaExportsTable<'_> for Matrix3 {
         };
 
         let matrix3_lerp = |_, (a, b, t): (LuaUserDataRef<Matrix3>, LuaUserDataRef<Matrix3>, f32)| {
-            Ok(Matrix3 {
-                m00: a.m00 + (b.m00 - a.m00) * t, m01: a.m01 + (b.m01 - a.m01) * t, m02: a.m02 + (b.m02 - a.m02) * t,
-                m10: a.m10 + (b.m10 - a.m10) * t, m11: a.m11 + (b.m11 - a.m11) * t, m12: a.m12 + (b.m12 - a.m12) * t,
-                m20: a.m20 + (b.m20 - a.m20) * t, m21: a.m21 + (b.m21 - a.m21) * t, m22: a.m22 + (b.m22 - a.m22) * t,
-            })
+            Ok(a.lerp(*b, t))
         };
 
         TableBuilder::new(lua)?
@@ -109,11 +162,7 @@ impl LuaUserData for Matrix3 {
 
     fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
         methods.add_method("Lerp", |_, this, (other, t) : (LuaUserDataRef<Matrix3>, f32)| {
-            Ok(Matrix3 {
-                m00: this.m00 + (other.m00 - this.m00) * t, m01: this.m01 + (other.m01 - this.m01) * t, m02: this.m02 + (other.m02 - this.m02) * t,
-                m10: this.m10 + (other.m10 - this.m10) * t, m11: this.m11 + (other.m11 - this.m11) * t, m12: this.m12 + (other.m12 - this.m12) * t,
-                m20: this.m20 + (other.m20 - this.m20) * t, m21: this.m21 + (other.m21 - this.m21) * t, m22: this.m22 + (other.m22 - this.m22) * t,
-            })
+            Ok(this.lerp(*other, t))
         });
 
         methods.add_meta_method(LuaMetaMethod::Eq, userdata_impl_eq);
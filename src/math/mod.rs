@@ -1,6 +1,23 @@
+pub mod circle;
+pub use circle::Circle;
+
+pub mod color;
+pub use color::Color;
+
+pub mod geometry;
+
 pub mod matrix3;
 pub use matrix3::Matrix3;
 
+pub mod polygon;
+pub use polygon::Polygon;
+
+pub mod rect;
+pub use rect::Rect;
+
+pub mod segment;
+pub use segment::Segment;
+
 pub mod vector2;
 pub use vector2::Vector2;
 
@@ -9,15 +26,20 @@ use mlua::prelude::*;
 use crate::lune::table_builder::TableBuilder;
 use crate::lune::exports::export;
 
-fn create_all_exports(lua: &Lua) -> LuaResult<Vec<(&'static str, LuaValue)>> {
+fn create_all_exports(lua: &Lua) -> LuaResult<Vec<(&'static str, LuaValue<'_>)>> {
 
     Ok(vec![
+        export::< Circle>(lua)?,
+        export::< Color>(lua)?,
         export::< Matrix3>(lua)?,
+        export::< Polygon>(lua)?,
+        export::< Rect>(lua)?,
+        export::< Segment>(lua)?,
         export::< Vector2>(lua)?,
     ])
 }
 
-pub fn module(lua: &Lua) -> LuaResult<LuaTable> {
+pub fn module(lua: &Lua) -> LuaResult<LuaTable<'_>> {
     let exports = create_all_exports(lua)?;
     TableBuilder::new(lua)?
         .with_values(exports)?
@@ -0,0 +1,229 @@
+use core::fmt;
+use std::f32::consts::TAU;
+
+use mlua::prelude::*;
+use crate::lune::table_builder::*;
+use crate::lune::exports::*;
+use crate::lune::userdata::*;
+
+use super::geometry::{self, Geometry};
+use super::{Matrix3, Rect, Segment, Vector2};
+
+/**
+    A convex polygon, its points going around it in either direction.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon {
+    points: Vec<Vector2>,
+}
+
+impl Polygon {
+    // checks that `points` go around a convex shape, points in a line along a side are fine
+    pub fn new(points: Vec<Vector2>) -> Result<Polygon, String> {
+        if points.len() < 3 {
+            return Err(format!("A Polygon needs at least 3 points, got {}", points.len()));
+        }
+
+        // a point repeated right after itself doesn't turn anywhere
+        let corners = (0..points.len())
+            .filter(|&i| points[i] != points[(i + 1) % points.len()])
+            .map(|i| points[i])
+            .collect::<Vec<_>>();
+
+        let n = corners.len();
+        let mut turning = 0.0;
+        let mut total_turn = 0.0;
+        for i in 0..n {
+            let (a, b, c) = (corners[i], corners[(i + 1) % n], corners[(i + 2) % n]);
+            let (incoming, outgoing) = (b - a, c - b);
+            let turn = incoming.cross(outgoing);
+            if turn == 0.0 {
+                // in a line is fine, doubling back along it isn't
+                if incoming.dot(outgoing) < 0.0 {
+                    return Err("Polygon points must go around a convex shape".into());
+                }
+                continue;
+            }
+            if turning == 0.0 {
+                turning = turn.signum();
+            } else if turn.signum() != turning {
+                return Err("Polygon points must go around a convex shape".into());
+            }
+            total_turn += turn.atan2(incoming.dot(outgoing));
+        }
+        if turning == 0.0 {
+            return Err("Polygon points can't all be on one line".into());
+        }
+        // turning the same way at every point, a star still goes around more than once
+        if (total_turn.abs() - TAU).abs() > 0.01 {
+            return Err("Polygon points must go around a convex shape once".into());
+        }
+
+        Ok(Polygon { points })
+    }
+
+    // for points that are convex already, like the corners of a transformed rectangle
+    pub fn from_convex(points: Vec<Vector2>) -> Polygon {
+        Polygon { points }
+    }
+
+    pub fn points(&self) -> &[Vector2] {
+        &self.points
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = Segment> + '_ {
+        let n = self.points.len();
+        (0..n).map(move |i| Segment::new(self.points[i], self.points[(i + 1) % n]))
+    }
+
+    fn signed_area(&self) -> f32 {
+        geometry::signed_area(&self.points)
+    }
+
+    pub fn contains(&self, point: Vector2) -> bool {
+        let winding = self.signed_area().signum();
+        self.edges().all(|edge| (edge.b() - edge.a()).cross(point - edge.a()) * winding >= 0.0)
+    }
+
+    // on the nearest edge, or where it is when the polygon covers it
+    pub fn closest_point(&self, point: Vector2) -> Vector2 {
+        if self.contains(point) {
+            return point;
+        }
+        self.edges()
+            .map(|edge| edge.closest_point(point))
+            .min_by(|a, b| (*a - point).length().total_cmp(&(*b - point).length()))
+            .unwrap_or(point)
+    }
+
+    pub fn centroid(&self) -> Vector2 {
+        let area = self.signed_area();
+        if area == 0.0 {
+            let sum = self.points.iter().fold(Vector2::new(0.0, 0.0), |sum, &p| sum + p);
+            return sum * (1.0 / self.points.len() as f32);
+        }
+
+        let weighted = self.edges().fold(Vector2::new(0.0, 0.0), |sum, edge| {
+            sum + (edge.a() + edge.b()) * edge.a().cross(edge.b())
+        });
+        weighted * (1.0 / (6.0 * area))
+    }
+
+    pub fn transform(&self, matrix: &Matrix3) -> Polygon {
+        Polygon {
+            points: self.points.iter().map(|&p| geometry::transform_point(matrix, p)).collect(),
+        }
+    }
+}
+
+impl LuaExportsTable<'_> for Polygon {
+    const EXPORT_NAME: &'static str = "Polygon";
+
+    fn create_exports_table(lua: &Lua) -> LuaResult<LuaTable<'_>> {
+        let polygon_new = |_, points: Vec<LuaUserDataRef<Vector2>>| {
+            Polygon::new(points.iter().map(|p| **p).collect()).map_err(LuaError::RuntimeError)
+        };
+
+        TableBuilder::new(lua)?
+            .with_function("new", polygon_new)?
+            .build_readonly()
+    }
+}
+
+impl LuaUserData for Polygon {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_field_method_get("Points", |_, this| Ok(this.points.clone()));
+        fields.add_field_method_get("Area", |_, this| Ok(this.signed_area().abs()));
+        fields.add_field_method_get("Centroid", |_, this| Ok(this.centroid()));
+        fields.add_field_method_get("Bounds", |_, this| Ok(Rect::bounding(&this.points)));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("Contains", |_, this, point: LuaUserDataRef<Vector2>| Ok(this.contains(*point)));
+        methods.add_method("Intersects", |_, this, other: Geometry| Ok(Geometry::Polygon(this.clone()).intersects(&other)));
+        methods.add_method("ClosestPoint", |_, this, point: LuaUserDataRef<Vector2>| Ok(this.closest_point(*point)));
+        methods.add_method("Transform", |_, this, matrix: LuaUserDataRef<Matrix3>| Ok(this.transform(&matrix)));
+
+        methods.add_meta_method(LuaMetaMethod::Eq, userdata_impl_eq);
+        methods.add_meta_method(LuaMetaMethod::ToString, userdata_impl_to_string);
+    }
+}
+
+impl fmt::Display for Polygon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let points = self.points.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        write!(f, "{}", points.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Circle;
+
+    fn polygon(points: &[(f32, f32)]) -> Result<Polygon, String> {
+        Polygon::new(points.iter().map(|&(x, y)| Vector2::new(x, y)).collect())
+    }
+
+    #[test]
+    fn only_convex_outlines() {
+        assert!(polygon(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]).is_ok());
+        assert!(polygon(&[(0.0, 10.0), (10.0, 10.0), (10.0, 0.0), (0.0, 0.0)]).is_ok());
+        // extra points along a side or repeated ones don't change the shape
+        assert!(polygon(&[(0.0, 0.0), (5.0, 0.0), (10.0, 0.0), (10.0, 10.0), (10.0, 10.0), (0.0, 10.0)]).is_ok());
+
+        assert!(polygon(&[(0.0, 0.0), (10.0, 0.0), (5.0, 2.0), (10.0, 10.0), (0.0, 10.0)]).is_err());
+        assert!(polygon(&[(0.0, 0.0), (5.0, 5.0), (10.0, 10.0)]).is_err());
+        assert!(polygon(&[(0.0, 0.0), (10.0, 0.0), (5.0, 0.0), (5.0, 10.0)]).is_err());
+        assert!(polygon(&[(0.0, 0.0), (10.0, 0.0)]).is_err());
+    }
+
+    #[test]
+    fn stars_are_not_convex() {
+        let corner = |i: usize| {
+            let angle = i as f32 * TAU / 5.0;
+            (angle.cos() * 10.0, angle.sin() * 10.0)
+        };
+        assert!(polygon(&[corner(0), corner(1), corner(2), corner(3), corner(4)]).is_ok());
+        assert!(polygon(&[corner(0), corner(2), corner(4), corner(1), corner(3)]).is_err());
+
+        // around a square twice
+        let square = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)];
+        assert!(polygon(&[square, square].concat()).is_err());
+    }
+
+    #[test]
+    fn containment_and_closest_points() {
+        let triangle = polygon(&[(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)]).unwrap();
+        assert!(triangle.contains(Vector2::new(2.0, 2.0)));
+        assert!(triangle.contains(Vector2::new(5.0, 5.0)));
+        assert!(!triangle.contains(Vector2::new(6.0, 6.0)));
+
+        assert_eq!(triangle.closest_point(Vector2::new(2.0, 2.0)), Vector2::new(2.0, 2.0));
+        assert_eq!(triangle.closest_point(Vector2::new(5.0, -3.0)), Vector2::new(5.0, 0.0));
+        assert_eq!(triangle.closest_point(Vector2::new(-2.0, -2.0)), Vector2::new(0.0, 0.0));
+        assert!((triangle.centroid() - Vector2::new(10.0 / 3.0, 10.0 / 3.0)).length() < 1e-5);
+    }
+
+    #[test]
+    fn intersections() {
+        let diamond = Geometry::Polygon(polygon(&[(5.0, 0.0), (10.0, 5.0), (5.0, 10.0), (0.0, 5.0)]).unwrap());
+        let touching = Geometry::Polygon(polygon(&[(10.0, 5.0), (15.0, 0.0), (15.0, 10.0)]).unwrap());
+        // inside the bounds of the diamond, but past its corner
+        let corner = Geometry::Rect(Rect::new(Vector2::new(8.0, 8.0), Vector2::new(12.0, 12.0)));
+
+        assert!(diamond.intersects(&touching));
+        assert!(!diamond.intersects(&corner));
+        assert!(diamond.intersects(&Geometry::Circle(Circle::new(Vector2::new(9.0, 9.0), 3.0).unwrap())));
+        assert!(!diamond.intersects(&Geometry::Circle(Circle::new(Vector2::new(9.0, 9.0), 2.0).unwrap())));
+        assert!(diamond.intersects(&Geometry::Segment(Segment::new(Vector2::new(-5.0, 5.0), Vector2::new(20.0, 5.0)))));
+    }
+
+    #[test]
+    fn transforms_every_point() {
+        let triangle = polygon(&[(0.0, 0.0), (2.0, 0.0), (0.0, 1.0)]).unwrap();
+        let moved = triangle.transform(&(Matrix3::translation(10.0, 0.0) * Matrix3::scaling(2.0, 2.0)));
+        assert_eq!(moved.points(), [Vector2::new(10.0, 0.0), Vector2::new(14.0, 0.0), Vector2::new(10.0, 2.0)]);
+        assert_eq!(moved.signed_area(), triangle.signed_area() * 4.0);
+    }
+}
@@ -0,0 +1,197 @@
+use core::fmt;
+
+use mlua::prelude::*;
+use crate::lune::table_builder::*;
+use crate::lune::exports::*;
+use crate::lune::userdata::*;
+
+use super::geometry::{self, Geometry};
+use super::{Matrix3, Polygon, Vector2};
+
+/**
+    An axis-aligned rectangle, as its smallest and largest corner.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    min: Vector2,
+    max: Vector2,
+}
+
+impl Rect {
+    // any two opposite corners, in either order
+    pub fn new(a: Vector2, b: Vector2) -> Rect {
+        Rect {
+            min: Vector2::new(a.get_x().min(b.get_x()), a.get_y().min(b.get_y())),
+            max: Vector2::new(a.get_x().max(b.get_x()), a.get_y().max(b.get_y())),
+        }
+    }
+
+    // the smallest rectangle around `points`, which can't be empty
+    pub fn bounding(points: &[Vector2]) -> Rect {
+        points.iter().fold(Rect::new(points[0], points[0]), |rect, &p| rect.union(&Rect::new(p, p)))
+    }
+
+    pub fn min(&self) -> Vector2 {
+        self.min
+    }
+
+    pub fn max(&self) -> Vector2 {
+        self.max
+    }
+
+    pub fn center(&self) -> Vector2 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn size(&self) -> Vector2 {
+        self.max - self.min
+    }
+
+    // grown by `amount` on every side
+    pub fn grow(&self, amount: f32) -> Rect {
+        let amount = Vector2::new(amount, amount);
+        Rect { min: self.min - amount, max: self.max + amount }
+    }
+
+    // counter-clockwise in math terms, starting at the smallest one
+    pub fn corners(&self) -> [Vector2; 4] {
+        [
+            self.min,
+            Vector2::new(self.max.get_x(), self.min.get_y()),
+            self.max,
+            Vector2::new(self.min.get_x(), self.max.get_y()),
+        ]
+    }
+
+    pub fn contains(&self, point: Vector2) -> bool {
+        (self.min.get_x()..=self.max.get_x()).contains(&point.get_x())
+            && (self.min.get_y()..=self.max.get_y()).contains(&point.get_y())
+    }
+
+    pub fn intersects(&self, other: &Rect) -> bool {
+        self.min.get_x() <= other.max.get_x() && other.min.get_x() <= self.max.get_x()
+            && self.min.get_y() <= other.max.get_y() && other.min.get_y() <= self.max.get_y()
+    }
+
+    pub fn union(&self, other: &Rect) -> Rect {
+        Rect {
+            min: Vector2::new(self.min.get_x().min(other.min.get_x()), self.min.get_y().min(other.min.get_y())),
+            max: Vector2::new(self.max.get_x().max(other.max.get_x()), self.max.get_y().max(other.max.get_y())),
+        }
+    }
+
+    // the area both cover, `None` when they don't touch
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        self.intersects(other).then(|| Rect {
+            min: Vector2::new(self.min.get_x().max(other.min.get_x()), self.min.get_y().max(other.min.get_y())),
+            max: Vector2::new(self.max.get_x().min(other.max.get_x()), self.max.get_y().min(other.max.get_y())),
+        })
+    }
+
+    // clamped into the rectangle, so a point already in it stays put
+    pub fn closest_point(&self, point: Vector2) -> Vector2 {
+        Vector2::new(
+            point.get_x().clamp(self.min.get_x(), self.max.get_x()),
+            point.get_y().clamp(self.min.get_y(), self.max.get_y()),
+        )
+    }
+}
+
+impl LuaExportsTable<'_> for Rect {
+    const EXPORT_NAME: &'static str = "Rect";
+
+    fn create_exports_table(lua: &Lua) -> LuaResult<LuaTable<'_>> {
+        let rect_new = |_, (a, b): (LuaUserDataRef<Vector2>, LuaUserDataRef<Vector2>)| {
+            Ok(Rect::new(*a, *b))
+        };
+
+        let rect_from_center = |_, (center, size): (LuaUserDataRef<Vector2>, LuaUserDataRef<Vector2>)| {
+            let half = Vector2::new(size.get_x().abs() / 2.0, size.get_y().abs() / 2.0);
+            Ok(Rect::new(*center - half, *center + half))
+        };
+
+        TableBuilder::new(lua)?
+            .with_function("new", rect_new)?
+            .with_function("fromCenter", rect_from_center)?
+            .build_readonly()
+    }
+}
+
+impl LuaUserData for Rect {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_field_method_get("Min", |_, this| Ok(this.min));
+        fields.add_field_method_get("Max", |_, this| Ok(this.max));
+        fields.add_field_method_get("Center", |_, this| Ok(this.center()));
+        fields.add_field_method_get("Size", |_, this| Ok(this.size()));
+        fields.add_field_method_get("Area", |_, this| Ok(this.size().get_x() * this.size().get_y()));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("Contains", |_, this, point: LuaUserDataRef<Vector2>| Ok(this.contains(*point)));
+        methods.add_method("Intersects", |_, this, other: Geometry| Ok(Geometry::Rect(*this).intersects(&other)));
+        methods.add_method("Union", |_, this, other: LuaUserDataRef<Rect>| Ok(this.union(&other)));
+        methods.add_method("Intersection", |_, this, other: LuaUserDataRef<Rect>| Ok(this.intersection(&other)));
+        methods.add_method("ClosestPoint", |_, this, point: LuaUserDataRef<Vector2>| Ok(this.closest_point(*point)));
+
+        // a rotated rectangle isn't axis-aligned anymore, so this gives a Polygon
+        methods.add_method("Transform", |_, this, matrix: LuaUserDataRef<Matrix3>| {
+            Ok(Polygon::from_convex(this.corners().iter().map(|&p| geometry::transform_point(&matrix, p)).collect()))
+        });
+
+        methods.add_meta_method(LuaMetaMethod::Eq, userdata_impl_eq);
+        methods.add_meta_method(LuaMetaMethod::ToString, userdata_impl_to_string);
+    }
+}
+
+impl fmt::Display for Rect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, {}", self.min, self.max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Circle;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn rect(a: (f32, f32), b: (f32, f32)) -> Rect {
+        Rect::new(Vector2::new(a.0, a.1), Vector2::new(b.0, b.1))
+    }
+
+    #[test]
+    fn containment_includes_the_edges() {
+        let square = rect((10.0, 10.0), (0.0, 0.0));
+        assert_eq!((square.min(), square.max()), (Vector2::new(0.0, 0.0), Vector2::new(10.0, 10.0)));
+        assert!(square.contains(Vector2::new(5.0, 5.0)));
+        assert!(square.contains(Vector2::new(10.0, 0.0)));
+        assert!(!square.contains(Vector2::new(10.1, 5.0)));
+
+        assert_eq!(square.closest_point(Vector2::new(5.0, 5.0)), Vector2::new(5.0, 5.0));
+        assert_eq!(square.closest_point(Vector2::new(-5.0, 20.0)), Vector2::new(0.0, 10.0));
+    }
+
+    #[test]
+    fn intersections() {
+        let square = rect((0.0, 0.0), (10.0, 10.0));
+        assert_eq!(square.intersection(&rect((5.0, 5.0), (20.0, 20.0))), Some(rect((5.0, 5.0), (10.0, 10.0))));
+        assert_eq!(square.intersection(&rect((10.0, 0.0), (20.0, 10.0))), Some(rect((10.0, 0.0), (10.0, 10.0))));
+        assert_eq!(square.intersection(&rect((11.0, 0.0), (20.0, 10.0))), None);
+        assert_eq!(square.union(&rect((-5.0, 2.0), (3.0, 3.0))), rect((-5.0, 0.0), (10.0, 10.0)));
+
+        let square = Geometry::Rect(square);
+        assert!(square.intersects(&Geometry::Circle(Circle::new(Vector2::new(13.0, 14.0), 5.0).unwrap())));
+        assert!(!square.intersects(&Geometry::Circle(Circle::new(Vector2::new(13.0, 14.0), 4.9).unwrap())));
+    }
+
+    #[test]
+    fn rotating_gives_the_turned_corners() {
+        let rect = rect((0.0, 0.0), (4.0, 2.0));
+        let turned = rect.corners().map(|p| geometry::transform_point(&Matrix3::rotation_radians(FRAC_PI_2), p));
+        let expected = [(0.0, 0.0), (0.0, 4.0), (-2.0, 4.0), (-2.0, 0.0)];
+        for (point, (x, y)) in turned.iter().zip(expected) {
+            assert!((*point - Vector2::new(x, y)).length() < 1e-5, "{} instead of {}, {}", point, x, y);
+        }
+        assert_eq!(Rect::bounding(&turned).size().get_x().round(), 2.0);
+    }
+}
@@ -0,0 +1,165 @@
+use core::fmt;
+
+use mlua::prelude::*;
+use crate::lune::table_builder::*;
+use crate::lune::exports::*;
+use crate::lune::userdata::*;
+
+use super::geometry::{self, Geometry};
+use super::{Matrix3, Rect, Vector2};
+
+/**
+    The straight line between two points.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    a: Vector2,
+    b: Vector2,
+}
+
+impl Segment {
+    pub fn new(a: Vector2, b: Vector2) -> Segment {
+        Segment { a, b }
+    }
+
+    pub fn a(&self) -> Vector2 {
+        self.a
+    }
+
+    pub fn b(&self) -> Vector2 {
+        self.b
+    }
+
+    pub fn closest_point(&self, point: Vector2) -> Vector2 {
+        let edge = self.b - self.a;
+        let length_squared = edge.dot(edge);
+        if length_squared <= geometry::EPSILON * geometry::EPSILON {
+            return self.a;
+        }
+        let t = ((point - self.a).dot(edge) / length_squared).clamp(0.0, 1.0);
+        self.a + edge * t
+    }
+
+    /**
+        Where the two segments cross, or for segments on the same line that
+        overlap, the shared point closest to `a`. `None` when they don't meet.
+    */
+    pub fn intersection(&self, other: &Segment) -> Option<Vector2> {
+        let (r, s) = (self.b - self.a, other.b - other.a);
+        let offset = other.a - self.a;
+        let denominator = r.cross(s);
+
+        // the sine of the angle between them is too small to tell them from parallel
+        if denominator.abs() <= geometry::EPSILON * r.length() * s.length() {
+            // so they only meet on the same line, which a segment with no length always is on
+            let off_line = |direction: Vector2| {
+                let length = direction.length();
+                length > geometry::EPSILON && (offset.cross(direction) / length).abs() > geometry::EPSILON
+            };
+            if off_line(r) || off_line(s) {
+                return None;
+            }
+            let length_squared = r.dot(r);
+            if length_squared <= geometry::EPSILON * geometry::EPSILON {
+                return ((other.closest_point(self.a) - self.a).length() <= geometry::EPSILON).then_some(self.a);
+            }
+            let start = offset.dot(r) / length_squared;
+            let end = start + s.dot(r) / length_squared;
+            let (low, high) = (start.min(end), start.max(end));
+            return (high >= 0.0 && low <= 1.0).then(|| self.a + r * low.max(0.0));
+        }
+
+        let t = offset.cross(s) / denominator;
+        let u = offset.cross(r) / denominator;
+        ((0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u)).then(|| self.a + r * t)
+    }
+
+    pub fn transform(&self, matrix: &Matrix3) -> Segment {
+        Segment {
+            a: geometry::transform_point(matrix, self.a),
+            b: geometry::transform_point(matrix, self.b),
+        }
+    }
+}
+
+impl LuaExportsTable<'_> for Segment {
+    const EXPORT_NAME: &'static str = "Segment";
+
+    fn create_exports_table(lua: &Lua) -> LuaResult<LuaTable<'_>> {
+        let segment_new = |_, (a, b): (LuaUserDataRef<Vector2>, LuaUserDataRef<Vector2>)| {
+            Ok(Segment::new(*a, *b))
+        };
+
+        TableBuilder::new(lua)?
+            .with_function("new", segment_new)?
+            .build_readonly()
+    }
+}
+
+impl LuaUserData for Segment {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_field_method_get("A", |_, this| Ok(this.a));
+        fields.add_field_method_get("B", |_, this| Ok(this.b));
+        fields.add_field_method_get("Length", |_, this| Ok((this.b - this.a).length()));
+        fields.add_field_method_get("Direction", |_, this| Ok((this.b - this.a).normalized()));
+        fields.add_field_method_get("Bounds", |_, this| Ok(Rect::new(this.a, this.b)));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        // a point is on the segment when the closest point on it is the point itself
+        methods.add_method("Contains", |_, this, point: LuaUserDataRef<Vector2>| {
+            Ok((this.closest_point(*point) - *point).length() <= geometry::EPSILON)
+        });
+        methods.add_method("Intersects", |_, this, other: Geometry| Ok(Geometry::Segment(*this).intersects(&other)));
+        methods.add_method("Intersection", |_, this, other: LuaUserDataRef<Segment>| Ok(this.intersection(&other)));
+        methods.add_method("ClosestPoint", |_, this, point: LuaUserDataRef<Vector2>| Ok(this.closest_point(*point)));
+        methods.add_method("Transform", |_, this, matrix: LuaUserDataRef<Matrix3>| Ok(this.transform(&matrix)));
+
+        methods.add_meta_method(LuaMetaMethod::Eq, userdata_impl_eq);
+        methods.add_meta_method(LuaMetaMethod::ToString, userdata_impl_to_string);
+    }
+}
+
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, {}", self.a, self.b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(a: (f32, f32), b: (f32, f32)) -> Segment {
+        Segment::new(Vector2::new(a.0, a.1), Vector2::new(b.0, b.1))
+    }
+
+    #[test]
+    fn crossing_segments() {
+        let point = segment((0.0, 0.0), (10.0, 10.0)).intersection(&segment((0.0, 10.0), (10.0, 0.0)));
+        assert_eq!(point, Some(Vector2::new(5.0, 5.0)));
+        assert_eq!(segment((0.0, 0.0), (10.0, 0.0)).intersection(&segment((0.0, 1.0), (10.0, 1.0))), None);
+        assert_eq!(segment((0.0, 0.0), (4.0, 4.0)).intersection(&segment((0.0, 10.0), (10.0, 0.0))), None);
+    }
+
+    #[test]
+    fn segments_on_one_line() {
+        let line = segment((0.0, 0.0), (100.0, 0.0));
+        assert_eq!(line.intersection(&segment((50.0, 0.0), (150.0, 0.0))), Some(Vector2::new(50.0, 0.0)));
+        assert_eq!(line.intersection(&segment((150.0, 0.0), (200.0, 0.0))), None);
+
+        // off by less than a hair counts as the same line
+        let nearly = segment((50.0, 0.00001), (150.0, 0.00002));
+        assert_eq!(line.intersection(&nearly), Some(Vector2::new(50.0, 0.0)));
+    }
+
+    #[test]
+    fn segments_without_length() {
+        let point = segment((3.0, 3.0), (3.0, 3.0));
+        let diagonal = segment((0.0, 0.0), (10.0, 10.0));
+        assert_eq!(point.intersection(&diagonal), Some(Vector2::new(3.0, 3.0)));
+        assert_eq!(diagonal.intersection(&point).map(|p| (p - Vector2::new(3.0, 3.0)).length() < 1e-4), Some(true));
+        assert_eq!(point.intersection(&segment((0.0, 1.0), (10.0, 11.0))), None);
+        assert_eq!(point.closest_point(Vector2::new(9.0, 0.0)), Vector2::new(3.0, 3.0));
+    }
+}
@@ -25,12 +25,42 @@ impl Vector2 {
     pub fn get_y(&self) -> f32 {
         self.y
     }
+
+    pub fn lerp(self, other: Vector2, alpha: f32) -> Vector2 {
+        Vector2 {
+            x: self.x + (other.x - self.x) * alpha, y: self.y + (other.y - self.y) * alpha
+        }
+    }
+
+    pub fn dot(self, other: Vector2) -> f32 {
+        self.x * other.x + self.y * other.y
+    }
+
+    // z component of the 3D cross product, positive when `other` is counter-clockwise from `self`
+    pub fn cross(self, other: Vector2) -> f32 {
+        self.x * other.y - self.y * other.x
+    }
+
+    pub fn length(self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    // turned a quarter counter-clockwise
+    pub fn perp(self) -> Vector2 {
+        Vector2 { x: -self.y, y: self.x }
+    }
+
+    // the zero vector stays zero rather than turning into NaN
+    pub fn normalized(self) -> Vector2 {
+        let length = self.length();
+        if length > 0.0 { self * (1.0 / length) } else { self }
+    }
 }
 
 impl LuaExportsTable<'_> for Vector2 {
     const EXPORT_NAME: &'static str = "Vector2";
 
-    fn create_exports_table(lua: &Lua) -> LuaResult<LuaTable> {
+    fn create_exports_table(lua: &Lua) -> LuaResult<LuaTable<'_>> {
         let vector2_new = |_, (x, y): (Option<f32>, Option<f32>)| {
             Ok(Vector2 {
                 x: x.unwrap_or(0.0), y: y.unwrap_or(0.0),
@@ -63,17 +93,15 @@ impl LuaUserData for Vector2 {
 
     fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
         methods.add_method("Lerp", |_, this, ( v, alpha): (LuaUserDataRef<Vector2>, f32)| {
-            Ok(Vector2 {
-                x: this.x + (v.x - this.x) * alpha, y: this.y + (v.y - this.y) * alpha
-            })
+            Ok(this.lerp(*v, alpha))
         });
 
         methods.add_method("Dot", |_, this, v: LuaUserDataRef<Vector2>| {
-            Ok(this.x * v.x + this.y * v.y)
+            Ok(this.dot(*v))
         });
 
         methods.add_method("Cross", |_, this, v: LuaUserDataRef<Vector2>| {
-            Ok(this.x * v.y - this.y * v.x)
+            Ok(this.cross(*v))
         });
 
         methods.add_method("Distance", |_, this, v: LuaUserDataRef<Vector2>| {
@@ -115,7 +143,7 @@ impl LuaUserData for Vector2 {
         methods.add_meta_method(LuaMetaMethod::Add, userdata_impl_add);
         methods.add_meta_method(LuaMetaMethod::Sub, userdata_impl_sub);
 
-        methods.add_meta_method_mut(LuaMetaMethod::Mul, |lua, this, value: LuaValue| {
+        methods.add_meta_method_mut(LuaMetaMethod::Mul, |_, this, value: LuaValue| {
             match value {
                 LuaValue::Number(n) => {
                     let factor = n as f32;
@@ -155,7 +183,7 @@ impl LuaUserData for Vector2 {
             }
         });
 
-        methods.add_meta_method_mut(LuaMetaMethod::Div, |lua, this, value: LuaValue| {
+        methods.add_meta_method_mut(LuaMetaMethod::Div, |_, this, value: LuaValue| {
             match value {
                 LuaValue::Number(n) => {
                     let divisor = n as f32;
@@ -231,4 +259,24 @@ impl ops::Sub for Vector2 {
             x: self.x - rhs.x, y: self.y - rhs.y
         }
     }
-}
\ No newline at end of file
+}
+
+impl ops::Mul<f32> for Vector2 {
+    type Output = Vector2;
+
+    fn mul(self, rhs: f32) -> Vector2 {
+        Vector2 {
+            x: self.x * rhs, y: self.y * rhs
+        }
+    }
+}
+
+impl ops::Neg for Vector2 {
+    type Output = Vector2;
+
+    fn neg(self) -> Vector2 {
+        Vector2 {
+            x: -self.x, y: -self.y
+        }
+    }
+}
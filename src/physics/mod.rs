@@ -0,0 +1,561 @@
+mod query;
+pub mod shape;
+pub mod spatial_hash;
+pub use shape::{Manifold, Shape};
+pub use spatial_hash::SpatialHash;
+
+use std::collections::BTreeSet;
+
+use mlua::prelude::*;
+use mlua::AppDataRefMut;
+
+use crate::engine::component::collider::ColliderData;
+use crate::engine::component::rigid_body::BodyType;
+use crate::engine::component::{self, Component, ComponentHandle};
+use crate::engine::gameobject::{scene_mut, GameObject, Scene};
+use crate::engine::transform;
+use crate::lune::signal::Signal;
+use crate::lune::table_builder::TableBuilder;
+use crate::math::{Rect, Vector2};
+
+// pixels per second squared, down the screen
+const DEFAULT_GRAVITY: (f32, f32) = (0.0, 980.0);
+const VELOCITY_ITERATIONS: usize = 10;
+// overlap left alone so resting contacts stay touching instead of jittering
+const SLOP: f32 = 0.5;
+// how much of the remaining overlap is pushed out each step
+const POSITION_CORRECTION: f32 = 0.4;
+// slower impacts than this don't bounce, so resting bodies settle
+const RESTITUTION_THRESHOLD: f32 = 40.0;
+// two contact points closer than this allows are solved one after the other instead of together
+const MAX_CONDITION: f32 = 1000.0;
+
+// a collider, as the GameObject it is on and its component id
+type ColliderKey = (GameObject, u32);
+
+/**
+    Settings of the physics world, the pairs of colliders that touched in
+    the last step and the colliders queries look through. Lives in the app
+    data of the [`Lua`] instance.
+*/
+pub struct Physics {
+    pub gravity: Vector2,
+    // the smaller key first, so a pair is the same whichever order it was found in
+    touching: BTreeSet<(ColliderKey, ColliderKey)>,
+    // built on the first query after something moved or changed
+    queries: Option<query::QueryWorld>,
+}
+
+impl Default for Physics {
+    fn default() -> Physics {
+        Physics {
+            gravity: Vector2::new(DEFAULT_GRAVITY.0, DEFAULT_GRAVITY.1),
+            touching: BTreeSet::new(),
+            queries: None,
+        }
+    }
+}
+
+fn physics_mut(lua: &Lua) -> LuaResult<AppDataRefMut<'_, Physics>> {
+    lua.app_data_mut::<Physics>()
+        .ok_or_else(|| LuaError::RuntimeError("Bee2D has not been initialized".into()))
+}
+
+// has the next query look at the scene again, after something that could move or change a collider
+pub fn invalidate_queries(lua: &Lua) {
+    if let Some(mut physics) = lua.app_data_mut::<Physics>() {
+        physics.queries = None;
+    }
+}
+
+struct Body {
+    object: GameObject,
+    id: u32,
+    position: Vector2,
+    angle: f32,
+    velocity: Vector2,
+    angular_velocity: f32,
+    inverse_mass: f32,
+    inverse_inertia: f32,
+}
+
+// what the solver sees in place of colliders that don't move
+const STATIC_BODY: usize = 0;
+
+struct ColliderInstance {
+    key: ColliderKey,
+    body: usize,
+    // whether it is on a dynamic or kinematic body
+    moves: bool,
+    shape: Shape,
+    bounds: Rect,
+    is_trigger: bool,
+    friction: f32,
+    restitution: f32,
+    layer: u32,
+    mask: u32,
+}
+
+struct ContactPoint {
+    // from the centers of the bodies
+    ra: Vector2,
+    rb: Vector2,
+    depth: f32,
+    normal_mass: f32,
+    tangent_mass: f32,
+    normal_impulse: f32,
+    tangent_impulse: f32,
+    // the velocity to bounce back with
+    bounce: f32,
+}
+
+struct Contact {
+    a: usize,
+    b: usize,
+    normal: Vector2,
+    friction: f32,
+    points: Vec<ContactPoint>,
+    // how an impulse along the normal at each of two points changes the velocity at both, as k11, k12 and k22
+    block: Option<(f32, f32, f32)>,
+}
+
+fn cross_scalar(w: f32, r: Vector2) -> Vector2 {
+    Vector2::new(-w * r.get_y(), w * r.get_x())
+}
+
+// the shapes of every collider on `object`, with their component ids
+fn collider_shapes(scene: &mut Scene, object: GameObject) -> Vec<(u32, Shape)> {
+    let matrix = transform::global_matrix(scene, object);
+    scene.component_ids(object)
+        .into_iter()
+        .filter_map(|id| match scene.component(object, id) {
+            Some(Component::Collider(collider)) => Some((id, collider.world_shape(&matrix)?)),
+            _ => None,
+        })
+        .collect()
+}
+
+/**
+    The point a body on `object` turns around and one over its rotational
+    inertia per unit of mass, approximated from the bounds of its colliders.
+    A body without colliders doesn't turn.
+*/
+pub fn body_frame(scene: &mut Scene, object: GameObject) -> (Vector2, f32) {
+    let matrix = transform::global_matrix(scene, object);
+    let center = Vector2::new(matrix.m02, matrix.m12);
+
+    let bounds = collider_shapes(scene, object)
+        .into_iter()
+        .map(|(_, shape)| shape.bounds())
+        .reduce(|a, b| a.union(&b));
+    let Some(bounds) = bounds else {
+        return (center, 0.0);
+    };
+
+    let size = bounds.size();
+    let offset = bounds.center() - center;
+    let inertia = size.dot(size) / 12.0 + offset.dot(offset);
+    (center, if inertia > 0.0 { 1.0 / inertia } else { 0.0 })
+}
+
+// every body and collider on `objects`, the static body first
+fn gather(scene: &mut Scene, objects: &[GameObject]) -> (Vec<Body>, Vec<ColliderInstance>) {
+    let mut bodies = vec![Body {
+        object: scene.root(),
+        id: 0,
+        position: Vector2::new(0.0, 0.0),
+        angle: 0.0,
+        velocity: Vector2::new(0.0, 0.0),
+        angular_velocity: 0.0,
+        inverse_mass: 0.0,
+        inverse_inertia: 0.0,
+    }];
+    let mut colliders = Vec::new();
+
+    for &object in objects {
+        let ids = scene.component_ids(object);
+        let body = ids.iter().find_map(|&id| match scene.component(object, id) {
+            Some(Component::RigidBody(body)) if body.body_type != BodyType::Static => Some((id, body.clone())),
+            _ => None,
+        });
+
+        let index = match body {
+            Some((id, body)) => {
+                let (position, unit_inverse_inertia) = body_frame(scene, object);
+                let angle = transform::global_matrix(scene, object).rotation();
+                let (inverse_mass, inverse_inertia) = match body.body_type {
+                    BodyType::Dynamic if body.fixed_rotation => (1.0 / body.mass, 0.0),
+                    BodyType::Dynamic => (1.0 / body.mass, unit_inverse_inertia / body.mass),
+                    _ => (0.0, 0.0),
+                };
+                bodies.push(Body {
+                    object,
+                    id,
+                    position,
+                    angle,
+                    velocity: body.velocity,
+                    angular_velocity: if body.fixed_rotation { 0.0 } else { body.angular_velocity },
+                    inverse_mass,
+                    inverse_inertia,
+                });
+                bodies.len() - 1
+            }
+            None => STATIC_BODY,
+        };
+
+        for (id, shape) in collider_shapes(scene, object) {
+            let Some(Component::Collider(collider)) = scene.component(object, id) else {
+                continue;
+            };
+            colliders.push(ColliderInstance {
+                key: (object, id),
+                body: index,
+                moves: index != STATIC_BODY,
+                bounds: shape.bounds(),
+                shape,
+                is_trigger: collider.is_trigger,
+                friction: collider.friction,
+                restitution: collider.restitution,
+                layer: collider.layer_bit(),
+                mask: collider.mask,
+            });
+        }
+    }
+
+    (bodies, colliders)
+}
+
+/**
+    Pairs of colliders whose bounds overlap, found by sweeping along x.
+    Pairs where neither collider moves or whose layers don't match are
+    left out.
+*/
+fn broadphase(colliders: &[ColliderInstance]) -> Vec<(usize, usize)> {
+    let mut order = (0..colliders.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| colliders[a].bounds.min().get_x().total_cmp(&colliders[b].bounds.min().get_x()));
+
+    let mut pairs = Vec::new();
+    for (i, &a) in order.iter().enumerate() {
+        for &b in &order[i + 1..] {
+            if colliders[b].bounds.min().get_x() > colliders[a].bounds.max().get_x() {
+                break;
+            }
+            let (ca, cb) = (&colliders[a], &colliders[b]);
+            let same_object = ca.key.0 == cb.key.0;
+            let layers_match = ca.mask & cb.layer != 0 && cb.mask & ca.layer != 0;
+            if (ca.moves || cb.moves) && !same_object && layers_match && ca.bounds.intersects(&cb.bounds) {
+                pairs.push((a.min(b), a.max(b)));
+            }
+        }
+    }
+    pairs
+}
+
+fn prepare_contact(bodies: &[Body], a: &ColliderInstance, b: &ColliderInstance, manifold: Manifold) -> Contact {
+    let (body_a, body_b) = (&bodies[a.body], &bodies[b.body]);
+    let normal = manifold.normal;
+    let tangent = normal.perp();
+    let restitution = a.restitution.max(b.restitution);
+
+    let points = manifold.points.iter()
+        .map(|&(point, depth)| {
+            let ra = point - body_a.position;
+            let rb = point - body_b.position;
+            let mass_along = |direction: Vector2| {
+                let (rna, rnb) = (ra.cross(direction), rb.cross(direction));
+                let k = body_a.inverse_mass + body_b.inverse_mass
+                    + body_a.inverse_inertia * rna * rna + body_b.inverse_inertia * rnb * rnb;
+                if k > 0.0 { 1.0 / k } else { 0.0 }
+            };
+
+            let relative = body_b.velocity + cross_scalar(body_b.angular_velocity, rb)
+                - body_a.velocity - cross_scalar(body_a.angular_velocity, ra);
+            let approach = relative.dot(normal);
+
+            ContactPoint {
+                ra,
+                rb,
+                depth,
+                normal_mass: mass_along(normal),
+                tangent_mass: mass_along(tangent),
+                normal_impulse: 0.0,
+                tangent_impulse: 0.0,
+                bounce: if approach < -RESTITUTION_THRESHOLD { -restitution * approach } else { 0.0 },
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let block = match points.as_slice() {
+        [p, q] => {
+            let k = |p: &ContactPoint, q: &ContactPoint| {
+                body_a.inverse_mass + body_b.inverse_mass
+                    + body_a.inverse_inertia * p.ra.cross(normal) * q.ra.cross(normal)
+                    + body_b.inverse_inertia * p.rb.cross(normal) * q.rb.cross(normal)
+            };
+            let (k11, k12, k22) = (k(p, p), k(p, q), k(q, q));
+            (k11 * k11 < MAX_CONDITION * (k11 * k22 - k12 * k12)).then_some((k11, k12, k22))
+        }
+        _ => None,
+    };
+
+    Contact {
+        a: a.body,
+        b: b.body,
+        normal,
+        friction: (a.friction * b.friction).sqrt(),
+        points,
+        block,
+    }
+}
+
+fn apply_impulse(bodies: &mut [Body], contact: &Contact, point: usize, impulse: Vector2) {
+    let (ra, rb) = (contact.points[point].ra, contact.points[point].rb);
+
+    let a = &mut bodies[contact.a];
+    a.velocity = a.velocity - impulse * a.inverse_mass;
+    a.angular_velocity -= a.inverse_inertia * ra.cross(impulse);
+
+    let b = &mut bodies[contact.b];
+    b.velocity = b.velocity + impulse * b.inverse_mass;
+    b.angular_velocity += b.inverse_inertia * rb.cross(impulse);
+}
+
+// velocity of `b` relative to `a` at a point of the contact
+fn relative_velocity(bodies: &[Body], contact: &Contact, point: usize) -> Vector2 {
+    let (a, b) = (&bodies[contact.a], &bodies[contact.b]);
+    let point = &contact.points[point];
+    b.velocity + cross_scalar(b.angular_velocity, point.rb) - a.velocity - cross_scalar(a.angular_velocity, point.ra)
+}
+
+/**
+    Solves the normal impulses of both points of a contact at once, so
+    neither is favored and a body resting on two points doesn't start to
+    turn. Tries both points pushing, then each alone, then none, and keeps
+    the first where no impulse pulls and no point is left approaching.
+*/
+fn solve_block(bodies: &mut [Body], contact: &mut Contact, (k11, k12, k22): (f32, f32, f32)) {
+    let normal = contact.normal;
+    let accumulated = (contact.points[0].normal_impulse, contact.points[1].normal_impulse);
+    let approach = |i: usize| relative_velocity(bodies, contact, i).dot(normal) - contact.points[i].bounce;
+    // the approach velocities left once the accumulated impulses are taken out
+    let b1 = approach(0) - (k11 * accumulated.0 + k12 * accumulated.1);
+    let b2 = approach(1) - (k12 * accumulated.0 + k22 * accumulated.1);
+
+    let determinant = k11 * k22 - k12 * k12;
+    let both = ((k12 * b2 - k22 * b1) / determinant, (k12 * b1 - k11 * b2) / determinant);
+    let first = (-b1 / k11, 0.0);
+    let second = (0.0, -b2 / k22);
+
+    let total = if both.0 >= 0.0 && both.1 >= 0.0 {
+        both
+    } else if first.0 >= 0.0 && k12 * first.0 + b2 >= 0.0 {
+        first
+    } else if second.1 >= 0.0 && k12 * second.1 + b1 >= 0.0 {
+        second
+    } else if b1 >= 0.0 && b2 >= 0.0 {
+        (0.0, 0.0)
+    } else {
+        return;
+    };
+
+    contact.points[0].normal_impulse = total.0;
+    contact.points[1].normal_impulse = total.1;
+    apply_impulse(bodies, contact, 0, normal * (total.0 - accumulated.0));
+    apply_impulse(bodies, contact, 1, normal * (total.1 - accumulated.1));
+}
+
+// sequential impulses, the accumulated impulse of each point is clamped rather than each step of it
+fn solve_velocities(bodies: &mut [Body], contacts: &mut [Contact]) {
+    for _ in 0..VELOCITY_ITERATIONS {
+        for contact in contacts.iter_mut() {
+            let normal = contact.normal;
+            let tangent = normal.perp();
+
+            if let Some(block) = contact.block {
+                solve_block(bodies, contact, block);
+            } else {
+                for i in 0..contact.points.len() {
+                    let point = &contact.points[i];
+                    let lambda = point.normal_mass * (point.bounce - relative_velocity(bodies, contact, i).dot(normal));
+                    let total = (point.normal_impulse + lambda).max(0.0);
+                    let delta = total - point.normal_impulse;
+                    contact.points[i].normal_impulse = total;
+                    apply_impulse(bodies, contact, i, normal * delta);
+                }
+            }
+
+            for i in 0..contact.points.len() {
+                let point = &contact.points[i];
+                let lambda = -point.tangent_mass * relative_velocity(bodies, contact, i).dot(tangent);
+                let limit = contact.friction * point.normal_impulse;
+                let total = (point.tangent_impulse + lambda).clamp(-limit, limit);
+                let delta = total - point.tangent_impulse;
+                contact.points[i].tangent_impulse = total;
+                apply_impulse(bodies, contact, i, tangent * delta);
+            }
+        }
+    }
+}
+
+// pushes overlapping bodies apart, shared by how easy each is to move
+fn correct_positions(bodies: &mut [Body], contacts: &[Contact]) {
+    for contact in contacts {
+        let total_inverse_mass = bodies[contact.a].inverse_mass + bodies[contact.b].inverse_mass;
+        if total_inverse_mass <= 0.0 {
+            continue;
+        }
+
+        let depth = contact.points.iter().map(|point| point.depth).fold(0.0, f32::max);
+        let correction = contact.normal * ((depth - SLOP).max(0.0) * POSITION_CORRECTION / total_inverse_mass);
+
+        let a = &mut bodies[contact.a];
+        a.position = a.position - correction * a.inverse_mass;
+        let b = &mut bodies[contact.b];
+        b.position = b.position + correction * b.inverse_mass;
+    }
+}
+
+// a signal of one collider to fire with the other
+struct TouchEvent {
+    signal: Signal,
+    other: ColliderKey,
+}
+
+impl TouchEvent {
+    fn fire(self, lua: &Lua) -> LuaResult<()> {
+        let (object, id) = self.other;
+        self.signal.fire(lua, ComponentHandle::<ColliderData>::new(object, id))
+    }
+}
+
+fn collider_signals(scene: &Scene, (object, id): ColliderKey) -> Option<(Signal, Signal)> {
+    match scene.component(object, id) {
+        Some(Component::Collider(collider)) => Some((collider.touched.clone(), collider.touch_ended.clone())),
+        _ => None,
+    }
+}
+
+/**
+    Advances every body in the scene tree by `dt` seconds: applies gravity
+    and forces, resolves collisions, moves the Transforms of the bodies and
+    then fires `Touched` and `TouchEnded` for pairs that started or stopped
+    touching.
+*/
+pub fn step(lua: &Lua, dt: f64) -> LuaResult<()> {
+    let dt = dt as f32;
+    let objects = component::active_objects(lua)?;
+
+    let events = {
+        let mut physics = physics_mut(lua)?;
+        let mut scene = scene_mut(lua)?;
+        let (mut bodies, colliders) = gather(&mut scene, &objects);
+
+        for body in bodies.iter_mut().skip(1) {
+            let Some(Component::RigidBody(data)) = scene.component(body.object, body.id) else {
+                continue;
+            };
+            if data.is_dynamic() {
+                let acceleration = physics.gravity * data.gravity_scale + data.force * body.inverse_mass;
+                body.velocity = (body.velocity + acceleration * dt) * (1.0 / (1.0 + dt * data.linear_damping));
+                body.angular_velocity = (body.angular_velocity + data.torque * body.inverse_inertia * dt)
+                    * (1.0 / (1.0 + dt * data.angular_damping));
+            }
+        }
+
+        let mut contacts = Vec::new();
+        // ordered, so that the events of a step always fire in the same order
+        let mut touching = BTreeSet::new();
+        for (a, b) in broadphase(&colliders) {
+            let (ca, cb) = (&colliders[a], &colliders[b]);
+            let Some(manifold) = ca.shape.collide(&cb.shape) else {
+                continue;
+            };
+
+            touching.insert((ca.key.min(cb.key), ca.key.max(cb.key)));
+            let dynamic = bodies[ca.body].inverse_mass > 0.0 || bodies[cb.body].inverse_mass > 0.0;
+            if dynamic && !ca.is_trigger && !cb.is_trigger {
+                contacts.push(prepare_contact(&bodies, ca, cb, manifold));
+            }
+        }
+
+        solve_velocities(&mut bodies, &mut contacts);
+
+        for body in bodies.iter_mut().skip(1) {
+            body.position = body.position + body.velocity * dt;
+            body.angle += body.angular_velocity * dt;
+        }
+        correct_positions(&mut bodies, &contacts);
+
+        for body in bodies.iter().skip(1) {
+            transform::set_global_pose(&mut scene, body.object, body.position, body.angle);
+            if let Some(Component::RigidBody(data)) = scene.component_mut(body.object, body.id) {
+                data.velocity = body.velocity;
+                data.angular_velocity = body.angular_velocity;
+                data.force = Vector2::new(0.0, 0.0);
+                data.torque = 0.0;
+            }
+        }
+
+        let mut events = Vec::new();
+        for &(a, b) in touching.difference(&physics.touching) {
+            if let (Some((touched_a, _)), Some((touched_b, _))) = (collider_signals(&scene, a), collider_signals(&scene, b)) {
+                events.push(TouchEvent { signal: touched_a, other: b });
+                events.push(TouchEvent { signal: touched_b, other: a });
+            }
+        }
+        // a collider that was removed only ends the touch for the one left
+        for &(a, b) in physics.touching.difference(&touching) {
+            if let Some((_, ended)) = collider_signals(&scene, a) {
+                events.push(TouchEvent { signal: ended, other: b });
+            }
+            if let Some((_, ended)) = collider_signals(&scene, b) {
+                events.push(TouchEvent { signal: ended, other: a });
+            }
+        }
+
+        physics.touching = touching;
+        physics.queries = None;
+        events
+    };
+
+    for event in events {
+        event.fire(lua)?;
+    }
+
+    Ok(())
+}
+
+/**
+    Creates the `Physics` library. Expects [`Physics`] to already be stored
+    in the app data of `lua`.
+
+    Queries take an optional table of params: `Mask`, the layers to look
+    at, `IncludeTriggers` and `Ignore`, a list of GameObjects to skip.
+*/
+pub fn create_api(lua: &Lua) -> LuaResult<LuaTable<'_>> {
+    let properties = TableBuilder::new(lua)?
+        .with_function("__index", |lua, (_, key): (LuaTable, LuaString)| {
+            match key.to_str()? {
+                "Gravity" => physics_mut(lua)?.gravity.into_lua(lua),
+                _ => Ok(LuaNil),
+            }
+        })?
+        .with_function("__newindex", |lua, (_, key, value): (LuaTable, LuaString, LuaValue)| {
+            match key.to_str()? {
+                "Gravity" => {
+                    physics_mut(lua)?.gravity = *LuaUserDataRef::<Vector2>::from_lua(value, lua)?;
+                    Ok(())
+                }
+                key => Err(LuaError::RuntimeError(format!("Physics.{} can't be assigned to", key))),
+            }
+        })?
+        .build_readonly()?;
+
+    TableBuilder::new(lua)?
+        .with_function("Raycast", query::raycast)?
+        .with_function("OverlapCircle", query::overlap_circle)?
+        .with_function("OverlapBox", query::overlap_box)?
+        .with_function("QueryPoint", query::query_point)?
+        .with_metatable(properties)?
+        .build_readonly()
+}
@@ -0,0 +1,170 @@
+use mlua::prelude::*;
+
+use crate::engine::component::collider::Collider;
+use crate::engine::component::{self, Component};
+use crate::engine::gameobject::{scene_mut, GameObject, Scene};
+use crate::lune::table_builder::TableBuilder;
+use crate::math::{Rect, Vector2};
+
+use super::{collider_shapes, physics_mut, ColliderKey, Shape, SpatialHash};
+
+// about the size of a default collider
+const CELL_SIZE: f32 = 128.0;
+
+struct QueryCollider {
+    key: ColliderKey,
+    shape: Shape,
+    layer: u32,
+    is_trigger: bool,
+}
+
+/**
+    Every collider in the scene tree where it was when this was built,
+    hashed by its bounds.
+*/
+pub struct QueryWorld {
+    colliders: Vec<QueryCollider>,
+    hash: SpatialHash,
+}
+
+impl QueryWorld {
+    fn build(scene: &mut Scene, objects: &[GameObject]) -> QueryWorld {
+        let mut world = QueryWorld {
+            colliders: Vec::new(),
+            hash: SpatialHash::new(CELL_SIZE),
+        };
+
+        for &object in objects {
+            for (id, shape) in collider_shapes(scene, object) {
+                let Some(Component::Collider(collider)) = scene.component(object, id) else {
+                    continue;
+                };
+                world.hash.insert(world.colliders.len(), &shape.bounds());
+                world.colliders.push(QueryCollider {
+                    key: (object, id),
+                    shape,
+                    layer: collider.layer_bit(),
+                    is_trigger: collider.is_trigger,
+                });
+            }
+        }
+
+        world
+    }
+
+    // the colliders behind `candidates` that `params` lets through
+    fn filter<'a>(&'a self, candidates: Vec<usize>, params: &'a QueryParams) -> impl Iterator<Item = &'a QueryCollider> {
+        candidates.into_iter()
+            .map(|index| &self.colliders[index])
+            .filter(|collider| params.accepts(collider))
+    }
+}
+
+pub struct QueryParams {
+    mask: u32,
+    include_triggers: bool,
+    ignore: Vec<GameObject>,
+}
+
+impl QueryParams {
+    fn accepts(&self, collider: &QueryCollider) -> bool {
+        self.mask & collider.layer != 0
+            && (self.include_triggers || !collider.is_trigger)
+            && !self.ignore.contains(&collider.key.0)
+    }
+}
+
+impl<'lua> FromLua<'lua> for QueryParams {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<QueryParams> {
+        match value {
+            LuaValue::Nil => Ok(QueryParams { mask: u32::MAX, include_triggers: false, ignore: Vec::new() }),
+            LuaValue::Table(table) => Ok(QueryParams {
+                mask: table.get::<_, Option<u32>>("Mask")?.unwrap_or(u32::MAX),
+                include_triggers: table.get::<_, Option<bool>>("IncludeTriggers")?.unwrap_or(false),
+                ignore: table.get::<_, Option<Vec<GameObject>>>("Ignore")?.unwrap_or_default(),
+            }),
+            other => Err(LuaError::RuntimeError(format!("Expected a query params table, got {}", other.type_name()))),
+        }
+    }
+}
+
+// runs `f` on the colliders in the scene, looking at the scene again if something changed since the last query
+fn with_world<R>(lua: &Lua, f: impl FnOnce(&QueryWorld) -> R) -> LuaResult<R> {
+    let mut physics = physics_mut(lua)?;
+    if physics.queries.is_none() {
+        let objects = component::active_objects(lua)?;
+        let mut scene = scene_mut(lua)?;
+        physics.queries = Some(QueryWorld::build(&mut scene, &objects));
+    }
+    Ok(f(physics.queries.as_ref().expect("queries were just built")))
+}
+
+fn overlapping(lua: &Lua, shape: Shape, params: QueryParams) -> LuaResult<Vec<Collider>> {
+    let keys = with_world(lua, |world| {
+        world.filter(world.hash.query(&shape.bounds()), &params)
+            .filter(|collider| collider.shape.collide(&shape).is_some())
+            .map(|collider| collider.key)
+            .collect::<Vec<_>>()
+    })?;
+    Ok(keys.into_iter().map(|(object, id)| Collider::new(object, id)).collect())
+}
+
+/**
+    The first collider along `direction` from `origin`, as far as the length
+    of `direction`, or nil. Colliders the ray starts in are passed through.
+*/
+pub fn raycast<'lua>(
+    lua: &'lua Lua,
+    (origin, direction, params): (LuaUserDataRef<'lua, Vector2>, LuaUserDataRef<'lua, Vector2>, QueryParams),
+) -> LuaResult<Option<LuaTable<'lua>>> {
+    let (origin, length) = (*origin, direction.length());
+    if length <= 0.0 {
+        return Ok(None);
+    }
+    let unit = *direction * (1.0 / length);
+
+    let hit = with_world(lua, |world| {
+        world.filter(world.hash.segment(origin, origin + *direction), &params)
+            .filter_map(|collider| collider.shape.raycast(origin, unit, length).map(|(distance, normal)| (collider.key, distance, normal)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    })?;
+
+    let Some(((object, id), distance, normal)) = hit else {
+        return Ok(None);
+    };
+    TableBuilder::new(lua)?
+        .with_value("Position", origin + unit * distance)?
+        .with_value("Normal", normal)?
+        .with_value("Distance", distance)?
+        .with_value("GameObject", object)?
+        .with_value("Collider", Collider::new(object, id))?
+        .build()
+        .map(Some)
+}
+
+// the colliders touching a circle
+pub fn overlap_circle(lua: &Lua, (center, radius, params): (LuaUserDataRef<Vector2>, f32, QueryParams)) -> LuaResult<Vec<Collider>> {
+    if radius.is_nan() || radius < 0.0 {
+        return Err(LuaError::RuntimeError(format!("radius must be 0 or greater, got {}", radius)));
+    }
+    overlapping(lua, Shape::new(vec![*center], radius), params)
+}
+
+// the colliders touching an axis-aligned box
+pub fn overlap_box(lua: &Lua, (center, size, params): (LuaUserDataRef<Vector2>, LuaUserDataRef<Vector2>, QueryParams)) -> LuaResult<Vec<Collider>> {
+    let half = Vector2::new(size.get_x().abs() / 2.0, size.get_y().abs() / 2.0);
+    let corners = Rect::new(*center - half, *center + half).corners();
+    overlapping(lua, Shape::new(corners.to_vec(), 0.0), params)
+}
+
+// the colliders `point` is inside of
+pub fn query_point(lua: &Lua, (point, params): (LuaUserDataRef<Vector2>, QueryParams)) -> LuaResult<Vec<Collider>> {
+    let point = *point;
+    let keys = with_world(lua, |world| {
+        world.filter(world.hash.query(&Rect::new(point, point)), &params)
+            .filter(|collider| collider.shape.contains(point))
+            .map(|collider| collider.key)
+            .collect::<Vec<_>>()
+    })?;
+    Ok(keys.into_iter().map(|(object, id)| Collider::new(object, id)).collect())
+}
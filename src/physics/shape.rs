@@ -0,0 +1,439 @@
+use crate::math::geometry::{self, EPSILON};
+use crate::math::{Matrix3, Rect, Segment, Vector2};
+
+// contact points this much further apart than the closest ones still count, so resting shapes get two
+const CONTACT_TOLERANCE: f32 = 0.5;
+
+/**
+    A convex shape in world space, made of a core grown by a radius.
+
+    Circles are a single point, capsules a segment and boxes and polygons
+    their corners with no radius, which lets every pair of shapes collide
+    through the same code.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shape {
+    // counter-clockwise when there are 3 or more
+    points: Vec<Vector2>,
+    radius: f32,
+}
+
+/**
+    How two overlapping shapes touch. The normal points from the first
+    shape towards the second, each point comes with how deep it is.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Manifold {
+    pub normal: Vector2,
+    pub points: Vec<(Vector2, f32)>,
+}
+
+impl Shape {
+    /**
+        A shape from its core points in world space, which have to be convex.
+        Points closer than a hair are merged.
+    */
+    pub fn new(points: Vec<Vector2>, radius: f32) -> Shape {
+        let mut unique: Vec<Vector2> = Vec::with_capacity(points.len());
+        for point in points {
+            if unique.iter().all(|&p| (p - point).length() > EPSILON) {
+                unique.push(point);
+            }
+        }
+        if unique.len() >= 3 && geometry::signed_area(&unique) < 0.0 {
+            unique.reverse();
+        }
+
+        Shape { points: unique, radius: radius.max(0.0) }
+    }
+
+    pub fn transformed(points: &[Vector2], radius: f32, matrix: &Matrix3) -> Shape {
+        let points = points.iter().map(|&p| geometry::transform_point(matrix, p)).collect();
+        let (scale_x, scale_y) = matrix.scale();
+        Shape::new(points, radius * scale_x.max(scale_y))
+    }
+
+    pub fn bounds(&self) -> Rect {
+        Rect::bounding(&self.points).grow(self.radius)
+    }
+
+    pub fn centroid(&self) -> Vector2 {
+        let sum = self.points.iter().fold(Vector2::new(0.0, 0.0), |sum, &p| sum + p);
+        sum * (1.0 / self.points.len() as f32)
+    }
+
+    // the edges of the core, a single one for a segment and none for a point
+    fn edges(&self) -> Vec<(Vector2, Vector2)> {
+        match self.points.len() {
+            0 | 1 => Vec::new(),
+            2 => vec![(self.points[0], self.points[1])],
+            n => (0..n).map(|i| (self.points[i], self.points[(i + 1) % n])).collect(),
+        }
+    }
+
+    // whether `point` is inside the core, only possible for polygons
+    fn core_contains(&self, point: Vector2) -> bool {
+        self.points.len() >= 3 && self.edges().iter().all(|&(a, b)| (b - a).cross(point - a) >= -EPSILON)
+    }
+
+    pub fn contains(&self, point: Vector2) -> bool {
+        !self.points.is_empty() && (self.core_contains(point) || self.distance_to_core(point).0 <= self.radius)
+    }
+
+    // distance from `point` to the outline of the core, and the closest point on it
+    fn distance_to_core(&self, point: Vector2) -> (f32, Vector2) {
+        if self.points.len() == 1 {
+            return ((point - self.points[0]).length(), self.points[0]);
+        }
+        self.edges().iter()
+            .map(|&(a, b)| {
+                let closest = Segment::new(a, b).closest_point(point);
+                ((point - closest).length(), closest)
+            })
+            .fold((f32::MAX, point), |best, candidate| if candidate.0 < best.0 { candidate } else { best })
+    }
+
+    fn cores_intersect(&self, other: &Shape) -> bool {
+        self.points.iter().any(|&p| other.core_contains(p))
+            || other.points.iter().any(|&p| self.core_contains(p))
+            || self.edges().iter().any(|&(a0, a1)| {
+                let edge = Segment::new(a0, a1);
+                other.edges().iter().any(|&(b0, b1)| edge.intersection(&Segment::new(b0, b1)).is_some())
+            })
+            || (self.points.len() == 1 && other.points.len() == 1 && (self.points[0] - other.points[0]).length() <= EPSILON)
+    }
+
+    /**
+        The closest points between the outline of the cores of two shapes that
+        don't overlap, as pairs of a point on `self`, one on `other` and the
+        distance between them, the closest pair first.
+    */
+    fn closest_pairs(&self, other: &Shape) -> Vec<(Vector2, Vector2, f32)> {
+        let mut pairs = Vec::new();
+        for &p in &self.points {
+            let (distance, closest) = other.distance_to_core(p);
+            pairs.push((p, closest, distance));
+        }
+        for &p in &other.points {
+            let (distance, closest) = self.distance_to_core(p);
+            pairs.push((closest, p, distance));
+        }
+        pairs.sort_by(|a, b| a.2.total_cmp(&b.2));
+        pairs
+    }
+
+    /**
+        Where and how deep `other` overlaps this shape, or `None` if they
+        don't touch.
+    */
+    pub fn collide(&self, other: &Shape) -> Option<Manifold> {
+        if self.points.is_empty() || other.points.is_empty() {
+            return None;
+        }
+        let radii = self.radius + other.radius;
+
+        if !self.cores_intersect(other) {
+            let pairs = self.closest_pairs(other);
+            let (a, b, distance) = pairs[0];
+            if distance > radii {
+                return None;
+            }
+
+            let normal = if distance > EPSILON {
+                (b - a) * (1.0 / distance)
+            } else {
+                (other.centroid() - self.centroid()).normalized()
+            };
+
+            // the other pairs about as close and along the same normal touch too, like a capsule lying on a box
+            let mut points: Vec<(Vector2, f32)> = Vec::new();
+            for &(a, b, d) in &pairs {
+                let parallel = d <= EPSILON || (b - a).dot(normal) >= d * 0.99;
+                if d <= distance + CONTACT_TOLERANCE && d <= radii && parallel {
+                    let point = (a + normal * self.radius + b - normal * other.radius) * 0.5;
+                    if points.iter().all(|&(p, _)| (p - point).length() > CONTACT_TOLERANCE) {
+                        points.push((point, radii - d));
+                    }
+                }
+            }
+            return Some(Manifold { normal, points: farthest_two(points) });
+        }
+
+        // the cores overlap, so they are pushed apart along the axis they overlap least on
+        let mut best: Option<(f32, Vector2, bool)> = None;
+        let axes = geometry::axes(&self.points).into_iter().map(|axis| (axis, true))
+            .chain(geometry::axes(&other.points).into_iter().map(|axis| (axis, false)));
+        for (axis, owned_by_self) in axes {
+            let overlap = geometry::overlap(&self.points, &other.points, axis);
+            if best.is_none_or(|(o, _, _)| overlap < o) {
+                best = Some((overlap, axis, owned_by_self));
+            }
+        }
+
+        let Some((overlap, axis, owned_by_self)) = best else {
+            // two points in the same place
+            return Some(Manifold { normal: Vector2::new(0.0, -1.0), points: vec![(self.points[0], radii)] });
+        };
+
+        let normal = if axis.dot(other.centroid() - self.centroid()) < 0.0 { -axis } else { axis };
+        let (reference, incident, reference_normal) = if owned_by_self {
+            (self, other, normal)
+        } else {
+            (other, self, -normal)
+        };
+
+        let points = clip_contacts(reference, incident, reference_normal, radii)
+            .unwrap_or_else(|| vec![(incident.support(-reference_normal), overlap + radii)]);
+        Some(Manifold { normal, points })
+    }
+
+    /**
+        How far along `direction`, a unit vector, a ray from `origin` enters
+        the shape and the normal where it does, if that's within
+        `max_distance`. Rays starting inside the shape don't hit it.
+    */
+    pub fn raycast(&self, origin: Vector2, direction: Vector2, max_distance: f32) -> Option<(f32, Vector2)> {
+        if self.points.is_empty() || self.contains(origin) {
+            return None;
+        }
+
+        let mut hits = Vec::new();
+        for (a, b, normal) in self.sides() {
+            let facing = direction.dot(normal);
+            if facing >= 0.0 {
+                continue;
+            }
+            let distance = (a - origin).dot(normal) / facing;
+            let edge = b - a;
+            let along = (origin + direction * distance - a).dot(edge) / edge.dot(edge);
+            if (-EPSILON..=1.0 + EPSILON).contains(&along) {
+                hits.push((distance, normal));
+            }
+        }
+
+        // the rounded corners
+        if self.radius > 0.0 {
+            for &center in &self.points {
+                let offset = origin - center;
+                let along = offset.dot(direction);
+                let discriminant = along * along - (offset.dot(offset) - self.radius * self.radius);
+                if discriminant >= 0.0 {
+                    let distance = -along - discriminant.sqrt();
+                    hits.push((distance, (origin + direction * distance - center).normalized()));
+                }
+            }
+        }
+
+        hits.into_iter()
+            .filter(|&(distance, _)| (0.0..=max_distance).contains(&distance))
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+    }
+
+    // the flat sides of the shape with their outward normals, the core edges pushed out by the radius
+    fn sides(&self) -> Vec<(Vector2, Vector2, Vector2)> {
+        let edges = match self.points.len() {
+            0 | 1 => Vec::new(),
+            // both sides of a segment
+            2 => vec![(self.points[0], self.points[1]), (self.points[1], self.points[0])],
+            _ => self.edges(),
+        };
+        edges.into_iter()
+            .map(|(a, b)| {
+                let normal = -(b - a).normalized().perp();
+                (a + normal * self.radius, b + normal * self.radius, normal)
+            })
+            .collect()
+    }
+
+    // the point of the core furthest along `direction`
+    fn support(&self, direction: Vector2) -> Vector2 {
+        self.points.iter()
+            .copied()
+            .max_by(|a, b| a.dot(direction).total_cmp(&b.dot(direction)))
+            .unwrap_or(Vector2::new(0.0, 0.0))
+    }
+
+    // the edge facing `direction` the most, or the whole core if it has less than 3 points
+    fn face(&self, direction: Vector2) -> Vec<Vector2> {
+        if self.points.len() < 3 {
+            return self.points.clone();
+        }
+        let (a, b) = self.edges()
+            .into_iter()
+            .max_by(|&(a0, a1), &(b0, b1)| {
+                let facing = |a: Vector2, b: Vector2| -(b - a).normalized().perp().dot(direction);
+                facing(a0, a1).total_cmp(&facing(b0, b1))
+            })
+            .expect("a polygon has edges");
+        vec![a, b]
+    }
+}
+
+/**
+    Contact points of the face of `incident` pointing against `normal`,
+    clipped to the sides of the face of `reference` along it, with their
+    depths. `None` when clipping leaves nothing.
+*/
+fn clip_contacts(reference: &Shape, incident: &Shape, normal: Vector2, radii: f32) -> Option<Vec<(Vector2, f32)>> {
+    let face = reference.face(normal);
+    let (r0, r1) = (face[0], *face.last().expect("faces have points"));
+    let tangent = (r1 - r0).normalized();
+    // pushed past the end of a segment, there's no face to clip to
+    if face.len() < 2 || tangent.dot(normal).abs() > 0.01 {
+        return None;
+    }
+    let (low, high) = (r0.dot(tangent), r1.dot(tangent));
+
+    let mut candidates = incident.face(-normal);
+    if candidates.len() == 2 {
+        // clip the incident segment to the slab of the reference face
+        let (p, q) = (candidates[0], candidates[1]);
+        let (dp, dq) = (p.dot(tangent), q.dot(tangent));
+        let clip = |t: f32| if (dq - dp).abs() > EPSILON { p + (q - p) * ((t - dp) / (dq - dp)).clamp(0.0, 1.0) } else { p };
+        let (dmin, dmax) = (dp.min(dq), dp.max(dq));
+        if dmax < low - EPSILON || dmin > high + EPSILON {
+            return None;
+        }
+        candidates = vec![clip(low.max(dmin)), clip(high.min(dmax))];
+    }
+
+    let points = candidates.into_iter()
+        .filter_map(|point| {
+            let separation = (point - r0).dot(normal);
+            (separation <= radii).then_some((point, radii - separation))
+        })
+        .collect::<Vec<_>>();
+
+    (!points.is_empty()).then(|| farthest_two(points))
+}
+
+// at most two points, the ones furthest apart, which is all a 2D contact needs
+fn farthest_two(points: Vec<(Vector2, f32)>) -> Vec<(Vector2, f32)> {
+    if points.len() <= 2 {
+        return points;
+    }
+
+    let mut best = (0, 1, f32::MIN);
+    for i in 0..points.len() {
+        for j in i + 1..points.len() {
+            let distance = (points[i].0 - points[j].0).length();
+            if distance > best.2 {
+                best = (i, j, distance);
+            }
+        }
+    }
+    vec![points[best.0], points[best.1]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rectangle(x: f32, y: f32, width: f32, height: f32) -> Shape {
+        let corners = [(x, y), (x + width, y), (x + width, y + height), (x, y + height)];
+        Shape::new(corners.map(|(x, y)| Vector2::new(x, y)).to_vec(), 0.0)
+    }
+
+    // a 400 wide floor whose top is at y = 300, y going down
+    fn floor() -> Shape {
+        rectangle(-200.0, 300.0, 400.0, 40.0)
+    }
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-3, "expected {}, got {}", expected, actual);
+    }
+
+    // the contact of a shape resting on the floor, pushed down out of it with every point as deep as `depth`
+    fn assert_resting(shape: &Shape, points: usize, depth: f32) -> Manifold {
+        let manifold = shape.collide(&floor()).expect("the shape should touch the floor");
+        assert_near(manifold.normal.get_x(), 0.0);
+        assert_near(manifold.normal.get_y(), 1.0);
+        assert_eq!(manifold.points.len(), points, "{:?}", manifold.points);
+        for &(_, d) in &manifold.points {
+            assert_near(d, depth);
+        }
+        manifold
+    }
+
+    #[test]
+    fn box_resting_on_a_box() {
+        let manifold = assert_resting(&rectangle(-20.0, 260.5, 40.0, 40.0), 2, 0.5);
+        let mut xs = manifold.points.iter().map(|(p, _)| p.get_x()).collect::<Vec<_>>();
+        xs.sort_by(f32::total_cmp);
+        assert_near(xs[0], -20.0);
+        assert_near(xs[1], 20.0);
+    }
+
+    #[test]
+    fn box_wider_than_its_floor() {
+        // the contact is clipped to the sides of the floor
+        let plank = rectangle(-300.0, 290.0, 600.0, 11.0);
+        let manifold = assert_resting(&plank, 2, 1.0);
+        let mut xs = manifold.points.iter().map(|(p, _)| p.get_x()).collect::<Vec<_>>();
+        xs.sort_by(f32::total_cmp);
+        assert_near(xs[0], -200.0);
+        assert_near(xs[1], 200.0);
+    }
+
+    #[test]
+    fn circle_resting_on_a_box() {
+        let manifold = assert_resting(&Shape::new(vec![Vector2::new(50.0, 290.0)], 10.5), 1, 0.5);
+        assert_near(manifold.points[0].0.get_x(), 50.0);
+    }
+
+    #[test]
+    fn capsule_resting_on_a_box() {
+        // standing up, its rounded end touches
+        let standing = Shape::new(vec![Vector2::new(0.0, 255.0), Vector2::new(0.0, 285.0)], 15.5);
+        assert_resting(&standing, 1, 0.5);
+
+        // lying down, both ends do
+        let lying = Shape::new(vec![Vector2::new(-20.0, 290.0), Vector2::new(20.0, 290.0)], 10.5);
+        assert_resting(&lying, 2, 0.5);
+    }
+
+    #[test]
+    fn capsule_sunk_into_a_box_is_pushed_up() {
+        // its core has no width across, which must not make sideways the shallowest way out
+        let sunk = Shape::new(vec![Vector2::new(0.0, 270.0), Vector2::new(0.0, 305.0)], 5.0);
+        let manifold = sunk.collide(&floor()).unwrap();
+        assert_near(manifold.normal.get_x(), 0.0);
+        assert_near(manifold.normal.get_y(), 1.0);
+        assert_near(manifold.points[0].1, 10.0);
+
+        let circle = Shape::new(vec![Vector2::new(150.0, 302.0)], 5.0);
+        let manifold = circle.collide(&floor()).unwrap();
+        assert_near(manifold.normal.get_y(), 1.0);
+    }
+
+    #[test]
+    fn polygon_resting_on_a_box() {
+        let triangle = Shape::new(
+            vec![Vector2::new(-30.0, 300.5), Vector2::new(0.0, 250.0), Vector2::new(30.0, 300.5)],
+            0.0,
+        );
+        assert_resting(&triangle, 2, 0.5);
+
+        // standing on a corner there is a single point
+        let diamond = Shape::new(
+            vec![Vector2::new(0.0, 300.5), Vector2::new(20.0, 280.0), Vector2::new(0.0, 260.0), Vector2::new(-20.0, 280.0)],
+            0.0,
+        );
+        let manifold = assert_resting(&diamond, 1, 0.5);
+        assert_near(manifold.points[0].0.get_x(), 0.0);
+    }
+
+    #[test]
+    fn normals_point_from_the_first_shape() {
+        let above = rectangle(-20.0, 260.5, 40.0, 40.0);
+        let manifold = floor().collide(&above).unwrap();
+        assert_near(manifold.normal.get_y(), -1.0);
+    }
+
+    #[test]
+    fn apart_shapes_do_not_collide() {
+        assert_eq!(rectangle(-20.0, 250.0, 40.0, 40.0).collide(&floor()), None);
+        assert_eq!(Shape::new(vec![Vector2::new(0.0, 280.0)], 19.0).collide(&floor()), None);
+        assert_eq!(rectangle(300.0, 300.0, 10.0, 10.0).collide(&floor()), None);
+    }
+}
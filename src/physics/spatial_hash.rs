@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+
+use crate::math::{Rect, Vector2};
+
+// items covering more cells than this are kept aside and looked at by every query
+const MAX_CELLS: i64 = 64;
+
+/**
+    Buckets items by the cells of a square grid their bounds cover, so a
+    query only looks at the items near the area it asks about.
+*/
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    oversized: Vec<usize>,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> SpatialHash {
+        SpatialHash {
+            cell_size,
+            cells: HashMap::new(),
+            oversized: Vec::new(),
+        }
+    }
+
+    fn cell(&self, point: Vector2) -> (i32, i32) {
+        ((point.get_x() / self.cell_size).floor() as i32, (point.get_y() / self.cell_size).floor() as i32)
+    }
+
+    fn cell_count(min: (i32, i32), max: (i32, i32)) -> i64 {
+        (max.0 as i64 - min.0 as i64 + 1) * (max.1 as i64 - min.1 as i64 + 1)
+    }
+
+    pub fn insert(&mut self, item: usize, bounds: &Rect) {
+        let (min, max) = (self.cell(bounds.min()), self.cell(bounds.max()));
+        if SpatialHash::cell_count(min, max) > MAX_CELLS {
+            self.oversized.push(item);
+            return;
+        }
+
+        for x in min.0..=max.0 {
+            for y in min.1..=max.1 {
+                self.cells.entry((x, y)).or_default().push(item);
+            }
+        }
+    }
+
+    // every item once, in the order they were inserted
+    fn finish(&self, mut items: Vec<usize>) -> Vec<usize> {
+        items.extend_from_slice(&self.oversized);
+        items.sort_unstable();
+        items.dedup();
+        items
+    }
+
+    /**
+        The items in the cells `area` covers, which may include some that
+        don't overlap it.
+    */
+    pub fn query(&self, area: &Rect) -> Vec<usize> {
+        let (min, max) = (self.cell(area.min()), self.cell(area.max()));
+        let in_range = |&(x, y): &(i32, i32)| (min.0..=max.0).contains(&x) && (min.1..=max.1).contains(&y);
+
+        let mut items = Vec::new();
+        // a huge area is cheaper to check against the cells that are in use
+        if SpatialHash::cell_count(min, max) > self.cells.len() as i64 {
+            for (_, cell) in self.cells.iter().filter(|(key, _)| in_range(key)) {
+                items.extend_from_slice(cell);
+            }
+        } else {
+            for x in min.0..=max.0 {
+                for y in min.1..=max.1 {
+                    if let Some(cell) = self.cells.get(&(x, y)) {
+                        items.extend_from_slice(cell);
+                    }
+                }
+            }
+        }
+        self.finish(items)
+    }
+
+    /**
+        The items in the cells the segment from `from` to `to` passes
+        through, found by walking the grid along it.
+    */
+    pub fn segment(&self, from: Vector2, to: Vector2) -> Vec<usize> {
+        let (mut cell, end) = (self.cell(from), self.cell(to));
+        let steps = (end.0 as i64 - cell.0 as i64).abs() + (end.1 as i64 - cell.1 as i64).abs();
+        if steps > self.cells.len() as i64 {
+            return self.query(&Rect::new(from, to));
+        }
+
+        // how far along the segment the next cell boundary on each axis is, and how far apart they are
+        let axis = |from: f32, delta: f32, cell: i32| {
+            if delta == 0.0 {
+                return (0, f32::INFINITY, f32::INFINITY);
+            }
+            let step = if delta > 0.0 { 1 } else { 0 };
+            let boundary = (cell + step) as f32 * self.cell_size;
+            (if delta > 0.0 { 1 } else { -1 }, (boundary - from) / delta, self.cell_size / delta.abs())
+        };
+        let delta = to - from;
+        let (step_x, mut next_x, spacing_x) = axis(from.get_x(), delta.get_x(), cell.0);
+        let (step_y, mut next_y, spacing_y) = axis(from.get_y(), delta.get_y(), cell.1);
+
+        let mut items = Vec::new();
+        for _ in 0..=steps {
+            if let Some(items_in_cell) = self.cells.get(&cell) {
+                items.extend_from_slice(items_in_cell);
+            }
+            if cell == end {
+                break;
+            }
+            if next_x < next_y {
+                cell.0 += step_x;
+                next_x += spacing_x;
+            } else {
+                cell.1 += step_y;
+                next_y += spacing_y;
+            }
+        }
+        self.finish(items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(a: (f32, f32), b: (f32, f32)) -> Rect {
+        Rect::new(Vector2::new(a.0, a.1), Vector2::new(b.0, b.1))
+    }
+
+    #[test]
+    fn queries_only_look_at_nearby_cells() {
+        let mut hash = SpatialHash::new(10.0);
+        hash.insert(0, &rect((1.0, 1.0), (5.0, 5.0)));
+        hash.insert(1, &rect((25.0, 1.0), (35.0, 5.0)));
+        hash.insert(2, &rect((-15.0, -15.0), (-12.0, -12.0)));
+
+        assert_eq!(hash.query(&rect((0.0, 0.0), (9.0, 9.0))), [0]);
+        assert_eq!(hash.query(&rect((8.0, 0.0), (31.0, 2.0))), [0, 1]);
+        assert_eq!(hash.query(&rect((-20.0, -20.0), (-11.0, -11.0))), [2]);
+        assert_eq!(hash.query(&rect((50.0, 50.0), (60.0, 60.0))), []);
+        // more cells than are in use
+        assert_eq!(hash.query(&rect((-1e6, -1e6), (1e6, 1e6))), [0, 1, 2]);
+    }
+
+    #[test]
+    fn oversized_items_are_always_candidates() {
+        let mut hash = SpatialHash::new(10.0);
+        hash.insert(0, &rect((0.0, 0.0), (5.0, 5.0)));
+        hash.insert(1, &rect((-1000.0, 0.0), (1000.0, 5.0)));
+
+        assert_eq!(hash.query(&rect((500.0, 500.0), (501.0, 501.0))), [1]);
+        assert_eq!(hash.query(&rect((0.0, 0.0), (1.0, 1.0))), [0, 1]);
+        assert_eq!(hash.segment(Vector2::new(0.0, 500.0), Vector2::new(0.0, 600.0)), [1]);
+    }
+
+    #[test]
+    fn segments_walk_every_cell_they_cross() {
+        let mut hash = SpatialHash::new(10.0);
+        for (item, x) in [(0, 5.0), (1, 15.0), (2, 25.0), (3, 35.0)] {
+            hash.insert(item, &rect((x, 5.0), (x, 5.0)));
+        }
+        // off the line the segment follows
+        hash.insert(4, &rect((15.0, 25.0), (15.0, 25.0)));
+        hash.insert(5, &rect((100.0, 100.0), (100.0, 100.0)));
+
+        assert_eq!(hash.segment(Vector2::new(1.0, 5.0), Vector2::new(39.0, 5.0)), [0, 1, 2, 3]);
+        assert_eq!(hash.segment(Vector2::new(39.0, 5.0), Vector2::new(12.0, 5.0)), [1, 2, 3]);
+        // diagonally through (0, 0), (1, 0), (1, 1) and (1, 2) but not (0, 1)
+        assert_eq!(hash.segment(Vector2::new(8.0, 2.0), Vector2::new(19.0, 28.0)), [0, 1, 4]);
+        assert_eq!(hash.segment(Vector2::new(5.0, 5.0), Vector2::new(5.0, 5.0)), [0]);
+    }
+}
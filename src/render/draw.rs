@@ -0,0 +1,175 @@
+use mlua::prelude::*;
+
+use crate::math::Vector2;
+
+use super::{DrawCommand, DrawQueue, Point, Rgba, Style};
+
+/**
+    Reads the arguments of a draw function in order, producing the same
+    "bad argument" errors as the Luau standard library.
+
+    Points can be given either as a `Vector2` or as two numbers, so
+    `drawCircle(Vector2.new(10, 20), 5, color)` and `drawCircle(10, 20, 5, color)`
+    are the same call.
+*/
+struct Args<'lua> {
+    lua: &'lua Lua,
+    function: &'static str,
+    values: std::vec::IntoIter<LuaValue<'lua>>,
+    position: usize,
+}
+
+impl<'lua> Args<'lua> {
+    fn new(lua: &'lua Lua, function: &'static str, args: LuaMultiValue<'lua>) -> Args<'lua> {
+        Args { lua, function, values: args.into_vec().into_iter(), position: 0 }
+    }
+
+    fn error(&self, message: String) -> LuaError {
+        LuaError::RuntimeError(format!("bad argument #{} to '{}' ({})", self.position, self.function, message))
+    }
+
+    fn next(&mut self) -> LuaValue<'lua> {
+        self.position += 1;
+        self.values.next().unwrap_or(LuaNil)
+    }
+
+    fn number(&mut self) -> LuaResult<f32> {
+        match self.next() {
+            LuaValue::Integer(value) => Ok(value as f32),
+            LuaValue::Number(value) => Ok(value as f32),
+            value => Err(self.error(format!("number expected, got {}", value.type_name()))),
+        }
+    }
+
+    // infinite angles have no end to draw to
+    fn angle(&mut self) -> LuaResult<f32> {
+        let angle = self.number()?;
+        if !angle.is_finite() {
+            return Err(self.error(format!("finite angle expected, got {}", angle)));
+        }
+        Ok(angle)
+    }
+
+    fn point(&mut self) -> LuaResult<Point> {
+        match self.next() {
+            LuaValue::Integer(x) => Ok((x as f32, self.number()?)),
+            LuaValue::Number(x) => Ok((x as f32, self.number()?)),
+            value => self.vector(value),
+        }
+    }
+
+    fn vector(&self, value: LuaValue<'lua>) -> LuaResult<Point> {
+        if let LuaValue::UserData(userdata) = &value {
+            if let Ok(vector) = userdata.borrow::<Vector2>() {
+                return Ok((vector.get_x(), vector.get_y()));
+            }
+        }
+        Err(self.error(format!("Vector2 expected, got {}", value.type_name())))
+    }
+
+    fn points(&mut self) -> LuaResult<Vec<Point>> {
+        let value = self.next();
+        let LuaValue::Table(table) = value else {
+            return Err(self.error(format!("table of Vector2 expected, got {}", value.type_name())));
+        };
+
+        table.sequence_values::<LuaValue>()
+            .map(|value| self.vector(value?))
+            .collect()
+    }
+
+    fn color(&mut self) -> LuaResult<Rgba> {
+        let value = self.next();
+        Rgba::from_lua(value, self.lua).map_err(|err| self.error(err.to_string()))
+    }
+}
+
+type ReadCommand = fn(&mut Args) -> LuaResult<DrawCommand>;
+
+const DRAW_FUNCTIONS: &[(&str, ReadCommand)] = &[
+    ("drawRectangle", |args| {
+        Ok(DrawCommand::Rectangle { position: args.point()?, size: args.point()?, style: Style::Fill, color: args.color()? })
+    }),
+    ("drawRectangleOutline", |args| {
+        let (position, size) = (args.point()?, args.point()?);
+        let style = Style::Outline { thickness: args.number()? };
+        Ok(DrawCommand::Rectangle { position, size, style, color: args.color()? })
+    }),
+    ("drawRoundedRectangle", |args| {
+        let (position, size, radius) = (args.point()?, args.point()?, args.number()?);
+        Ok(DrawCommand::RoundedRectangle { position, size, radius, style: Style::Fill, color: args.color()? })
+    }),
+    ("drawRoundedRectangleOutline", |args| {
+        let (position, size, radius) = (args.point()?, args.point()?, args.number()?);
+        let style = Style::Outline { thickness: args.number()? };
+        Ok(DrawCommand::RoundedRectangle { position, size, radius, style, color: args.color()? })
+    }),
+    ("drawCircle", |args| {
+        Ok(DrawCommand::Circle { center: args.point()?, radius: args.number()?, style: Style::Fill, color: args.color()? })
+    }),
+    ("drawCircleOutline", |args| {
+        let (center, radius) = (args.point()?, args.number()?);
+        let style = Style::Outline { thickness: args.number()? };
+        Ok(DrawCommand::Circle { center, radius, style, color: args.color()? })
+    }),
+    ("drawEllipse", |args| {
+        let (center, radius) = (args.point()?, (args.number()?, args.number()?));
+        Ok(DrawCommand::Ellipse { center, radius, style: Style::Fill, color: args.color()? })
+    }),
+    ("drawEllipseOutline", |args| {
+        let (center, radius) = (args.point()?, (args.number()?, args.number()?));
+        let style = Style::Outline { thickness: args.number()? };
+        Ok(DrawCommand::Ellipse { center, radius, style, color: args.color()? })
+    }),
+    ("drawArc", |args| {
+        let (center, radius) = (args.point()?, args.number()?);
+        let (start_angle, end_angle) = (args.angle()?, args.angle()?);
+        Ok(DrawCommand::Arc { center, radius, start_angle, end_angle, style: Style::Fill, color: args.color()? })
+    }),
+    ("drawArcOutline", |args| {
+        let (center, radius) = (args.point()?, args.number()?);
+        let (start_angle, end_angle) = (args.angle()?, args.angle()?);
+        let style = Style::Outline { thickness: args.number()? };
+        Ok(DrawCommand::Arc { center, radius, start_angle, end_angle, style, color: args.color()? })
+    }),
+    ("drawLine", |args| {
+        Ok(DrawCommand::Line { from: args.point()?, to: args.point()?, thickness: args.number()?, color: args.color()? })
+    }),
+    ("drawTriangle", |args| {
+        let points = [args.point()?, args.point()?, args.point()?];
+        Ok(DrawCommand::Triangle { points, style: Style::Fill, color: args.color()? })
+    }),
+    ("drawTriangleOutline", |args| {
+        let points = [args.point()?, args.point()?, args.point()?];
+        let style = Style::Outline { thickness: args.number()? };
+        Ok(DrawCommand::Triangle { points, style, color: args.color()? })
+    }),
+    ("drawPolygon", |args| {
+        Ok(DrawCommand::Polygon { points: args.points()?, style: Style::Fill, color: args.color()? })
+    }),
+    ("drawPolygonOutline", |args| {
+        let points = args.points()?;
+        let style = Style::Outline { thickness: args.number()? };
+        Ok(DrawCommand::Polygon { points, style, color: args.color()? })
+    }),
+];
+
+/**
+    The shape drawing functions of the `Bee2D` table, each one queues a
+    [`DrawCommand`] for the current frame.
+*/
+pub fn create_functions(lua: &Lua) -> LuaResult<Vec<(&'static str, LuaFunction<'_>)>> {
+    DRAW_FUNCTIONS.iter()
+        .map(|&(name, read)| {
+            let function = lua.create_function(move |lua, args: LuaMultiValue| {
+                let command = read(&mut Args::new(lua, name, args))?;
+
+                lua.app_data_mut::<DrawQueue>()
+                    .ok_or_else(|| LuaError::RuntimeError("Bee2D has not been initialized".into()))?
+                    .push(command);
+                Ok(())
+            })?;
+            Ok((name, function))
+        })
+        .collect()
+}
@@ -0,0 +1,321 @@
+use mlua::prelude::*;
+
+use crate::math::{Color, Matrix3};
+
+pub mod draw;
+pub mod shapes;
+
+pub mod software;
+pub use software::{Framebuffer, SoftwareRenderer};
+
+#[cfg(feature = "raylib")]
+pub mod raylib_backend;
+#[cfg(feature = "raylib")]
+pub use raylib_backend::RaylibRenderer;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgba {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Rgba {
+    pub const BLACK: Rgba = Rgba { r: 0, g: 0, b: 0, a: 255 };
+    pub const WHITE: Rgba = Rgba { r: 255, g: 255, b: 255, a: 255 };
+
+    pub fn new(r: u8, g: u8, b: u8, a: u8) -> Rgba {
+        Rgba { r, g, b, a }
+    }
+}
+
+impl From<Color> for Rgba {
+    fn from(color: Color) -> Rgba {
+        let [r, g, b, a] = color.to_rgb();
+        Rgba { r, g, b, a }
+    }
+}
+
+impl From<Rgba> for Color {
+    fn from(color: Rgba) -> Color {
+        Color::from_rgb(color.r as f32, color.g as f32, color.b as f32, color.a as f32)
+    }
+}
+
+fn color_conversion_error(from: &'static str, message: String) -> LuaError {
+    LuaError::FromLuaConversionError { from, to: "Color", message: Some(message) }
+}
+
+// colors are `Color` userdata in Luau, `{r, g, b, a}` tables of 0-255 channels are still accepted
+impl<'lua> FromLua<'lua> for Rgba {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<Rgba> {
+        match &value {
+            LuaValue::UserData(userdata) => match userdata.borrow::<Color>() {
+                Ok(color) => Ok(Rgba::from(*color)),
+                Err(_) => Err(color_conversion_error("userdata", "Expected a Color".into())),
+            },
+            LuaValue::Table(table) => {
+                let mut channels = [255; 4];
+                let length = table.raw_len();
+                if !(3..=4).contains(&length) {
+                    return Err(color_conversion_error("table", format!("Expected a table of 3 or 4 channels, got {}", length)));
+                }
+
+                for (index, channel) in channels.iter_mut().enumerate().take(length) {
+                    let value: LuaValue = table.raw_get(index + 1)?;
+                    let got = match value {
+                        LuaValue::Integer(n) if (0..=255).contains(&n) => Ok(n as u8),
+                        LuaValue::Number(n) if (0.0..=255.0).contains(&n) => Ok(n.round() as u8),
+                        LuaValue::Integer(n) => Err(n.to_string()),
+                        LuaValue::Number(n) => Err(n.to_string()),
+                        _ => Err(value.type_name().to_string()),
+                    };
+
+                    *channel = got.map_err(|got| color_conversion_error("table", format!(
+                        "Expected channel {} to be a number from 0 to 255, got {}", index + 1, got
+                    )))?;
+                }
+
+                let [r, g, b, a] = channels;
+                Ok(Rgba::new(r, g, b, a))
+            }
+            _ => Err(color_conversion_error(value.type_name(), format!("Expected a Color, got {}", value.type_name()))),
+        }
+    }
+}
+
+impl<'lua> IntoLua<'lua> for Rgba {
+    fn into_lua(self, lua: &'lua Lua) -> LuaResult<LuaValue<'lua>> {
+        Color::from(self).into_lua(lua)
+    }
+}
+
+// index of a texture inside the renderer that loaded it
+pub type TextureId = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureInfo {
+    pub id: TextureId,
+    pub width: u32,
+    pub height: u32,
+}
+
+// x, y, width, height
+pub type Region = (f32, f32, f32, f32);
+
+pub type Point = (f32, f32);
+pub type Triangle = [Point; 3];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Style {
+    Fill,
+    Outline { thickness: f32 },
+}
+
+/**
+    A single immediate-mode draw call, queued by scripts during a frame and
+    replayed onto the [`Canvas`] when the frame is rendered.
+
+    Angles are in degrees, measured clockwise on screen from the positive x axis.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub enum DrawCommand {
+    Rectangle { position: Point, size: Point, style: Style, color: Rgba },
+    RoundedRectangle { position: Point, size: Point, radius: f32, style: Style, color: Rgba },
+    Circle { center: Point, radius: f32, style: Style, color: Rgba },
+    Ellipse { center: Point, radius: Point, style: Style, color: Rgba },
+    // filled as a pie slice, outlined along the curved edge only
+    Arc { center: Point, radius: f32, start_angle: f32, end_angle: f32, style: Style, color: Rgba },
+    Line { from: Point, to: Point, thickness: f32, color: Rgba },
+    Triangle { points: [Point; 3], style: Style, color: Rgba },
+    Polygon { points: Vec<Point>, style: Style, color: Rgba },
+    // `transform` maps pixels of the `source` region, with its top left corner at the origin, to the screen
+    Texture { texture: TextureId, source: Region, transform: Matrix3, color: Rgba },
+}
+
+fn outline_or_fill(points: &[Point], style: Style) -> Vec<Triangle> {
+    match style {
+        Style::Fill => shapes::triangulate(points),
+        Style::Outline { thickness } => shapes::stroke(points, thickness, true),
+    }
+}
+
+// puts the top left corner of an image at (x, y), rotated in degrees around that corner
+pub fn placement(x: f32, y: f32, rotation: f32, scale: f32) -> Matrix3 {
+    Matrix3::translation(x, y) * Matrix3::rotation_radians(rotation.to_radians()) * Matrix3::scaling(scale, scale)
+}
+
+impl DrawCommand {
+    // the whole texture drawn through `placement`
+    pub fn texture(texture: TextureInfo, x: f32, y: f32, rotation: f32, scale: f32, color: Rgba) -> DrawCommand {
+        DrawCommand::Texture {
+            texture: texture.id,
+            source: (0.0, 0.0, texture.width as f32, texture.height as f32),
+            transform: placement(x, y, rotation, scale),
+            color,
+        }
+    }
+
+    pub fn draw(&self, canvas: &mut dyn Canvas) {
+        let (triangles, color) = match self {
+            DrawCommand::Rectangle { position, size, style, color } => {
+                (outline_or_fill(&shapes::rectangle(position.0, position.1, size.0, size.1), *style), *color)
+            }
+            DrawCommand::RoundedRectangle { position, size, radius, style, color } => {
+                (outline_or_fill(&shapes::rounded_rectangle(position.0, position.1, size.0, size.1, *radius), *style), *color)
+            }
+            DrawCommand::Circle { center, radius, style, color } => {
+                (outline_or_fill(&shapes::ellipse(*center, *radius, *radius), *style), *color)
+            }
+            DrawCommand::Ellipse { center, radius, style, color } => {
+                (outline_or_fill(&shapes::ellipse(*center, radius.0, radius.1), *style), *color)
+            }
+            DrawCommand::Arc { center, radius, start_angle, end_angle, style, color } => {
+                let curve = shapes::arc(*center, *radius, *radius, *start_angle, *end_angle);
+                let triangles = match style {
+                    Style::Fill => shapes::triangulate(&[vec![*center], curve].concat()),
+                    Style::Outline { thickness } => shapes::stroke(&curve, *thickness, false),
+                };
+                (triangles, *color)
+            }
+            DrawCommand::Line { from, to, thickness, color } => {
+                (shapes::stroke(&[*from, *to], *thickness, false), *color)
+            }
+            DrawCommand::Triangle { points, style, color } => (outline_or_fill(points, *style), *color),
+            DrawCommand::Polygon { points, style, color } => (outline_or_fill(points, *style), *color),
+            DrawCommand::Texture { texture, source, transform, color } => {
+                canvas.draw_texture(*texture, *source, *transform, *color);
+                return;
+            }
+        };
+
+        canvas.fill_triangles(&triangles, color);
+    }
+}
+
+/**
+    Immediate-mode draw calls made during the current frame, stored in the
+    app data of the Lua VM and cleared once the frame has been rendered.
+
+    Calls go into the world, drawn through every camera, unless the queue is
+    switched to screen space, which is drawn once on top in window pixels.
+*/
+#[derive(Default)]
+pub struct DrawQueue {
+    commands: Vec<DrawCommand>,
+    screen_commands: Vec<DrawCommand>,
+    screen_space: bool,
+}
+
+impl DrawQueue {
+    pub fn push(&mut self, command: DrawCommand) {
+        if self.screen_space {
+            self.screen_commands.push(command);
+        } else {
+            self.commands.push(command);
+        }
+    }
+
+    pub fn set_screen_space(&mut self, screen_space: bool) {
+        self.screen_space = screen_space;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &DrawCommand> {
+        self.commands.iter()
+    }
+
+    pub fn iter_screen(&self) -> impl Iterator<Item = &DrawCommand> {
+        self.screen_commands.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.commands.clear();
+        self.screen_commands.clear();
+        self.screen_space = false;
+    }
+}
+
+/**
+    Drawing surface handed out by [`Renderer::draw_frame`] for the duration of a single frame.
+*/
+pub trait Canvas {
+    // fills the union of `triangles`, blending every covered pixel only once where the backend can
+    fn fill_triangles(&mut self, triangles: &[Triangle], color: Rgba);
+
+    /**
+        Draws the `source` region of a texture as a parallelogram, with `transform`
+        taking texture pixels, relative to the top left corner of the region, to the screen.
+
+        Any affine transform works, including shears and mirroring.
+    */
+    fn draw_texture(&mut self, texture: TextureId, source: Region, transform: Matrix3, tint: Rgba);
+
+    // restricts drawing to a rectangle of the frame in pixels, or lifts the restriction with None
+    fn set_clip(&mut self, clip: Option<Region>);
+}
+
+/**
+    Passes drawing on to another canvas after taking it through `view`,
+    which is how the scene is drawn through a camera.
+*/
+pub struct ViewCanvas<'a> {
+    canvas: &'a mut dyn Canvas,
+    view: Matrix3,
+}
+
+impl ViewCanvas<'_> {
+    pub fn new(canvas: &mut dyn Canvas, view: Matrix3) -> ViewCanvas<'_> {
+        ViewCanvas { canvas, view }
+    }
+}
+
+impl Canvas for ViewCanvas<'_> {
+    fn fill_triangles(&mut self, triangles: &[Triangle], color: Rgba) {
+        let triangles = triangles.iter()
+            .map(|triangle| triangle.map(|(x, y)| self.view.transform_point(x, y)))
+            .collect::<Vec<_>>();
+        self.canvas.fill_triangles(&triangles, color);
+    }
+
+    fn draw_texture(&mut self, texture: TextureId, source: Region, transform: Matrix3, tint: Rgba) {
+        self.canvas.draw_texture(texture, source, self.view * transform, tint);
+    }
+
+    fn set_clip(&mut self, clip: Option<Region>) {
+        self.canvas.set_clip(clip);
+    }
+}
+
+/**
+    A rendering backend the engine draws through.
+
+    [`RaylibRenderer`] draws to a window, while [`SoftwareRenderer`] rasterizes
+    on the CPU into an in-memory framebuffer and needs neither a window nor a GPU.
+*/
+pub trait Renderer {
+    fn should_close(&self) -> bool;
+
+    // fails when the new size can't be drawn to, leaving the old one in place
+    fn set_size(&mut self, width: i32, height: i32) -> Result<(), String>;
+
+    fn set_title(&mut self, title: &str);
+
+    // frames per second to wait for between presenting frames, 0 for no limit
+    fn set_target_fps(&mut self, fps: u32);
+
+    fn set_vsync(&mut self, enabled: bool);
+
+    // creates the texture drawn with `id` from an image, replacing whatever had that id before
+    fn create_texture(&mut self, id: TextureId, image: Framebuffer) -> Result<(), String>;
+
+    fn unload_texture(&mut self, id: TextureId);
+
+    // the last presented frame, for backends that keep it in memory
+    fn framebuffer(&self) -> Option<&Framebuffer> {
+        None
+    }
+
+    // clears the frame to `clear`, lets `draw` fill it in and presents it
+    fn draw_frame(&mut self, clear: Rgba, draw: &mut dyn FnMut(&mut dyn Canvas));
+}
@@ -0,0 +1,219 @@
+use std::ffi::{c_void, CString};
+use std::path::Path;
+
+use raylib::prelude::*;
+
+use crate::math::Matrix3;
+
+use super::{Canvas, Framebuffer, Region, Renderer, Rgba, TextureId, Triangle};
+
+// rlgl is linked into raylib, but the Linux bindings of raylib-sys don't expose it
+const RL_QUADS: i32 = 0x0007;
+
+extern "C" {
+    fn rlCheckRenderBatchLimit(vertex_count: i32) -> bool;
+    fn rlSetTexture(id: u32);
+    fn rlBegin(mode: i32);
+    fn rlEnd();
+    fn rlColor4ub(r: u8, g: u8, b: u8, a: u8);
+    fn rlNormal3f(x: f32, y: f32, z: f32);
+    fn rlTexCoord2f(x: f32, y: f32);
+    fn rlVertex2f(x: f32, y: f32);
+}
+
+/**
+    Decodes any image raylib can load, like JPG, BMP, TGA or GIF, into RGBA8.
+
+    Only touches the file and memory, never the window, so it can run on the
+    threads textures are loaded on in the background.
+*/
+pub fn decode_image(path: &Path) -> Result<Framebuffer, String> {
+    let c_path = path.to_str()
+        .and_then(|path| CString::new(path).ok())
+        .ok_or_else(|| "the path can't be passed to raylib".to_string())?;
+
+    // SAFETY: the path is a valid C string for the duration of the call
+    let mut image = unsafe { ffi::LoadImage(c_path.as_ptr()) };
+    if image.data.is_null() {
+        return Err("raylib could not decode the image".into());
+    }
+
+    let rgba = ffi::PixelFormat::PIXELFORMAT_PIXELFORMAT_UNCOMPRESSED_R8G8B8A8 as i32;
+    // SAFETY: `image` was loaded above, raylib leaves it alone when it can't convert it
+    unsafe { ffi::ImageFormat(&mut image, rgba) };
+
+    // compressed formats can't be converted, and only RGBA8 pixels are as long as read below
+    if image.format != rgba || image.data.is_null() || image.width <= 0 || image.height <= 0 {
+        // SAFETY: `image` was loaded above and isn't used after this
+        unsafe { ffi::UnloadImage(image) };
+        return Err("raylib could not convert the image to RGBA8 pixels".into());
+    }
+
+    // SAFETY: the image is RGBA8, so its data holds 4 bytes for each of its pixels, and it is unloaded once they are copied out
+    let pixels = unsafe {
+        let length = image.width as usize * image.height as usize * 4;
+        let pixels = std::slice::from_raw_parts(image.data as *const u8, length).to_vec();
+        ffi::UnloadImage(image);
+        pixels
+    };
+
+    Ok(Framebuffer::from_pixels(image.width as u32, image.height as u32, pixels))
+}
+
+fn to_color(color: Rgba) -> Color {
+    Color::new(color.r, color.g, color.b, color.a)
+}
+
+pub struct RaylibRenderer {
+    handle: RaylibHandle,
+    thread: RaylibThread,
+    textures: Vec<Option<Texture2D>>,
+}
+
+impl RaylibRenderer {
+    pub fn new(width: i32, height: i32, title: &str) -> RaylibRenderer {
+        let (handle, thread) = raylib::init()
+            .size(width, height)
+            .title(title)
+            .build();
+
+        RaylibRenderer {
+            handle,
+            thread,
+            textures: Vec::new(),
+        }
+    }
+}
+
+struct RaylibCanvas<'a, 'b> {
+    draw_handle: &'a mut RaylibDrawHandle<'b>,
+    textures: &'a [Option<Texture2D>],
+}
+
+impl Canvas for RaylibCanvas<'_, '_> {
+    fn fill_triangles(&mut self, triangles: &[Triangle], color: Rgba) {
+        for &[a, b, c] in triangles {
+            // raylib culls triangles that aren't counter-clockwise on screen
+            let winding = (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0);
+            let (b, c) = if winding > 0.0 { (c, b) } else { (b, c) };
+
+            self.draw_handle.draw_triangle(
+                Vector2::new(a.0, a.1),
+                Vector2::new(b.0, b.1),
+                Vector2::new(c.0, c.1),
+                to_color(color),
+            );
+        }
+    }
+
+    fn draw_texture(&mut self, texture: TextureId, source: Region, transform: Matrix3, tint: Rgba) {
+        let Some(Some(texture)) = self.textures.get(texture) else {
+            return;
+        };
+        let texture: &ffi::Texture2D = texture.as_ref();
+
+        let (x, y, width, height) = source;
+        let (texture_width, texture_height) = (texture.width as f32, texture.height as f32);
+
+        // corners of the source region, counter-clockwise on screen unless the transform mirrors it
+        let mut corners = [(0.0, 0.0), (0.0, height), (width, height), (width, 0.0)];
+        if transform.m00 * transform.m11 - transform.m01 * transform.m10 < 0.0 {
+            corners.reverse();
+        }
+
+        // SAFETY: only called while drawing, after raylib has been initialized
+        unsafe {
+            rlCheckRenderBatchLimit(4);
+            rlSetTexture(texture.id);
+            rlBegin(RL_QUADS);
+            rlColor4ub(tint.r, tint.g, tint.b, tint.a);
+            rlNormal3f(0.0, 0.0, 1.0);
+
+            for (u, v) in corners {
+                let (px, py) = transform.transform_point(u, v);
+                rlTexCoord2f((x + u) / texture_width, (y + v) / texture_height);
+                rlVertex2f(px, py);
+            }
+
+            rlEnd();
+            rlSetTexture(0);
+        }
+    }
+
+    fn set_clip(&mut self, clip: Option<Region>) {
+        // SAFETY: only called while drawing, after raylib has been initialized
+        unsafe {
+            ffi::EndScissorMode();
+            if let Some((x, y, width, height)) = clip {
+                ffi::BeginScissorMode(x as i32, y as i32, width as i32, height as i32);
+            }
+        }
+    }
+}
+
+impl Renderer for RaylibRenderer {
+    fn should_close(&self) -> bool {
+        self.handle.window_should_close()
+    }
+
+    fn set_size(&mut self, width: i32, height: i32) -> Result<(), String> {
+        self.handle.set_window_size(width, height);
+        Ok(())
+    }
+
+    fn set_title(&mut self, title: &str) {
+        self.handle.set_window_title(&self.thread, title);
+    }
+
+    fn set_target_fps(&mut self, fps: u32) {
+        self.handle.set_target_fps(fps);
+    }
+
+    fn set_vsync(&mut self, enabled: bool) {
+        let state = WindowState::default().set_vsync_hint(true);
+        if enabled {
+            self.handle.set_window_state(state);
+        } else {
+            self.handle.clear_window_state(state);
+        }
+    }
+
+    fn create_texture(&mut self, id: TextureId, image: Framebuffer) -> Result<(), String> {
+        let raw = ffi::Image {
+            data: image.pixels().as_ptr() as *mut c_void,
+            width: image.width() as i32,
+            height: image.height() as i32,
+            mipmaps: 1,
+            format: ffi::PixelFormat::PIXELFORMAT_PIXELFORMAT_UNCOMPRESSED_R8G8B8A8 as i32,
+        };
+
+        // SAFETY: raylib only reads the pixels while uploading them, `image` outlives the call
+        let texture = unsafe { ffi::LoadTextureFromImage(raw) };
+        if texture.id == 0 {
+            return Err("the GPU texture could not be created".into());
+        }
+
+        if id >= self.textures.len() {
+            self.textures.resize_with(id + 1, || None);
+        }
+        // SAFETY: the texture was just created and nothing else owns it
+        self.textures[id] = Some(unsafe { Texture2D::from_raw(texture) });
+        Ok(())
+    }
+
+    fn unload_texture(&mut self, id: TextureId) {
+        if let Some(texture) = self.textures.get_mut(id) {
+            *texture = None;
+        }
+    }
+
+    fn draw_frame(&mut self, clear: Rgba, draw: &mut dyn FnMut(&mut dyn Canvas)) {
+        let mut draw_handle = self.handle.begin_drawing(&self.thread);
+        draw_handle.clear_background(to_color(clear));
+
+        draw(&mut RaylibCanvas {
+            draw_handle: &mut draw_handle,
+            textures: &self.textures,
+        });
+    }
+}
@@ -0,0 +1,221 @@
+use std::f32::consts::TAU;
+
+use super::{Point, Triangle};
+
+// longest a miter can get, as a multiple of half the stroke thickness, before it is clamped
+const MITER_LIMIT: f32 = 4.0;
+
+fn sub(a: Point, b: Point) -> Point {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+fn cross(o: Point, a: Point, b: Point) -> f32 {
+    (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+}
+
+fn normalize(v: Point) -> Option<Point> {
+    let length = (v.0 * v.0 + v.1 * v.1).sqrt();
+    if length > f32::EPSILON {
+        Some((v.0 / length, v.1 / length))
+    } else {
+        None
+    }
+}
+
+// unit normal to the left of the direction from `a` to `b`
+fn segment_normal(a: Point, b: Point) -> Option<Point> {
+    normalize(sub(b, a)).map(|(x, y)| (-y, x))
+}
+
+fn signed_area(points: &[Point]) -> f32 {
+    let mut area = 0.0;
+    for (i, a) in points.iter().enumerate() {
+        let b = points[(i + 1) % points.len()];
+        area += a.0 * b.1 - b.0 * a.1;
+    }
+    area / 2.0
+}
+
+fn contains(triangle: &Triangle, p: Point) -> bool {
+    let [a, b, c] = *triangle;
+    let d1 = cross(a, b, p);
+    let d2 = cross(b, c, p);
+    let d3 = cross(c, a, p);
+
+    let has_negative = d1 < 0.0 || d2 < 0.0 || d3 < 0.0;
+    let has_positive = d1 > 0.0 || d2 > 0.0 || d3 > 0.0;
+    !(has_negative && has_positive)
+}
+
+fn dedup(points: &[Point]) -> Vec<Point> {
+    let mut result: Vec<Point> = Vec::with_capacity(points.len());
+    for &p in points {
+        if result.last() != Some(&p) {
+            result.push(p);
+        }
+    }
+    if result.len() > 1 && result.first() == result.last() {
+        result.pop();
+    }
+    result
+}
+
+// enough segments for a curve of the given radius to look round at its size on screen
+fn segment_count(radius: f32, sweep: f32) -> usize {
+    let full_circle = (radius.abs().sqrt() * 4.0).clamp(12.0, 128.0);
+    ((full_circle * sweep.abs() / TAU).ceil() as usize).max(1)
+}
+
+/**
+    Points along an elliptical arc, angles in degrees, measured clockwise on
+    screen from the positive x axis. Both ends are included, and an arc
+    sweeping more than a full turn stops after one.
+*/
+pub fn arc(center: Point, radius_x: f32, radius_y: f32, start_angle: f32, end_angle: f32) -> Vec<Point> {
+    let start = start_angle.to_radians();
+    // past a full turn the arc only draws over itself
+    let sweep = (end_angle.to_radians() - start).clamp(-TAU, TAU);
+    let segments = segment_count(radius_x.max(radius_y), sweep);
+
+    (0..=segments)
+        .map(|i| {
+            let angle = start + sweep * i as f32 / segments as f32;
+            (center.0 + radius_x * angle.cos(), center.1 + radius_y * angle.sin())
+        })
+        .collect()
+}
+
+pub fn ellipse(center: Point, radius_x: f32, radius_y: f32) -> Vec<Point> {
+    let mut points = arc(center, radius_x, radius_y, 0.0, 360.0);
+    points.pop();
+    points
+}
+
+pub fn rectangle(x: f32, y: f32, width: f32, height: f32) -> Vec<Point> {
+    vec![(x, y), (x + width, y), (x + width, y + height), (x, y + height)]
+}
+
+pub fn rounded_rectangle(x: f32, y: f32, width: f32, height: f32, radius: f32) -> Vec<Point> {
+    let radius = radius.clamp(0.0, width.abs().min(height.abs()) / 2.0);
+    if radius <= 0.0 {
+        return rectangle(x, y, width, height);
+    }
+
+    let (left, top) = (x.min(x + width) + radius, y.min(y + height) + radius);
+    let (right, bottom) = (x.max(x + width) - radius, y.max(y + height) - radius);
+
+    let mut points = Vec::new();
+    points.extend(arc((right, top), radius, radius, -90.0, 0.0));
+    points.extend(arc((right, bottom), radius, radius, 0.0, 90.0));
+    points.extend(arc((left, bottom), radius, radius, 90.0, 180.0));
+    points.extend(arc((left, top), radius, radius, 180.0, 270.0));
+    points
+}
+
+/**
+    Splits a simple polygon, convex or concave, into triangles by ear clipping.
+
+    Self-intersecting polygons don't have ears everywhere, whatever is left of
+    them once no more ears can be found is filled as a fan.
+*/
+pub fn triangulate(points: &[Point]) -> Vec<Triangle> {
+    let points = dedup(points);
+    if points.len() < 3 {
+        return Vec::new();
+    }
+
+    let mut indices: Vec<usize> = (0..points.len()).collect();
+    if signed_area(&points) < 0.0 {
+        indices.reverse();
+    }
+
+    let mut triangles = Vec::with_capacity(points.len() - 2);
+
+    while indices.len() > 3 {
+        let count = indices.len();
+
+        let ear = (0..count).find(|&i| {
+            let a = points[indices[(i + count - 1) % count]];
+            let b = points[indices[i]];
+            let c = points[indices[(i + 1) % count]];
+
+            if cross(a, b, c) <= 0.0 {
+                return false;
+            }
+
+            let triangle = [a, b, c];
+            indices.iter()
+                .map(|&index| points[index])
+                .filter(|p| *p != a && *p != b && *p != c)
+                .all(|p| !contains(&triangle, p))
+        });
+
+        let Some(i) = ear else {
+            break;
+        };
+
+        triangles.push([
+            points[indices[(i + count - 1) % count]],
+            points[indices[i]],
+            points[indices[(i + 1) % count]],
+        ]);
+        indices.remove(i);
+    }
+
+    for i in 1..indices.len() - 1 {
+        triangles.push([points[indices[0]], points[indices[i]], points[indices[i + 1]]]);
+    }
+
+    triangles
+}
+
+/**
+    Triangles covering a line of the given thickness along `points`, centered
+    on the path. Corners are mitered, and open paths end with flat caps.
+*/
+pub fn stroke(points: &[Point], thickness: f32, closed: bool) -> Vec<Triangle> {
+    let points = dedup(points);
+    let count = points.len();
+    if count < 2 || thickness <= 0.0 {
+        return Vec::new();
+    }
+
+    let half = thickness / 2.0;
+    let mut left = Vec::with_capacity(count);
+    let mut right = Vec::with_capacity(count);
+
+    for (i, &p) in points.iter().enumerate() {
+        let previous = if i > 0 { Some(points[i - 1]) } else if closed { Some(points[count - 1]) } else { None };
+        let next = if i + 1 < count { Some(points[i + 1]) } else if closed { Some(points[0]) } else { None };
+
+        let normal_in = previous.and_then(|previous| segment_normal(previous, p));
+        let normal_out = next.and_then(|next| segment_normal(p, next));
+
+        let offset = match (normal_in, normal_out) {
+            (Some(a), Some(b)) => match normalize((a.0 + b.0, a.1 + b.1)) {
+                Some(miter) => {
+                    let length = (half / (miter.0 * a.0 + miter.1 * a.1)).min(half * MITER_LIMIT);
+                    (miter.0 * length, miter.1 * length)
+                }
+                // the path folds back onto itself here
+                None => (a.0 * half, a.1 * half),
+            },
+            (Some(n), None) | (None, Some(n)) => (n.0 * half, n.1 * half),
+            (None, None) => (0.0, 0.0),
+        };
+
+        left.push((p.0 + offset.0, p.1 + offset.1));
+        right.push((p.0 - offset.0, p.1 - offset.1));
+    }
+
+    let segments = if closed { count } else { count - 1 };
+    let mut triangles = Vec::with_capacity(segments * 2);
+
+    for i in 0..segments {
+        let j = (i + 1) % count;
+        triangles.push([left[i], left[j], right[j]]);
+        triangles.push([left[i], right[j], right[i]]);
+    }
+
+    triangles
+}
@@ -0,0 +1,319 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use crate::math::Matrix3;
+
+use super::{Canvas, Region, Renderer, Rgba, TextureId, Triangle};
+
+// decodes any PNG into (width, height, RGBA8 pixels)
+fn decode_png(path: &Path) -> Result<(u32, u32, Vec<u8>), String> {
+    let file = File::open(path).map_err(|err| err.to_string())?;
+
+    let mut decoder = png::Decoder::new(file);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+    let mut reader = decoder.read_info().map_err(|err| err.to_string())?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).map_err(|err| err.to_string())?;
+    buffer.truncate(info.buffer_size());
+
+    let pixels = match info.color_type {
+        png::ColorType::Rgba => buffer,
+        png::ColorType::Rgb => buffer.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+        png::ColorType::GrayscaleAlpha => buffer.chunks_exact(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+        png::ColorType::Grayscale => buffer.iter().flat_map(|&v| [v, v, v, 255]).collect(),
+        png::ColorType::Indexed => return Err("indexed PNGs should have been expanded by the decoder".into()),
+    };
+
+    Ok((info.width, info.height, pixels))
+}
+
+pub struct Texture {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Texture {
+    fn sample(&self, u: f32, v: f32) -> Option<Rgba> {
+        if u < 0.0 || v < 0.0 || u >= self.width as f32 || v >= self.height as f32 {
+            return None;
+        }
+
+        let index = ((v as u32 * self.width + u as u32) * 4) as usize;
+        let p = &self.pixels[index..index + 4];
+        Some(Rgba::new(p[0], p[1], p[2], p[3]))
+    }
+}
+
+/**
+    An RGBA8 image in row-major order, with no padding between rows.
+*/
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Framebuffer {
+    // bytes of pixels in a framebuffer of the size, `None` when no buffer can be that long
+    fn byte_len(width: u32, height: u32) -> Option<usize> {
+        (width as usize).checked_mul(height as usize)?.checked_mul(4).filter(|&length| length <= isize::MAX as usize)
+    }
+
+    pub fn new(width: u32, height: u32) -> Result<Framebuffer, String> {
+        let length = Framebuffer::byte_len(width, height)
+            .ok_or_else(|| format!("A {}x{} framebuffer is too large", width, height))?;
+        Ok(Framebuffer { width, height, pixels: vec![0; length] })
+    }
+
+    pub fn from_pixels(width: u32, height: u32, pixels: Vec<u8>) -> Framebuffer {
+        assert_eq!(Some(pixels.len()), Framebuffer::byte_len(width, height), "pixel buffer does not match the framebuffer size");
+        Framebuffer { width, height, pixels }
+    }
+
+    pub fn load_png(path: &Path) -> Result<Framebuffer, String> {
+        let (width, height, pixels) = decode_png(path)?;
+        Ok(Framebuffer { width, height, pixels })
+    }
+
+    pub fn save_png(&self, path: &Path) -> Result<(), String> {
+        let file = File::create(path).map_err(|err| err.to_string())?;
+
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header().map_err(|err| err.to_string())?;
+        writer.write_image_data(&self.pixels).map_err(|err| err.to_string())
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn clear(&mut self, color: Rgba) {
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&[color.r, color.g, color.b, color.a]);
+        }
+    }
+
+    // source-over blends `color` onto the pixel, ignoring pixels outside the framebuffer
+    pub fn blend_pixel(&mut self, x: i32, y: i32, color: Rgba) {
+        if x < 0 || y < 0 || x as u32 >= self.width || y as u32 >= self.height || color.a == 0 {
+            return;
+        }
+
+        let index = ((y as u32 * self.width + x as u32) * 4) as usize;
+        let dst = &mut self.pixels[index..index + 4];
+
+        let alpha = color.a as u32;
+        let inverse = 255 - alpha;
+        let mix = |src: u8, dst: u8| ((src as u32 * alpha + dst as u32 * inverse + 127) / 255) as u8;
+
+        dst[0] = mix(color.r, dst[0]);
+        dst[1] = mix(color.g, dst[1]);
+        dst[2] = mix(color.b, dst[2]);
+        dst[3] = (alpha + (dst[3] as u32 * inverse + 127) / 255) as u8;
+    }
+}
+
+// range of pixel indices whose centers lie inside [start, end)
+fn covered_pixels(start: f32, end: f32) -> std::ops::Range<i32> {
+    (start - 0.5).ceil() as i32..(end - 0.5).ceil() as i32
+}
+
+// twice the signed area of the triangle (a, b, p)
+fn edge(a: (f32, f32), b: (f32, f32), p: (f32, f32)) -> f32 {
+    (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0)
+}
+
+fn modulate(color: Rgba, tint: Rgba) -> Rgba {
+    let mul = |a: u8, b: u8| ((a as u32 * b as u32 + 127) / 255) as u8;
+    Rgba::new(mul(color.r, tint.r), mul(color.g, tint.g), mul(color.b, tint.b), mul(color.a, tint.a))
+}
+
+struct SoftwareCanvas<'a> {
+    framebuffer: &'a mut Framebuffer,
+    textures: &'a [Option<Texture>],
+    clip: Option<Region>,
+}
+
+impl SoftwareCanvas<'_> {
+    // the area that can be drawn to as min x, min y, max x, max y
+    fn bounds(&self) -> (f32, f32, f32, f32) {
+        let (width, height) = (self.framebuffer.width as f32, self.framebuffer.height as f32);
+        match self.clip {
+            Some((x, y, w, h)) => (x.max(0.0), y.max(0.0), (x + w).min(width), (y + h).min(height)),
+            None => (0.0, 0.0, width, height),
+        }
+    }
+}
+
+impl Canvas for SoftwareCanvas<'_> {
+    fn fill_triangles(&mut self, triangles: &[Triangle], color: Rgba) {
+        let (left, top, right, bottom) = self.bounds();
+        let points = triangles.iter().flatten();
+        let min_x = points.clone().map(|p| p.0).fold(f32::INFINITY, f32::min).max(left);
+        let max_x = points.clone().map(|p| p.0).fold(f32::NEG_INFINITY, f32::max).min(right);
+        let min_y = points.clone().map(|p| p.1).fold(f32::INFINITY, f32::min).max(top);
+        let max_y = points.map(|p| p.1).fold(f32::NEG_INFINITY, f32::max).min(bottom);
+
+        let (columns, rows) = (covered_pixels(min_x, max_x), covered_pixels(min_y, max_y));
+        if columns.is_empty() || rows.is_empty() {
+            return;
+        }
+
+        // triangles of the same shape share edges and may overlap, so coverage is
+        // gathered first and every pixel is blended at most once
+        let width = columns.len();
+        let mut coverage = vec![false; width * rows.len()];
+
+        for &[a, b, c] in triangles {
+            let area = edge(a, b, c);
+            if area == 0.0 {
+                continue;
+            }
+
+            // only the pixels around this triangle, inside the ones around the whole shape,
+            // centers on its far edges included since the edge test counts them as inside
+            let around = |start: f32, end: f32, shape: &std::ops::Range<i32>| {
+                (start - 0.5).ceil().max(shape.start as f32) as i32..((end - 0.5).floor() + 1.0).min(shape.end as f32) as i32
+            };
+            let triangle_columns = around(a.0.min(b.0).min(c.0), a.0.max(b.0).max(c.0), &columns);
+            let triangle_rows = around(a.1.min(b.1).min(c.1), a.1.max(b.1).max(c.1), &rows);
+
+            for py in triangle_rows {
+                let row = (py - rows.start) as usize;
+                for px in triangle_columns.clone() {
+                    let p = (px as f32 + 0.5, py as f32 + 0.5);
+                    let weights = [edge(b, c, p), edge(c, a, p), edge(a, b, p)];
+
+                    if weights.iter().all(|w| w * area >= 0.0) {
+                        coverage[row * width + (px - columns.start) as usize] = true;
+                    }
+                }
+            }
+        }
+
+        for (row, py) in rows.enumerate() {
+            for (column, px) in columns.clone().enumerate() {
+                if coverage[row * width + column] {
+                    self.framebuffer.blend_pixel(px, py, color);
+                }
+            }
+        }
+    }
+
+    fn draw_texture(&mut self, texture: TextureId, source: Region, transform: Matrix3, tint: Rgba) {
+        let Some(Some(texture)) = self.textures.get(texture) else {
+            return;
+        };
+
+        // a degenerate transform squashes the texture onto a line, nothing would be visible
+        let Some(inverse) = transform.affine_inverse() else {
+            return;
+        };
+
+        let (left, top, right, bottom) = self.bounds();
+        let (source_x, source_y, width, height) = source;
+        let corners = [(0.0, 0.0), (width, 0.0), (0.0, height), (width, height)]
+            .map(|(u, v)| transform.transform_point(u, v));
+        let min_x = corners.iter().map(|c| c.0).fold(f32::INFINITY, f32::min).max(left);
+        let max_x = corners.iter().map(|c| c.0).fold(f32::NEG_INFINITY, f32::max).min(right);
+        let min_y = corners.iter().map(|c| c.1).fold(f32::INFINITY, f32::min).max(top);
+        let max_y = corners.iter().map(|c| c.1).fold(f32::NEG_INFINITY, f32::max).min(bottom);
+
+        // every covered pixel center is mapped back into the source region
+        for py in covered_pixels(min_y, max_y) {
+            for px in covered_pixels(min_x, max_x) {
+                let (u, v) = inverse.transform_point(px as f32 + 0.5, py as f32 + 0.5);
+                if u < 0.0 || v < 0.0 || u >= width || v >= height {
+                    continue;
+                }
+
+                if let Some(texel) = texture.sample(source_x + u, source_y + v) {
+                    self.framebuffer.blend_pixel(px, py, modulate(texel, tint));
+                }
+            }
+        }
+    }
+
+    fn set_clip(&mut self, clip: Option<Region>) {
+        self.clip = clip;
+    }
+}
+
+/**
+    CPU rasterizer that renders into an in-memory [`Framebuffer`].
+*/
+pub struct SoftwareRenderer {
+    framebuffer: Framebuffer,
+    textures: Vec<Option<Texture>>,
+}
+
+impl SoftwareRenderer {
+    pub fn new(width: i32, height: i32) -> Result<SoftwareRenderer, String> {
+        Ok(SoftwareRenderer {
+            framebuffer: Framebuffer::new(width.max(0) as u32, height.max(0) as u32)?,
+            textures: Vec::new(),
+        })
+    }
+}
+
+impl Renderer for SoftwareRenderer {
+    fn should_close(&self) -> bool {
+        false
+    }
+
+    fn set_size(&mut self, width: i32, height: i32) -> Result<(), String> {
+        self.framebuffer = Framebuffer::new(width.max(0) as u32, height.max(0) as u32)?;
+        Ok(())
+    }
+
+    fn set_title(&mut self, _title: &str) {}
+
+    // frames are only ever presented when stepped, there is nothing to wait for
+    fn set_target_fps(&mut self, _fps: u32) {}
+
+    fn set_vsync(&mut self, _enabled: bool) {}
+
+    fn create_texture(&mut self, id: TextureId, image: Framebuffer) -> Result<(), String> {
+        if id >= self.textures.len() {
+            self.textures.resize_with(id + 1, || None);
+        }
+
+        let Framebuffer { width, height, pixels } = image;
+        self.textures[id] = Some(Texture { width, height, pixels });
+        Ok(())
+    }
+
+    fn unload_texture(&mut self, id: TextureId) {
+        if let Some(texture) = self.textures.get_mut(id) {
+            *texture = None;
+        }
+    }
+
+    fn framebuffer(&self) -> Option<&Framebuffer> {
+        Some(&self.framebuffer)
+    }
+
+    fn draw_frame(&mut self, clear: Rgba, draw: &mut dyn FnMut(&mut dyn Canvas)) {
+        self.framebuffer.clear(clear);
+
+        draw(&mut SoftwareCanvas {
+            framebuffer: &mut self.framebuffer,
+            textures: &self.textures,
+            clip: None,
+        });
+    }
+}
@@ -0,0 +1,395 @@
+use std::collections::VecDeque;
+
+use mlua::prelude::*;
+use mlua::AppDataRefMut;
+
+use crate::lune::table_builder::TableBuilder;
+
+// `wait` has to yield from Luau itself, since Rust callbacks can't yield across
+// the C boundary. The Rust side only records when the thread should wake up.
+const WAIT_SOURCE: &str = r#"
+local scheduleWait = ...
+
+return function(seconds)
+    if not coroutine.isyieldable() then
+        error("wait can only be called from inside a task", 2)
+    end
+
+    scheduleWait(coroutine.running(), seconds)
+    return coroutine.yield()
+end
+"#;
+
+enum ResumeWith {
+    // resumes with the amount of game time that passed since the thread went to sleep
+    Elapsed { since: f64 },
+    Args(Vec<LuaRegistryKey>),
+}
+
+struct ScheduledThread {
+    thread: LuaRegistryKey,
+    resume_at: f64,
+    resume_with: ResumeWith,
+}
+
+/**
+    Cooperative scheduler for Luau coroutines.
+
+    Threads are parked here by `wait`, `task.delay` and `task.defer`, and are
+    resumed by [`step`] once enough game time has passed. The scheduler lives
+    in the app data of the [`Lua`] instance so that both the frame loop and the
+    `task` library can reach it.
+*/
+pub struct Scheduler {
+    clock: f64,
+    waiting: Vec<ScheduledThread>,
+    deferred: VecDeque<ScheduledThread>,
+    // threads waiting on something other than the clock, like a Signal, by park id
+    parked: Vec<(u64, LuaRegistryKey)>,
+    next_park_id: u64,
+}
+
+impl Scheduler {
+    pub fn new() -> Scheduler {
+        Scheduler {
+            clock: 0.0,
+            waiting: Vec::new(),
+            deferred: VecDeque::new(),
+            parked: Vec::new(),
+            next_park_id: 0,
+        }
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Scheduler::new()
+    }
+}
+
+fn scheduler_mut(lua: &Lua) -> LuaResult<AppDataRefMut<'_, Scheduler>> {
+    lua.app_data_mut::<Scheduler>()
+        .ok_or_else(|| LuaError::RuntimeError("the task scheduler has not been installed".into()))
+}
+
+fn to_thread<'lua>(lua: &'lua Lua, callable: LuaValue<'lua>) -> LuaResult<LuaThread<'lua>> {
+    match callable {
+        LuaValue::Function(func) => lua.create_thread(func),
+        LuaValue::Thread(thread) => Ok(thread),
+        other => Err(LuaError::FromLuaConversionError {
+            from: other.type_name(),
+            to: "function or thread",
+            message: Some(format!("Expected function or thread, got {}", other.type_name())),
+        }),
+    }
+}
+
+fn pack_args(lua: &Lua, args: LuaMultiValue) -> LuaResult<Vec<LuaRegistryKey>> {
+    args.into_iter()
+        .map(|value| lua.create_registry_value(value))
+        .collect()
+}
+
+fn resume_scheduled(lua: &Lua, entry: ScheduledThread, clock: f64) -> LuaResult<()> {
+    let thread: LuaThread = lua.registry_value(&entry.thread)?;
+    lua.remove_registry_value(entry.thread)?;
+
+    let args = match entry.resume_with {
+        ResumeWith::Elapsed { since } => LuaMultiValue::from_vec(vec![LuaValue::Number(clock - since)]),
+        ResumeWith::Args(keys) => {
+            let mut values = Vec::with_capacity(keys.len());
+            for key in keys {
+                values.push(lua.registry_value::<LuaValue>(&key)?);
+                lua.remove_registry_value(key)?;
+            }
+            LuaMultiValue::from_vec(values)
+        }
+    };
+
+    // the thread may have finished or errored through some other path since it was parked
+    if thread.status() == LuaThreadStatus::Resumable {
+        thread.resume::<_, ()>(args)?;
+    }
+
+    Ok(())
+}
+
+/**
+    Starts `callable` (a function or a suspended thread) right away with the
+    given arguments, and returns the thread it is running on.
+*/
+pub fn spawn<'lua>(lua: &'lua Lua, callable: LuaValue<'lua>, args: LuaMultiValue<'lua>) -> LuaResult<LuaThread<'lua>> {
+    let thread = to_thread(lua, callable)?;
+    thread.resume::<_, ()>(args)?;
+    Ok(thread)
+}
+
+/**
+    Queues `callable` to be resumed at the end of the current scheduler step.
+*/
+pub fn defer<'lua>(lua: &'lua Lua, callable: LuaValue<'lua>, args: LuaMultiValue<'lua>) -> LuaResult<LuaThread<'lua>> {
+    let thread = to_thread(lua, callable)?;
+    let entry = ScheduledThread {
+        thread: lua.create_registry_value(thread.clone())?,
+        resume_at: 0.0,
+        resume_with: ResumeWith::Args(pack_args(lua, args)?),
+    };
+
+    scheduler_mut(lua)?.deferred.push_back(entry);
+    Ok(thread)
+}
+
+/**
+    Resumes `callable` with the given arguments after `seconds` of game time.
+*/
+pub fn delay<'lua>(lua: &'lua Lua, seconds: f64, callable: LuaValue<'lua>, args: LuaMultiValue<'lua>) -> LuaResult<LuaThread<'lua>> {
+    let thread = to_thread(lua, callable)?;
+    let thread_key = lua.create_registry_value(thread.clone())?;
+    let resume_with = ResumeWith::Args(pack_args(lua, args)?);
+
+    let mut scheduler = scheduler_mut(lua)?;
+    let resume_at = scheduler.clock + seconds.max(0.0);
+    scheduler.waiting.push(ScheduledThread { thread: thread_key, resume_at, resume_with });
+
+    Ok(thread)
+}
+
+/**
+    Parks `thread` until `seconds` of game time have passed. The caller is
+    responsible for actually yielding the thread afterwards.
+*/
+pub fn sleep(lua: &Lua, thread: LuaThread, seconds: f64) -> LuaResult<()> {
+    let thread_key = lua.create_registry_value(thread)?;
+
+    let mut scheduler = scheduler_mut(lua)?;
+    let since = scheduler.clock;
+    scheduler.waiting.push(ScheduledThread {
+        thread: thread_key,
+        resume_at: since + seconds.max(0.0),
+        resume_with: ResumeWith::Elapsed { since },
+    });
+
+    Ok(())
+}
+
+/**
+    Parks `thread` until whatever it waits on hands the returned id to
+    [`unpark`]. The caller is responsible for actually yielding the thread
+    afterwards.
+*/
+pub fn park(lua: &Lua, thread: LuaThread) -> LuaResult<u64> {
+    let thread_key = lua.create_registry_value(thread)?;
+
+    let mut scheduler = scheduler_mut(lua)?;
+    let id = scheduler.next_park_id;
+    scheduler.next_park_id += 1;
+    scheduler.parked.push((id, thread_key));
+
+    Ok(id)
+}
+
+/**
+    Takes back the thread parked under `id`, `None` once it has been
+    cancelled or unparked already.
+*/
+pub fn unpark(lua: &Lua, id: u64) -> LuaResult<Option<LuaThread<'_>>> {
+    let key = {
+        let mut scheduler = scheduler_mut(lua)?;
+        let Some(index) = scheduler.parked.iter().position(|(parked, _)| *parked == id) else {
+            return Ok(None);
+        };
+        scheduler.parked.swap_remove(index).1
+    };
+
+    let thread = lua.registry_value(&key)?;
+    lua.remove_registry_value(key)?;
+    Ok(Some(thread))
+}
+
+/**
+    Removes every pending resumption of `thread`, so it never runs again
+    through the scheduler.
+*/
+pub fn cancel(lua: &Lua, thread: LuaThread) -> LuaResult<()> {
+    let mut scheduler = scheduler_mut(lua)?;
+
+    let is_other = |entry: &ScheduledThread| {
+        lua.registry_value::<LuaThread>(&entry.thread)
+            .map(|scheduled| scheduled != thread)
+            .unwrap_or(true)
+    };
+
+    scheduler.waiting.retain(is_other);
+    scheduler.deferred.retain(is_other);
+    scheduler.parked.retain(|(_, key)| {
+        lua.registry_value::<LuaThread>(key)
+            .map(|parked| parked != thread)
+            .unwrap_or(true)
+    });
+
+    Ok(())
+}
+
+/**
+    Advances the scheduler clock by `dt` seconds, resumes every thread whose
+    wait has elapsed (in wake-up order) and then every deferred thread.
+    Returns the first error a thread raised, after resuming the rest.
+
+    Threads that are scheduled again while being resumed are picked up on the
+    next step, so a `wait()` loop runs at most once per frame.
+*/
+pub fn step(lua: &Lua, dt: f64) -> LuaResult<()> {
+    let (clock, due, deferred) = {
+        let mut scheduler = scheduler_mut(lua)?;
+        scheduler.clock += dt;
+        let clock = scheduler.clock;
+
+        let (mut due, waiting): (Vec<_>, Vec<_>) = scheduler.waiting
+            .drain(..)
+            .partition(|entry| entry.resume_at <= clock);
+        scheduler.waiting = waiting;
+        due.sort_by(|a, b| a.resume_at.total_cmp(&b.resume_at));
+
+        let deferred: Vec<_> = scheduler.deferred.drain(..).collect();
+        (clock, due, deferred)
+    };
+
+    // a thread that errors doesn't keep the ones after it from running, the first error is returned once all have
+    let mut first_error = None;
+    for entry in due.into_iter().chain(deferred) {
+        if let Err(err) = resume_scheduled(lua, entry, clock) {
+            first_error.get_or_insert(err);
+        }
+    }
+
+    first_error.map_or(Ok(()), Err)
+}
+
+/**
+    Creates the `task` library. Expects a [`Scheduler`] to already be stored
+    in the app data of `lua`.
+*/
+pub fn module(lua: &Lua) -> LuaResult<LuaTable<'_>> {
+    let schedule_wait = lua.create_function(|lua, (thread, seconds): (LuaThread, Option<f64>)| {
+        sleep(lua, thread, seconds.unwrap_or(0.0))
+    })?;
+
+    let wait: LuaFunction = lua.load(WAIT_SOURCE)
+        .set_name("wait")
+        .call(schedule_wait)?;
+
+    TableBuilder::new(lua)?
+        .with_value("wait", wait)?
+        .with_function("spawn", |lua, (callable, args): (LuaValue, LuaMultiValue)| {
+            spawn(lua, callable, args)
+        })?
+        .with_function("defer", |lua, (callable, args): (LuaValue, LuaMultiValue)| {
+            defer(lua, callable, args)
+        })?
+        .with_function("delay", |lua, (seconds, callable, args): (Option<f64>, LuaValue, LuaMultiValue)| {
+            delay(lua, seconds.unwrap_or(0.0), callable, args)
+        })?
+        .with_function("cancel", |lua, thread: LuaThread| {
+            cancel(lua, thread)
+        })?
+        .build_readonly()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lua() -> Lua {
+        let lua = Lua::new();
+        lua.set_app_data(Scheduler::new());
+        lua.globals().set("task", module(&lua).unwrap()).unwrap();
+        lua.load("order = {}").exec().unwrap();
+        lua
+    }
+
+    fn order(lua: &Lua) -> Vec<String> {
+        lua.globals().get("order").unwrap()
+    }
+
+    #[test]
+    fn waits_resume_in_wake_up_order() {
+        let lua = lua();
+        lua.load(r#"
+            task.spawn(function() task.wait(0.2) table.insert(order, "slow") end)
+            task.spawn(function() task.wait(0.1) table.insert(order, "fast") end)
+            task.spawn(function() task.wait() table.insert(order, "next") end)
+        "#).exec().unwrap();
+
+        step(&lua, 0.05).unwrap();
+        assert_eq!(order(&lua), ["next"]);
+        step(&lua, 0.2).unwrap();
+        assert_eq!(order(&lua), ["next", "fast", "slow"]);
+    }
+
+    #[test]
+    fn wait_returns_the_time_waited() {
+        let lua = lua();
+        lua.load("task.spawn(function() waited = task.wait(0.1) end)").exec().unwrap();
+        step(&lua, 0.25).unwrap();
+        assert_eq!(lua.globals().get::<_, f64>("waited").unwrap(), 0.25);
+    }
+
+    #[test]
+    fn deferred_threads_run_after_due_ones() {
+        let lua = lua();
+        lua.load(r#"
+            task.defer(function(name) table.insert(order, name) end, "deferred")
+            task.delay(0, function(name) table.insert(order, name) end, "delayed")
+            table.insert(order, "now")
+        "#).exec().unwrap();
+
+        assert_eq!(order(&lua), ["now"]);
+        step(&lua, 0.0).unwrap();
+        assert_eq!(order(&lua), ["now", "delayed", "deferred"]);
+    }
+
+    #[test]
+    fn delays_wait_for_game_time() {
+        let lua = lua();
+        lua.load(r#"task.delay(1, function() table.insert(order, "late") end)"#).exec().unwrap();
+
+        step(&lua, 0.5).unwrap();
+        assert!(order(&lua).is_empty());
+        step(&lua, 0.5).unwrap();
+        assert_eq!(order(&lua), ["late"]);
+    }
+
+    #[test]
+    fn cancelled_threads_never_resume() {
+        let lua = lua();
+        lua.load(r#"
+            local waiting = task.spawn(function() task.wait(0.1) table.insert(order, "waiting") end)
+            local delayed = task.delay(0.1, function() table.insert(order, "delayed") end)
+            local deferred = task.defer(function() table.insert(order, "deferred") end)
+            task.defer(function() table.insert(order, "kept") end)
+            task.cancel(waiting)
+            task.cancel(delayed)
+            task.cancel(deferred)
+        "#).exec().unwrap();
+
+        step(&lua, 1.0).unwrap();
+        assert_eq!(order(&lua), ["kept"]);
+    }
+
+    #[test]
+    fn an_error_leaves_the_other_threads_running() {
+        let lua = lua();
+        lua.load(r#"
+            task.delay(0, function() table.insert(order, "before") end)
+            task.delay(0, function() error("first") end)
+            task.delay(0, function() error("second") end)
+            task.delay(0, function() table.insert(order, "after") end)
+            task.defer(function() table.insert(order, "deferred") end)
+        "#).exec().unwrap();
+
+        let error = step(&lua, 0.0).unwrap_err();
+        assert!(error.to_string().contains("first"), "{}", error);
+        assert_eq!(order(&lua), ["before", "after", "deferred"]);
+        step(&lua, 0.0).unwrap();
+    }
+}
@@ -0,0 +1,622 @@
+use core::fmt;
+use std::cell::RefCell;
+use std::f64::consts::PI;
+use std::rc::Rc;
+
+use mlua::prelude::*;
+use mlua::AppDataRefMut;
+
+use crate::lune::signal::Signal;
+use crate::lune::table_builder::TableBuilder;
+use crate::lune::userdata::*;
+use crate::math::{Color, Matrix3, Vector2};
+
+// properties are read and written through Luau, so that tweens go through
+// the same fields scripts use, whatever the target is
+const PROPERTY_SOURCE: &str = r#"
+return function(target, property)
+    return target[property]
+end, function(target, property, value)
+    target[property] = value
+end
+"#;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EasingStyle {
+    Linear,
+    Quad,
+    Cubic,
+    // pulls back a little before heading to the goal
+    Back,
+    // springs around the goal
+    Elastic,
+    // bounces off the goal, like a dropped ball
+    Bounce,
+}
+
+const EASING_STYLES: &[(&str, EasingStyle)] = &[
+    ("Linear", EasingStyle::Linear),
+    ("Quad", EasingStyle::Quad),
+    ("Cubic", EasingStyle::Cubic),
+    ("Back", EasingStyle::Back),
+    ("Elastic", EasingStyle::Elastic),
+    ("Bounce", EasingStyle::Bounce),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EasingDirection {
+    In,
+    Out,
+    InOut,
+}
+
+const EASING_DIRECTIONS: &[(&str, EasingDirection)] = &[
+    ("In", EasingDirection::In),
+    ("Out", EasingDirection::Out),
+    ("InOut", EasingDirection::InOut),
+];
+
+fn parse_name<T: Copy>(names: &[(&str, T)], kind: &str, name: &str) -> LuaResult<T> {
+    names.iter()
+        .find(|(n, _)| *n == name)
+        .map(|&(_, value)| value)
+        .ok_or_else(|| {
+            let names = names.iter().map(|(n, _)| *n).collect::<Vec<_>>().join(", ");
+            LuaError::RuntimeError(format!("'{}' is not {}, expected one of {}", name, kind, names))
+        })
+}
+
+fn bounce_out(t: f64) -> f64 {
+    const N: f64 = 7.5625;
+    const D: f64 = 2.75;
+
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+impl EasingStyle {
+    // the `In` curve, the other directions are made from it
+    fn ease_in(self, t: f64) -> f64 {
+        const BACK: f64 = 1.70158;
+
+        match self {
+            EasingStyle::Linear => t,
+            EasingStyle::Quad => t * t,
+            EasingStyle::Cubic => t * t * t,
+            EasingStyle::Back => (BACK + 1.0) * t * t * t - BACK * t * t,
+            EasingStyle::Elastic if t <= 0.0 || t >= 1.0 => t,
+            EasingStyle::Elastic => -(2f64.powf(10.0 * t - 10.0)) * ((10.0 * t - 10.75) * 2.0 * PI / 3.0).sin(),
+            EasingStyle::Bounce => 1.0 - bounce_out(1.0 - t),
+        }
+    }
+}
+
+/**
+    How far along a tween is after `t` of its time has passed, both from 0 to
+    1 at the ends. `Back` and `Elastic` go past those in between.
+*/
+pub fn ease(style: EasingStyle, direction: EasingDirection, t: f64) -> f64 {
+    let t = t.clamp(0.0, 1.0);
+    match direction {
+        EasingDirection::In => style.ease_in(t),
+        EasingDirection::Out => 1.0 - style.ease_in(1.0 - t),
+        EasingDirection::InOut if t < 0.5 => style.ease_in(2.0 * t) / 2.0,
+        EasingDirection::InOut => 1.0 - style.ease_in(2.0 - 2.0 * t) / 2.0,
+    }
+}
+
+/**
+    How a tween plays, `{ Time = 1, EasingStyle = "Quad", EasingDirection = "Out" }`
+    in Luau, or just the time as a number.
+
+    A tween plays `RepeatCount` more times after the first, forever if it
+    is -1. With `Reverses` every time also plays back to the start, and
+    `DelayTime` is waited out before every time.
+*/
+#[derive(Debug, Clone, Copy)]
+pub struct TweenInfo {
+    time: f64,
+    style: EasingStyle,
+    direction: EasingDirection,
+    repeat_count: i32,
+    reverses: bool,
+    delay: f64,
+}
+
+impl Default for TweenInfo {
+    fn default() -> TweenInfo {
+        TweenInfo {
+            time: 1.0,
+            style: EasingStyle::Quad,
+            direction: EasingDirection::Out,
+            repeat_count: 0,
+            reverses: false,
+            delay: 0.0,
+        }
+    }
+}
+
+impl<'lua> FromLua<'lua> for TweenInfo {
+    fn from_lua(value: LuaValue<'lua>, _: &'lua Lua) -> LuaResult<TweenInfo> {
+        let defaults = TweenInfo::default();
+        let info = match value {
+            LuaValue::Nil => defaults,
+            LuaValue::Number(time) => TweenInfo { time, ..defaults },
+            LuaValue::Integer(time) => TweenInfo { time: time as f64, ..defaults },
+            LuaValue::Table(table) => TweenInfo {
+                time: table.get::<_, Option<f64>>("Time")?.unwrap_or(defaults.time),
+                style: match table.get::<_, Option<String>>("EasingStyle")? {
+                    Some(name) => parse_name(EASING_STYLES, "an easing style", &name)?,
+                    None => defaults.style,
+                },
+                direction: match table.get::<_, Option<String>>("EasingDirection")? {
+                    Some(name) => parse_name(EASING_DIRECTIONS, "an easing direction", &name)?,
+                    None => defaults.direction,
+                },
+                repeat_count: table.get::<_, Option<i32>>("RepeatCount")?.unwrap_or(defaults.repeat_count),
+                reverses: table.get::<_, Option<bool>>("Reverses")?.unwrap_or(defaults.reverses),
+                delay: table.get::<_, Option<f64>>("DelayTime")?.unwrap_or(defaults.delay),
+            },
+            other => return Err(LuaError::RuntimeError(format!("Expected a tween info table, got {}", other.type_name()))),
+        };
+
+        if info.time.is_nan() || info.time < 0.0 {
+            return Err(LuaError::RuntimeError(format!("Time must be 0 or greater, got {}", info.time)));
+        }
+        if info.delay.is_nan() || info.delay < 0.0 {
+            return Err(LuaError::RuntimeError(format!("DelayTime must be 0 or greater, got {}", info.delay)));
+        }
+        if info.repeat_count < -1 {
+            return Err(LuaError::RuntimeError(format!("RepeatCount must be -1 or greater, got {}", info.repeat_count)));
+        }
+
+        Ok(info)
+    }
+}
+
+// the kinds of values that can be tweened
+#[derive(Debug, Clone, Copy)]
+enum TweenValue {
+    Number(f64),
+    Vector2(Vector2),
+    Matrix3(Matrix3),
+    Color(Color),
+}
+
+impl TweenValue {
+    fn read(value: &LuaValue) -> Option<TweenValue> {
+        match value {
+            LuaValue::Number(n) => Some(TweenValue::Number(*n)),
+            LuaValue::Integer(n) => Some(TweenValue::Number(*n as f64)),
+            LuaValue::UserData(ud) => ud.borrow::<Vector2>().map(|v| TweenValue::Vector2(*v))
+                .or_else(|_| ud.borrow::<Matrix3>().map(|m| TweenValue::Matrix3(*m)))
+                .or_else(|_| ud.borrow::<Color>().map(|c| TweenValue::Color(*c)))
+                .ok(),
+            _ => None,
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            TweenValue::Number(_) => "number",
+            TweenValue::Vector2(_) => "Vector2",
+            TweenValue::Matrix3(_) => "Matrix3",
+            TweenValue::Color(_) => "Color",
+        }
+    }
+
+    // `None` if the two are different kinds of values
+    fn lerp(self, goal: TweenValue, alpha: f64) -> Option<TweenValue> {
+        Some(match (self, goal) {
+            (TweenValue::Number(a), TweenValue::Number(b)) => TweenValue::Number(a + (b - a) * alpha),
+            (TweenValue::Vector2(a), TweenValue::Vector2(b)) => TweenValue::Vector2(a.lerp(b, alpha as f32)),
+            (TweenValue::Matrix3(a), TweenValue::Matrix3(b)) => TweenValue::Matrix3(a.lerp(b, alpha as f32)),
+            (TweenValue::Color(a), TweenValue::Color(b)) => TweenValue::Color(a.lerp(b, alpha as f32)),
+            _ => return None,
+        })
+    }
+}
+
+impl<'lua> IntoLua<'lua> for TweenValue {
+    fn into_lua(self, lua: &'lua Lua) -> LuaResult<LuaValue<'lua>> {
+        match self {
+            TweenValue::Number(n) => n.into_lua(lua),
+            TweenValue::Vector2(v) => v.into_lua(lua),
+            TweenValue::Matrix3(m) => m.into_lua(lua),
+            TweenValue::Color(c) => c.into_lua(lua),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PlaybackState {
+    Begin,
+    Delayed,
+    Playing,
+    Paused,
+    Completed,
+    Cancelled,
+}
+
+impl PlaybackState {
+    fn name(self) -> &'static str {
+        match self {
+            PlaybackState::Begin => "Begin",
+            PlaybackState::Delayed => "Delayed",
+            PlaybackState::Playing => "Playing",
+            PlaybackState::Paused => "Paused",
+            PlaybackState::Completed => "Completed",
+            PlaybackState::Cancelled => "Cancelled",
+        }
+    }
+}
+
+struct TweenState {
+    target: LuaRegistryKey,
+    info: TweenInfo,
+    goals: Vec<(String, TweenValue)>,
+    // read from the target when the first time starts, empty until then
+    starts: Vec<TweenValue>,
+    // time into the current repeat, the delay included
+    elapsed: f64,
+    repeats: u32,
+    playback: PlaybackState,
+}
+
+impl TweenState {
+    // how far the properties are from their starts to their goals, before easing
+    fn progress(&self) -> f64 {
+        let info = &self.info;
+        let time = (self.elapsed - info.delay).max(0.0);
+        let time = if info.reverses && time > info.time { 2.0 * info.time - time } else { time };
+
+        if info.time > 0.0 {
+            (time / info.time).clamp(0.0, 1.0)
+        } else if info.reverses {
+            0.0
+        } else {
+            1.0
+        }
+    }
+
+    // the full length of a single repeat, not counting the delay
+    fn length(&self) -> f64 {
+        if self.info.reverses { 2.0 * self.info.time } else { self.info.time }
+    }
+}
+
+/**
+    Animates properties of a target from their current values to goals,
+    created with `TweenService.create(target, info, goals)`.
+
+    Tweens advance by game time each frame until they complete, then fire
+    `Completed` with how they ended.
+*/
+#[derive(Clone)]
+pub struct Tween {
+    state: Rc<RefCell<TweenState>>,
+    completed: Signal,
+}
+
+impl PartialEq for Tween {
+    fn eq(&self, other: &Tween) -> bool {
+        Rc::ptr_eq(&self.state, &other.state)
+    }
+}
+
+/**
+    Every tween that is playing, advanced by [`update`]. Lives in the app
+    data of the [`Lua`] instance.
+*/
+pub struct Tweens {
+    get: LuaRegistryKey,
+    set: LuaRegistryKey,
+    playing: Vec<Tween>,
+}
+
+impl Tweens {
+    pub fn new(lua: &Lua) -> LuaResult<Tweens> {
+        let (get, set) = lua.load(PROPERTY_SOURCE)
+            .set_name("tween")
+            .call::<_, (LuaFunction, LuaFunction)>(())?;
+
+        Ok(Tweens {
+            get: lua.create_registry_value(get)?,
+            set: lua.create_registry_value(set)?,
+            playing: Vec::new(),
+        })
+    }
+}
+
+fn tweens_mut(lua: &Lua) -> LuaResult<AppDataRefMut<'_, Tweens>> {
+    lua.app_data_mut::<Tweens>()
+        .ok_or_else(|| LuaError::RuntimeError("Bee2D has not been initialized".into()))
+}
+
+fn accessors(lua: &Lua) -> LuaResult<(LuaFunction<'_>, LuaFunction<'_>)> {
+    let tweens = tweens_mut(lua)?;
+    Ok((lua.registry_value(&tweens.get)?, lua.registry_value(&tweens.set)?))
+}
+
+fn read_property<'lua>(lua: &'lua Lua, target: &LuaValue<'lua>, property: &str, goal: TweenValue) -> LuaResult<TweenValue> {
+    let (get, _) = accessors(lua)?;
+    let value = get.call::<_, LuaValue>((target.clone(), property))?;
+
+    match TweenValue::read(&value) {
+        Some(current) if current.lerp(goal, 0.0).is_some() => Ok(current),
+        current => Err(LuaError::RuntimeError(format!(
+            "Can't tween {} from a {} to a {}",
+            property,
+            current.map_or(value.type_name(), |current| current.type_name()),
+            goal.type_name()
+        ))),
+    }
+}
+
+impl Tween {
+    fn new<'lua>(lua: &'lua Lua, target: LuaValue<'lua>, info: TweenInfo, goals: LuaTable<'lua>) -> LuaResult<Tween> {
+        if !matches!(target, LuaValue::UserData(_) | LuaValue::Table(_)) {
+            return Err(LuaError::RuntimeError(format!("Expected userdata or a table to tween, got {}", target.type_name())));
+        }
+
+        let mut parsed = Vec::new();
+        for pair in goals.pairs::<String, LuaValue>() {
+            let (property, goal) = pair?;
+            let goal = TweenValue::read(&goal).ok_or_else(|| LuaError::RuntimeError(format!(
+                "Can't tween {} to a {}, expected a number, Vector2, Matrix3 or Color", property, goal.type_name()
+            )))?;
+
+            // checked now so a mistake shows up where the tween is created, not somewhere in the frame loop,
+            // read-only properties only show up once the first step writes them, nothing is written before Play
+            read_property(lua, &target, &property, goal)?;
+            parsed.push((property, goal));
+        }
+        // `pairs` has no order, this keeps the order properties are written in the same every time
+        parsed.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        Ok(Tween {
+            state: Rc::new(RefCell::new(TweenState {
+                target: lua.create_registry_value(target)?,
+                info,
+                goals: parsed,
+                starts: Vec::new(),
+                elapsed: 0.0,
+                repeats: 0,
+                playback: PlaybackState::Begin,
+            })),
+            completed: Signal::new(),
+        })
+    }
+
+    fn playback(&self) -> PlaybackState {
+        self.state.borrow().playback
+    }
+
+    fn is_playing(&self) -> bool {
+        matches!(self.playback(), PlaybackState::Playing | PlaybackState::Delayed)
+    }
+
+    // whether both tweens change one of the same properties of the same target
+    fn overlaps(&self, lua: &Lua, other: &Tween) -> LuaResult<bool> {
+        let (this, other) = (self.state.borrow(), other.state.borrow());
+        let shares_property = this.goals.iter()
+            .any(|(property, _)| other.goals.iter().any(|(p, _)| p == property));
+
+        Ok(shares_property && lua.registry_value::<LuaValue>(&this.target)?
+            .equals(lua.registry_value::<LuaValue>(&other.target)?)?)
+    }
+
+    /**
+        Starts the tween over, or carries on after `pause`. Other tweens
+        playing on the same properties of the target are cancelled.
+    */
+    pub fn play(&self, lua: &Lua) -> LuaResult<()> {
+        {
+            let mut state = self.state.borrow_mut();
+            match state.playback {
+                PlaybackState::Playing | PlaybackState::Delayed => return Ok(()),
+                PlaybackState::Paused => {}
+                PlaybackState::Begin | PlaybackState::Completed | PlaybackState::Cancelled => {
+                    state.starts.clear();
+                    state.elapsed = 0.0;
+                    state.repeats = 0;
+                }
+            }
+            state.playback = if state.elapsed < state.info.delay { PlaybackState::Delayed } else { PlaybackState::Playing };
+        }
+
+        let playing = tweens_mut(lua)?.playing.clone();
+        for other in playing {
+            if other != *self && other.is_playing() && self.overlaps(lua, &other)? {
+                other.cancel(lua)?;
+            }
+        }
+
+        let mut tweens = tweens_mut(lua)?;
+        if !tweens.playing.contains(self) {
+            tweens.playing.push(self.clone());
+        }
+        Ok(())
+    }
+
+    pub fn pause(&self) {
+        if self.is_playing() {
+            self.state.borrow_mut().playback = PlaybackState::Paused;
+        }
+    }
+
+    // stops the tween where it is and forgets how far it got
+    pub fn cancel(&self, lua: &Lua) -> LuaResult<()> {
+        {
+            let mut state = self.state.borrow_mut();
+            if !matches!(state.playback, PlaybackState::Playing | PlaybackState::Delayed | PlaybackState::Paused) {
+                return Ok(());
+            }
+            state.playback = PlaybackState::Cancelled;
+            state.elapsed = 0.0;
+            state.repeats = 0;
+        }
+
+        self.completed.fire(lua, PlaybackState::Cancelled.name())
+    }
+
+    // cancels the tween, so a target that is gone or a property that can't be written fails only once
+    fn abort(&self, lua: &Lua, property: &str, err: LuaError) -> LuaResult<()> {
+        self.cancel(lua)?;
+        Err(LuaError::RuntimeError(format!("Can't tween {}: {}", property, err)))
+    }
+
+    fn step(&self, lua: &Lua, dt: f64) -> LuaResult<()> {
+        let (target, needs_starts) = {
+            let mut state = self.state.borrow_mut();
+            if !matches!(state.playback, PlaybackState::Playing | PlaybackState::Delayed) {
+                return Ok(());
+            }
+
+            state.elapsed += dt;
+            if state.elapsed < state.info.delay {
+                return Ok(());
+            }
+            state.playback = PlaybackState::Playing;
+            (lua.registry_value::<LuaValue>(&state.target)?, state.starts.is_empty())
+        };
+
+        if needs_starts {
+            let goals = self.state.borrow().goals.clone();
+            let mut starts = Vec::with_capacity(goals.len());
+            for (property, goal) in goals {
+                match read_property(lua, &target, &property, goal) {
+                    Ok(start) => starts.push(start),
+                    Err(err) => return self.abort(lua, &property, err),
+                }
+            }
+            self.state.borrow_mut().starts = starts;
+        }
+
+        let (values, completed) = {
+            let mut state = self.state.borrow_mut();
+            let info = state.info;
+            let mut completed = false;
+
+            if state.elapsed - info.delay >= state.length() {
+                let finite = info.repeat_count >= 0;
+                if finite && state.repeats >= info.repeat_count as u32 {
+                    completed = true;
+                    state.playback = PlaybackState::Completed;
+                    state.elapsed = info.delay + state.length();
+                } else {
+                    // at most one repeat starts a frame, so a short tween can't stall the frame
+                    state.repeats += 1;
+                    state.elapsed = (state.elapsed - info.delay - state.length()).min(info.delay + state.length());
+                    if state.elapsed < info.delay {
+                        state.playback = PlaybackState::Delayed;
+                    }
+                }
+            }
+
+            // while waiting out the delay of a repeat, the properties are left where the last one ended
+            let progress = match state.playback {
+                PlaybackState::Delayed if info.reverses => 0.0,
+                PlaybackState::Delayed => 1.0,
+                _ => state.progress(),
+            };
+            let alpha = ease(info.style, info.direction, progress);
+
+            let values = state.goals.iter()
+                .zip(&state.starts)
+                .filter_map(|((property, goal), start)| Some((property.clone(), start.lerp(*goal, alpha)?)))
+                .collect::<Vec<_>>();
+            (values, completed)
+        };
+
+        let (_, set) = accessors(lua)?;
+        for (property, value) in values {
+            if let Err(err) = set.call::<_, ()>((target.clone(), property.as_str(), value)) {
+                return self.abort(lua, &property, err);
+            }
+        }
+
+        if completed {
+            self.completed.fire(lua, PlaybackState::Completed.name())?;
+        }
+
+        Ok(())
+    }
+}
+
+/**
+    Advances every playing tween by `dt` seconds of game time and writes the
+    new values to their targets. One that fails is cancelled without holding
+    up the rest, the first error is returned once all of them stepped.
+*/
+pub fn update(lua: &Lua, dt: f64) -> LuaResult<()> {
+    let playing = tweens_mut(lua)?.playing.clone();
+    let mut first_error = None;
+    for tween in playing {
+        if let Err(err) = tween.step(lua, dt) {
+            first_error.get_or_insert(err);
+        }
+    }
+
+    tweens_mut(lua)?.playing.retain(Tween::is_playing);
+    first_error.map_or(Ok(()), Err)
+}
+
+impl LuaUserData for Tween {
+    fn add_fields<'lua, F: LuaUserDataFields<'lua, Self>>(fields: &mut F) {
+        fields.add_field_method_get("PlaybackState", |_, this| Ok(this.playback().name()));
+        fields.add_field_method_get("Completed", |_, this| Ok(this.completed.clone()));
+        fields.add_field_method_get("Target", |lua, this| lua.registry_value::<LuaValue>(&this.state.borrow().target));
+    }
+
+    fn add_methods<'lua, M: LuaUserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_method("Play", |lua, this, ()| this.play(lua));
+        methods.add_method("Pause", |_, this, ()| {
+            this.pause();
+            Ok(())
+        });
+        methods.add_method("Cancel", |lua, this, ()| this.cancel(lua));
+
+        methods.add_meta_method(LuaMetaMethod::Eq, userdata_impl_eq);
+        methods.add_meta_method(LuaMetaMethod::ToString, userdata_impl_to_string);
+    }
+}
+
+impl fmt::Display for Tween {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Tween")
+    }
+}
+
+/**
+    Creates the `TweenService` library. Expects [`Tweens`] to already be
+    stored in the app data of `lua`.
+*/
+pub fn module(lua: &Lua) -> LuaResult<LuaTable<'_>> {
+    TableBuilder::new(lua)?
+        .with_function("create", |lua, (target, info, goals): (LuaValue, TweenInfo, LuaTable)| {
+            Tween::new(lua, target, info, goals)
+        })?
+        .with_function("getValue", |_, (alpha, style, direction): (f64, Option<String>, Option<String>)| {
+            let style = match style {
+                Some(name) => parse_name(EASING_STYLES, "an easing style", &name)?,
+                None => EasingStyle::Linear,
+            };
+            let direction = match direction {
+                Some(name) => parse_name(EASING_DIRECTIONS, "an easing direction", &name)?,
+                None => EasingDirection::In,
+            };
+            Ok(ease(style, direction, alpha))
+        })?
+        .build_readonly()
+}
@@ -0,0 +1,97 @@
+use std::path::{Path, PathBuf};
+
+use bee2d_rust::{Framebuffer, GoldenTest, Outcome};
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+}
+
+fn temp(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("bee2d-golden-{}-{}", std::process::id(), name))
+}
+
+fn golden_test(script: &Path, extra: &[&str]) -> GoldenTest {
+    let reference = fixture("golden.png");
+    let args = [script.to_str().unwrap(), "--reference", reference.to_str().unwrap(), "--frames", "2"];
+    GoldenTest::from_args(args.iter().chain(extra).map(|arg| arg.to_string())).unwrap()
+}
+
+#[test]
+fn the_scene_matches_its_reference() {
+    match golden_test(&fixture("golden.luau"), &[]).run().unwrap() {
+        Outcome::Passed => {}
+        Outcome::Updated => panic!("the reference was updated instead of compared"),
+        Outcome::Failed(message) => panic!("{}", message),
+    }
+}
+
+#[test]
+fn a_changed_scene_is_reported_with_a_diff() {
+    // the same scene with a 10x10 square drawn where it was empty
+    let script = temp("changed.luau");
+    let source = std::fs::read_to_string(fixture("golden.luau")).unwrap();
+    std::fs::write(&script, source.replace("end)", "    Bee2D.drawRectangle(20, 20, 10, 10, {0, 255, 0, 255})\nend)")).unwrap();
+
+    let diff = temp("changed.diff.png");
+    let outcome = golden_test(&script, &["--diff", diff.to_str().unwrap()]).run().unwrap();
+    std::fs::remove_file(&script).unwrap();
+
+    let Outcome::Failed(message) = outcome else {
+        panic!("the changed scene was not reported");
+    };
+    assert!(message.starts_with("100 pixel(s) differ"), "{}", message);
+
+    let diff_image = Framebuffer::load_png(&diff).unwrap();
+    std::fs::remove_file(&diff).unwrap();
+    let pixel = |x: u32, y: u32| {
+        let index = ((y * diff_image.width() + x) * 4) as usize;
+        diff_image.pixels()[index..index + 4].to_vec()
+    };
+    assert_eq!(pixel(25, 25), [255, 0, 0, 255]);
+    assert_ne!(pixel(50, 50), [255, 0, 0, 255]);
+}
+
+#[test]
+fn tolerance_lets_small_differences_through() {
+    // a slightly different red from the reference
+    let script = temp("tinted.luau");
+    let source = std::fs::read_to_string(fixture("golden.luau")).unwrap();
+    std::fs::write(&script, source.replace("{220, 60, 60, 255}", "{222, 60, 60, 255}")).unwrap();
+
+    let diff = temp("tinted.diff.png");
+    let strict = golden_test(&script, &["--diff", diff.to_str().unwrap()]).run().unwrap();
+    let tolerant = golden_test(&script, &["--tolerance", "2"]).run().unwrap();
+    std::fs::remove_file(&script).unwrap();
+    std::fs::remove_file(&diff).unwrap();
+
+    assert!(matches!(strict, Outcome::Failed(_)), "the tinted scene passed without a tolerance");
+    assert!(matches!(tolerant, Outcome::Passed), "the tinted scene failed within the tolerance");
+}
+
+#[test]
+fn a_missing_reference_fails_until_updated() {
+    let reference = temp("missing.png");
+    let args = |update: bool| {
+        let mut args = vec![fixture("golden.luau").display().to_string(), "--reference".into(), reference.display().to_string()];
+        args.extend(["--frames".into(), "1".into()]);
+        if update {
+            args.push("--update".into());
+        }
+        GoldenTest::from_args(args.into_iter()).unwrap()
+    };
+
+    assert!(matches!(args(false).run().unwrap(), Outcome::Failed(message) if message.contains("does not exist")));
+    assert!(matches!(args(true).run().unwrap(), Outcome::Updated));
+    assert!(matches!(args(false).run().unwrap(), Outcome::Passed));
+    std::fs::remove_file(&reference).unwrap();
+}
+
+#[test]
+fn bad_arguments_are_refused() {
+    let parse = |args: &[&str]| GoldenTest::from_args(args.iter().map(|arg| arg.to_string()));
+    assert!(parse(&["scene.luau"]).is_err());
+    assert!(parse(&["--reference", "scene.png"]).is_err());
+    assert!(parse(&["scene.luau", "--reference", "scene.png", "--frames", "many"]).is_err());
+    assert!(parse(&["scene.luau", "--reference", "scene.png", "--colour"]).is_err());
+    assert!(parse(&["scene.luau", "--reference", "scene.png", "--tolerance", "300"]).is_err());
+}
@@ -0,0 +1,999 @@
+use bee2d_rust::{Bee2D, Bee2DConfig, Framebuffer};
+
+const FRAME: f64 = 1.0 / 60.0;
+
+fn headless() -> Bee2D {
+    Bee2D::new(Bee2DConfig {
+        width: 64,
+        height: 64,
+        headless: true,
+        ..Bee2DConfig::default()
+    })
+    .expect("engine should start headless")
+}
+
+fn pixel(framebuffer: &Framebuffer, x: u32, y: u32) -> [u8; 4] {
+    let index = ((y * framebuffer.width() + x) * 4) as usize;
+    framebuffer.pixels()[index..index + 4].try_into().unwrap()
+}
+
+#[test]
+fn step_resumes_waits_and_draws() {
+    let mut bee2d = headless();
+    bee2d.load_script("step", r#"
+        local shown = false
+        task.delay(0.1, function()
+            shown = true
+        end)
+        Bee2D.bindToDraw(function()
+            if shown then
+                Bee2D.drawRectangle(16, 16, 32, 32, {255, 0, 0, 255})
+            end
+        end)
+    "#).unwrap();
+
+    bee2d.step(FRAME).unwrap();
+    let before = pixel(bee2d.framebuffer().unwrap(), 32, 32);
+    assert_ne!(before, [255, 0, 0, 255]);
+
+    for _ in 0..10 {
+        bee2d.step(FRAME).unwrap();
+    }
+    let framebuffer = bee2d.framebuffer().unwrap();
+    assert_eq!(pixel(framebuffer, 32, 32), [255, 0, 0, 255]);
+    assert_eq!(pixel(framebuffer, 4, 4), before);
+}
+
+#[test]
+fn step_returns_callback_errors() {
+    let mut bee2d = headless();
+    bee2d.load_script("error", r#"
+        Bee2D.bindToUpdate(function()
+            error("broken update")
+        end)
+    "#).unwrap();
+
+    let error = bee2d.step(FRAME).unwrap_err();
+    assert!(error.to_string().contains("broken update"), "{}", error);
+}
+
+#[test]
+fn camera_component_moves_the_view() {
+    let mut bee2d = headless();
+    bee2d.load_script("camera", r#"
+        local eye = GameObject.new("Eye", Bee2D.Scene)
+        eye.Transform.LocalPosition = Vector2.new(100, 100)
+        eye:AddComponent("Camera", {Zoom = 2})
+        Bee2D.bindToDraw(function()
+            Bee2D.drawRectangle(96, 96, 8, 8, {0, 255, 0, 255})
+        end)
+    "#).unwrap();
+
+    bee2d.step(FRAME).unwrap();
+    let framebuffer = bee2d.framebuffer().unwrap();
+    // the rectangle is centered on the camera and twice as large
+    assert_eq!(pixel(framebuffer, 32, 32), [0, 255, 0, 255]);
+    assert_eq!(pixel(framebuffer, 24, 24), [0, 255, 0, 255]);
+    assert_ne!(pixel(framebuffer, 20, 20), [0, 255, 0, 255]);
+}
+
+#[test]
+fn cancelled_signal_waiters_stay_cancelled() {
+    let mut bee2d = headless();
+    bee2d.load_script("cancel", r#"
+        local signal = Signal.new()
+        local resumed = 0
+        local waiter = task.spawn(function()
+            signal:Wait()
+            resumed += 1
+        end)
+        task.spawn(function()
+            signal:Wait()
+            resumed += 10
+        end)
+
+        task.cancel(waiter)
+        signal:Fire()
+        signal:Fire()
+        assert(resumed == 10, `resumed {resumed}`)
+    "#).unwrap();
+
+    bee2d.step(FRAME).unwrap();
+}
+
+#[test]
+fn loading_a_texture_finishes_its_async_load() {
+    let path = std::env::temp_dir().join(format!("bee2d-async-{}.png", std::process::id()));
+    Framebuffer::new(2, 2).unwrap().save_png(&path).unwrap();
+
+    let mut bee2d = headless();
+    bee2d.load_script("assets", &format!(r#"
+        local path = "{}"
+        local texture = Bee2D.Assets.LoadTextureAsync(path)
+        texture.Loaded:Connect(function(ok)
+            _G.loaded = ok
+        end)
+        Bee2D.Assets.LoadTexture(path)
+    "#, path.display())).unwrap();
+
+    bee2d.step(FRAME).unwrap();
+    std::fs::remove_file(path).unwrap();
+    bee2d.load_script("check", "assert(_G.loaded == true, 'Loaded did not fire')").unwrap();
+}
+
+#[cfg(not(feature = "raylib"))]
+#[test]
+fn other_image_formats_need_raylib() {
+    let bee2d = headless();
+    let error = bee2d.load_script("jpg", r#"Bee2D.Assets.LoadTexture("photo.jpg")"#).unwrap_err();
+    assert!(error.to_string().contains("only PNG images"), "{}", error);
+}
+
+#[test]
+fn replacing_the_playing_clip_restarts_it() {
+    let path = std::env::temp_dir().join(format!("bee2d-clip-{}.png", std::process::id()));
+    Framebuffer::new(8, 2).unwrap().save_png(&path).unwrap();
+
+    let mut bee2d = headless();
+    bee2d.load_script("animator", &format!(r#"
+        local texture = Bee2D.Assets.LoadTexture("{}")
+        local frames = SpriteSheet.fromGrid(texture, 2, 2):GetRegions()
+        local object = GameObject.new("Runner", Bee2D.Scene)
+        object:AddComponent("SpriteRenderer")
+        _G.animator = object:AddComponent("Animator")
+        _G.animator:AddClip("run", {{ Frames = frames, FrameDuration = 0.05 }})
+        _G.animator:Play("run")
+        _G.frames = frames
+    "#, path.display())).unwrap();
+
+    for _ in 0..10 {
+        bee2d.step(FRAME).unwrap();
+    }
+    std::fs::remove_file(path).unwrap();
+
+    bee2d.load_script("replace", r#"
+        assert(_G.animator.Frame > 1, "the clip should have moved on")
+        _G.animator:AddClip("run", { Frames = { _G.frames[1] } })
+        assert(_G.animator.Frame == 1, "the replaced clip should start over")
+    "#).unwrap();
+    for _ in 0..10 {
+        bee2d.step(FRAME).unwrap();
+    }
+}
+
+#[test]
+fn tweens_write_nothing_until_played() {
+    let mut bee2d = headless();
+    bee2d.load_script("tween", r#"
+        local writes = 0
+        local target = setmetatable({}, {
+            __index = function() return 0 end,
+            __newindex = function(_, _, value) writes += 1 end,
+        })
+        local tween = TweenService.create(target, 1, { Value = 10 })
+        assert(writes == 0, `creating the tween wrote {writes} times`)
+        _G.tween, _G.writes = tween, function() return writes end
+    "#).unwrap();
+
+    bee2d.step(FRAME).unwrap();
+    bee2d.load_script("play", r#"
+        assert(_G.writes() == 0, "the tween wrote before playing")
+        _G.tween:Play()
+    "#).unwrap();
+    bee2d.step(FRAME).unwrap();
+    bee2d.load_script("check", r#"assert(_G.writes() > 0, "the tween never wrote")"#).unwrap();
+}
+
+#[test]
+fn tweening_a_read_only_property_fails_once() {
+    let mut bee2d = headless();
+    bee2d.load_script("tween", r#"
+        TweenService.create(Vector2.new(1, 2), 1, { X = 5 }):Play()
+    "#).unwrap();
+
+    let error = bee2d.step(FRAME).unwrap_err();
+    assert!(error.to_string().contains("Can't tween X"), "{}", error);
+    bee2d.step(FRAME).unwrap();
+}
+
+#[test]
+fn capsule_comes_to_rest_on_a_floor() {
+    let mut bee2d = headless();
+    bee2d.load_script("physics", r#"
+        local floor = GameObject.new("Floor", Bee2D.Scene)
+        floor.Transform.LocalPosition = Vector2.new(200, 300)
+        floor:AddComponent("Collider", { Size = Vector2.new(400, 40) })
+
+        local capsule = GameObject.new("Capsule", Bee2D.Scene)
+        capsule.Transform.LocalPosition = Vector2.new(200, 0)
+        capsule:AddComponent("RigidBody", { FixedRotation = true })
+        capsule:AddComponent("Collider", { Shape = "Capsule", Size = Vector2.new(10, 40) })
+        _G.capsule = capsule
+    "#).unwrap();
+
+    for _ in 0..120 {
+        bee2d.step(FRAME).unwrap();
+    }
+    bee2d.load_script("check", r#"
+        local position = _G.capsule.Transform.LocalPosition
+        assert(math.abs(position.X - 200) < 0.5, `the capsule slid to {position.X}`)
+        assert(math.abs(position.Y - 260) < 1, `the capsule is at {position.Y} instead of resting on the floor`)
+    "#).unwrap();
+}
+
+#[test]
+fn boxes_stack_without_sinking() {
+    let mut bee2d = headless();
+    bee2d.load_script("physics", r#"
+        local floor = GameObject.new("Floor", Bee2D.Scene)
+        floor.Transform.LocalPosition = Vector2.new(0, 300)
+        floor:AddComponent("Collider", { Size = Vector2.new(400, 40) })
+
+        _G.boxes = {}
+        for i = 1, 3 do
+            local box = GameObject.new(`Box{i}`, Bee2D.Scene)
+            box.Transform.LocalPosition = Vector2.new(0, 280 - i * 40)
+            box:AddComponent("RigidBody")
+            box:AddComponent("Collider", { Size = Vector2.new(40, 40) })
+            table.insert(_G.boxes, box)
+        end
+    "#).unwrap();
+
+    for _ in 0..180 {
+        bee2d.step(FRAME).unwrap();
+    }
+    bee2d.load_script("check", r#"
+        for i, box in _G.boxes do
+            local position = box.Transform.LocalPosition
+            local resting = 300 - 20 - (i - 0.5) * 40
+            assert(math.abs(position.X) < 1, `box {i} slid to {position.X}`)
+            assert(math.abs(position.Y - resting) < 2, `box {i} is at {position.Y} instead of {resting}`)
+        end
+    "#).unwrap();
+}
+
+#[test]
+fn colliders_on_other_layers_pass_through() {
+    let mut bee2d = headless();
+    bee2d.load_script("physics", r#"
+        local floor = GameObject.new("Floor", Bee2D.Scene)
+        floor.Transform.LocalPosition = Vector2.new(0, 100)
+        floor:AddComponent("Collider", { Size = Vector2.new(400, 40), Layer = 1 })
+
+        local function drop(name, mask)
+            local ball = GameObject.new(name, Bee2D.Scene)
+            ball:AddComponent("RigidBody")
+            ball:AddComponent("Collider", { Shape = "Circle", Size = Vector2.new(20, 20), Mask = mask })
+            return ball
+        end
+        _G.blocked = drop("Blocked", 2)
+        _G.ignored = drop("Ignored", 1)
+    "#).unwrap();
+
+    for _ in 0..90 {
+        bee2d.step(FRAME).unwrap();
+    }
+    bee2d.load_script("check", r#"
+        local blocked, ignored = _G.blocked.Transform.LocalPosition.Y, _G.ignored.Transform.LocalPosition.Y
+        assert(math.abs(blocked - 70) < 1, `the ball masking the floor's layer is at {blocked}`)
+        assert(ignored > 150, `the ball not masking the floor's layer stopped at {ignored}`)
+    "#).unwrap();
+}
+
+#[test]
+fn animators_reject_endless_timing_and_survive_long_steps() {
+    let path = std::env::temp_dir().join(format!("bee2d-speed-{}.png", std::process::id()));
+    Framebuffer::new(8, 2).unwrap().save_png(&path).unwrap();
+
+    let mut bee2d = headless();
+    bee2d.load_script("animator", &format!(r#"
+        local texture = Bee2D.Assets.LoadTexture("{}")
+        local frames = SpriteSheet.fromGrid(texture, 2, 2):GetRegions()
+        local object = GameObject.new("Runner", Bee2D.Scene)
+        object:AddComponent("SpriteRenderer")
+        local animator = object:AddComponent("Animator")
+
+        assert(not pcall(function() animator.Speed = math.huge end), "an infinite Speed was accepted")
+        assert(not pcall(function() animator.Speed = 0 / 0 end), "a NaN Speed was accepted")
+        assert(not pcall(animator.AddClip, animator, "nan", {{ Frames = frames, FrameDuration = 0 / 0 }}), "a NaN FrameDuration was accepted")
+        assert(not pcall(animator.AddClip, animator, "inf", {{ Frames = frames, Durations = {{ math.huge }} }}), "an infinite duration was accepted")
+
+        animator:AddClip("run", {{ Frames = frames, FrameDuration = 0.001, Mode = "PingPong" }})
+        animator:Play("run")
+        animator.Speed = 1e300
+    "#, path.display())).unwrap();
+
+    // a step covering countless cycles still returns
+    bee2d.step(FRAME).unwrap();
+    bee2d.step(1e6).unwrap();
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn a_failing_connection_still_wakes_waiters() {
+    let mut bee2d = headless();
+    bee2d.load_script("signal", r#"
+        local signal = Signal.new()
+        local ran = {}
+        signal:Connect(function() error("broken connection") end)
+        signal:Connect(function() table.insert(ran, "connection") end)
+        task.spawn(function()
+            signal:Wait()
+            table.insert(ran, "waiter")
+        end)
+
+        local ok, err = pcall(signal.Fire, signal)
+        assert(not ok and string.find(tostring(err), "broken connection"), "the error was lost")
+        assert(#ran == 2, `only {table.concat(ran, ", ")} ran`)
+    "#).unwrap();
+
+    bee2d.step(FRAME).unwrap();
+}
+
+#[test]
+fn grid_options_are_validated() {
+    let path = std::env::temp_dir().join(format!("bee2d-grid-{}.png", std::process::id()));
+    Framebuffer::new(8, 2).unwrap().save_png(&path).unwrap();
+
+    let bee2d = headless();
+    bee2d.load_script("grid", &format!(r#"
+        local texture = Bee2D.Assets.LoadTexture("{}")
+        assert(#SpriteSheet.fromGrid(texture, 2, 2, {{ Margin = 0, Spacing = 0 }}):GetRegions() == 4)
+
+        for _, options in {{ {{ Margin = -1 }}, {{ Spacing = -1 }}, {{ Margin = 0 / 0 }}, {{ Spacing = math.huge }} }} do
+            assert(not pcall(SpriteSheet.fromGrid, texture, 2, 2, options), "bad grid options were accepted")
+        end
+        assert(not pcall(SpriteSheet.fromGrid, texture, 0 / 0, 2), "a NaN frame width was accepted")
+        -- so many frames fit that counting them overflows
+        assert(not pcall(SpriteSheet.fromGrid, texture, 1e-30, 1e-30), "the overflowing grid was accepted")
+    "#, path.display())).unwrap();
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn window_settings_are_validated() {
+    let mut bee2d = headless();
+    bee2d.load_script("window", r#"
+        for _, scale in { math.huge, -1, 0 / 0 } do
+            assert(not pcall(function() Bee2D.timeScale = scale end), `timeScale {scale} was accepted`)
+        end
+        for _, size in { 0, -10, 1.5, 1e12, math.huge, 0 / 0 } do
+            assert(not pcall(Bee2D.setWidth, size), `width {size} was accepted`)
+            assert(not pcall(Bee2D.setHeight, size), `height {size} was accepted`)
+        end
+        assert(Bee2D.width == 64 and Bee2D.height == 64, "a rejected size was kept")
+
+        Bee2D.setWidth(32)
+        Bee2D.setHeight(16)
+    "#).unwrap();
+
+    bee2d.step(FRAME).unwrap();
+    let framebuffer = bee2d.framebuffer().unwrap();
+    assert_eq!((framebuffer.width(), framebuffer.height()), (32, 16));
+}
+
+#[test]
+fn touches_survive_reordering_and_fire_in_order() {
+    let mut bee2d = headless();
+    bee2d.load_script("touch", r#"
+        _G.events = {}
+        local function collider(name)
+            local object = GameObject.new(name, Bee2D.Scene)
+            local collider = object:AddComponent("Collider", { Size = Vector2.new(10, 10), IsTrigger = true })
+            collider.Touched:Connect(function(other)
+                table.insert(_G.events, `{name} touched {other.GameObject.Name}`)
+            end)
+            collider.TouchEnded:Connect(function(other)
+                table.insert(_G.events, `{name} stopped touching {other.GameObject.Name}`)
+            end)
+            return object
+        end
+
+        _G.sensor = collider("Sensor")
+        _G.sensor:AddComponent("RigidBody", { BodyType = "Kinematic" })
+        _G.others = { collider("A"), collider("B"), collider("C") }
+    "#).unwrap();
+
+    bee2d.step(FRAME).unwrap();
+    bee2d.load_script("reorder", r#"
+        local order = table.concat(_G.events, ", ")
+        assert(order == "Sensor touched A, A touched Sensor, Sensor touched B, B touched Sensor, Sensor touched C, C touched Sensor", order)
+        table.clear(_G.events)
+
+        -- the sensor is now found after the others
+        _G.sensor.Parent = _G.others[3]
+    "#).unwrap();
+
+    bee2d.step(FRAME).unwrap();
+    bee2d.load_script("check", r#"
+        assert(#_G.events == 0, table.concat(_G.events, ", "))
+    "#).unwrap();
+}
+
+#[test]
+fn tweens_of_destroyed_objects_are_cancelled() {
+    let mut bee2d = headless();
+    bee2d.load_script("tween", r#"
+        local doomed = GameObject.new("Doomed", Bee2D.Scene)
+        local survivor = GameObject.new("Survivor", Bee2D.Scene)
+        _G.survivor = survivor
+        _G.states = {}
+
+        local goal = { LocalPosition = Vector2.new(100, 0) }
+        local info = { Time = 1, DelayTime = 0.05 }
+        local doomed_tween = TweenService.create(doomed.Transform, info, goal)
+        doomed_tween.Completed:Connect(function(state) table.insert(_G.states, state) end)
+        doomed_tween:Play()
+        TweenService.create(survivor.Transform, 1, goal):Play()
+
+        -- gone before the delay ends and the tween reads where it starts from
+        doomed:Destroy()
+    "#).unwrap();
+
+    let error = bee2d.step(FRAME).and_then(|_| bee2d.step(FRAME)).and_then(|_| bee2d.step(FRAME)).unwrap_err();
+    assert!(error.to_string().contains("Can't tween LocalPosition"), "{}", error);
+    for _ in 0..10 {
+        bee2d.step(FRAME).unwrap();
+    }
+
+    bee2d.load_script("check", r#"
+        assert(table.concat(_G.states, ", ") == "Cancelled", table.concat(_G.states, ", "))
+        assert(_G.survivor.Transform.LocalPosition.X > 10, "the other tween stopped as well")
+    "#).unwrap();
+}
+
+#[test]
+fn physics_queries_find_and_filter_colliders() {
+    let mut bee2d = headless();
+    bee2d.load_script("queries", r#"
+        local function collider(name, x, y, props)
+            local object = GameObject.new(name, Bee2D.Scene)
+            object.Transform.LocalPosition = Vector2.new(x, y)
+            object:AddComponent("Collider", props)
+            return object
+        end
+        local wall = collider("Wall", 300, 0, { Size = Vector2.new(20, 100) })
+        collider("Sensor", 150, 0, { Size = Vector2.new(20, 20), IsTrigger = true })
+        collider("Back", 400, 0, { Size = Vector2.new(20, 20), Layer = 2 })
+        -- far too big for the cells, so every query has to look at it
+        collider("Floor", 0, 500, { Size = Vector2.new(5000, 20) })
+
+        local function names(colliders)
+            local names = {}
+            for _, collider in colliders do
+                table.insert(names, collider.GameObject.Name)
+            end
+            table.sort(names)
+            return table.concat(names, ", ")
+        end
+        local function near(a, b)
+            return (a - b).Magnitude < 1e-3
+        end
+
+        -- crosses a few cells before it reaches the wall
+        local hit = Physics.Raycast(Vector2.new(0, 0), Vector2.new(1000, 0))
+        assert(hit and hit.GameObject == wall, "the ray missed the wall")
+        assert(hit.Collider.GameObject == wall)
+        assert(near(hit.Position, Vector2.new(290, 0)), `hit at {hit.Position}`)
+        assert(near(hit.Normal, Vector2.new(-1, 0)), `normal {hit.Normal}`)
+        assert(math.abs(hit.Distance - 290) < 1e-3, `distance {hit.Distance}`)
+
+        assert(Physics.Raycast(Vector2.new(0, 0), Vector2.new(100, 0)) == nil, "the ray went further than its length")
+        assert(Physics.Raycast(Vector2.new(0, 0), Vector2.new(1000, 0), { IncludeTriggers = true }).GameObject.Name == "Sensor")
+        assert(Physics.Raycast(Vector2.new(0, 0), Vector2.new(1000, 0), { Ignore = { wall } }).GameObject.Name == "Back")
+        assert(Physics.Raycast(Vector2.new(0, 0), Vector2.new(1000, 0), { Mask = bit32.lshift(1, 2) }).GameObject.Name == "Back")
+        assert(Physics.Raycast(Vector2.new(0, 0), Vector2.new(1000, 0), { Mask = bit32.lshift(1, 3) }) == nil)
+        -- starting inside the wall passes through it
+        assert(Physics.Raycast(Vector2.new(300, 0), Vector2.new(200, 0)).GameObject.Name == "Back")
+
+        local down = Physics.Raycast(Vector2.new(2000, 0), Vector2.new(0, 1000))
+        assert(down and down.GameObject.Name == "Floor" and near(down.Position, Vector2.new(2000, 490)), "the ray missed the floor")
+        assert(near(down.Normal, Vector2.new(0, -1)), `normal {down.Normal}`)
+
+        assert(names(Physics.OverlapCircle(Vector2.new(300, 60), 15)) == "Wall")
+        assert(names(Physics.OverlapCircle(Vector2.new(300, 70), 15)) == "")
+        assert(names(Physics.OverlapCircle(Vector2.new(300, 0), 150, { IncludeTriggers = true })) == "Back, Sensor, Wall")
+        assert(names(Physics.OverlapCircle(Vector2.new(300, 0), 150, { Mask = 1 })) == "Wall")
+        assert(not pcall(Physics.OverlapCircle, Vector2.new(0, 0), -1), "a negative radius was accepted")
+
+        assert(names(Physics.OverlapBox(Vector2.new(150, 0), Vector2.new(10, 10))) == "")
+        assert(names(Physics.OverlapBox(Vector2.new(150, 0), Vector2.new(10, 10), { IncludeTriggers = true })) == "Sensor")
+        assert(names(Physics.OverlapBox(Vector2.new(350, 0), Vector2.new(120, 10))) == "Back, Wall")
+
+        assert(names(Physics.QueryPoint(Vector2.new(-2000, 505))) == "Floor")
+        assert(names(Physics.QueryPoint(Vector2.new(305, 45))) == "Wall")
+        assert(names(Physics.QueryPoint(Vector2.new(305, 45), { Ignore = { wall } })) == "")
+        assert(names(Physics.QueryPoint(Vector2.new(0, 0))) == "")
+    "#).unwrap();
+
+    bee2d.step(FRAME).unwrap();
+}
+
+#[test]
+fn arcs_stop_after_a_full_turn() {
+    let mut bee2d = headless();
+    bee2d.load_script("arcs", r#"
+        for _, angles in { { 0, math.huge }, { -math.huge, 90 }, { 0 / 0, 90 } } do
+            assert(not pcall(Bee2D.drawArc, 32, 32, 10, angles[1], angles[2], { 255, 255, 255, 255 }), "an endless arc was accepted")
+        end
+        Bee2D.bindToDraw(function()
+            Bee2D.drawArc(32, 32, 10, 0, 1e30, { 255, 0, 0, 255 })
+            Bee2D.drawArcOutline(32, 32, 20, 0, -1e30, 2, { 0, 255, 0, 255 })
+        end)
+    "#).unwrap();
+
+    bee2d.step(FRAME).unwrap();
+    let framebuffer = bee2d.framebuffer().unwrap();
+    assert_eq!(pixel(framebuffer, 32, 25), [255, 0, 0, 255]);
+    assert_eq!(pixel(framebuffer, 32, 52), [0, 255, 0, 255]);
+}
+
+#[test]
+fn oversized_framebuffers_are_refused() {
+    assert!(Framebuffer::new(u32::MAX, u32::MAX).is_err());
+    assert_eq!(Framebuffer::new(3, 2).unwrap().pixels().len(), 24);
+
+    let huge = Bee2D::new(Bee2DConfig { width: i32::MAX, height: i32::MAX, headless: true, ..Bee2DConfig::default() });
+    assert!(huge.is_err(), "the engine started with a framebuffer too large to exist");
+}
+
+#[test]
+fn children_follow_a_rotated_parent() {
+    let mut bee2d = headless();
+    bee2d.load_script("transforms", r#"
+        local parent = GameObject.new("Parent", Bee2D.Scene)
+        parent.Transform.LocalPosition = Vector2.new(100, 0)
+        parent.Transform.LocalRotation = Matrix3.fromRotationXYZ(math.pi / 2)
+        parent.Transform.LocalScale = Vector2.new(2, 2)
+
+        local child = GameObject.new("Child", parent)
+        child.Transform.LocalPosition = Vector2.new(10, 0)
+
+        local function near(a, b)
+            return (a - b).Magnitude < 1e-3
+        end
+        -- moved by its own position, not turned about the parent's origin first
+        local position = parent.Transform.GlobalPosition
+        assert(near(position, Vector2.new(100, 0)), `the parent is at {position}`)
+        -- the offset is scaled and turned a quarter clockwise on screen, then moved with the parent
+        position = child.Transform.GlobalPosition
+        assert(near(position, Vector2.new(100, 20)), `the child is at {position}`)
+        assert(math.abs(child.Transform.GlobalRotation - math.pi / 2) < 1e-5, `the child is turned {child.Transform.GlobalRotation}`)
+    "#).unwrap();
+
+    bee2d.step(FRAME).unwrap();
+}
+
+#[test]
+fn camera_zoom_must_be_finite_and_positive() {
+    let mut bee2d = headless();
+    bee2d.load_script("zoom", r#"
+        local camera = Camera2D.new()
+        local component = GameObject.new("Eye", Bee2D.Scene):AddComponent("Camera")
+        for _, zoom in { 0, -1, math.huge, 0 / 0 } do
+            assert(not pcall(function() camera.Zoom = zoom end), `Camera2D took a Zoom of {zoom}`)
+            assert(not pcall(function() component.Zoom = zoom end), `the Camera component took a Zoom of {zoom}`)
+        end
+        camera.Zoom, component.Zoom = 0.5, 3
+        assert(camera.Zoom == 0.5 and component.Zoom == 3)
+    "#).unwrap();
+
+    bee2d.step(FRAME).unwrap();
+}
+
+#[test]
+fn callbacks_run_in_stage_and_priority_order() {
+    let mut bee2d = headless();
+    bee2d.load_script("order", r#"
+        _G.ran = {}
+        local function record(name)
+            return function() table.insert(_G.ran, name) end
+        end
+        Bee2D.bindToDraw(record("UI"), { Stage = "UI" })
+        Bee2D.bindToDraw(record("Draw"))
+        Bee2D.bindToUpdate(record("Update late"), { Priority = 10 })
+        Bee2D.bindToUpdate(record("Update"))
+        Bee2D.bindToUpdate(record("Update early"), { Priority = -10 })
+        Bee2D.bindToUpdate(record("PostUpdate"), { Stage = "PostUpdate" })
+        Bee2D.bindToUpdate(record("PreUpdate"), { Stage = "PreUpdate" })
+        Bee2D.bindToStart(record("Start"))
+        _G.disconnected = Bee2D.bindToUpdate(record("disconnected"))
+        assert(not pcall(Bee2D.bindToUpdate, record("nowhere"), { Stage = "Draw" }), "a draw stage was taken for an update")
+    "#).unwrap();
+
+    bee2d.load_script("disconnect", r#"
+        assert(_G.disconnected.Connected)
+        _G.disconnected:Disconnect()
+        assert(not _G.disconnected.Connected)
+    "#).unwrap();
+    bee2d.step(FRAME).unwrap();
+    bee2d.load_script("check", r#"
+        local ran = table.concat(_G.ran, ", ")
+        assert(ran == "Start, PreUpdate, Update early, Update, Update late, PostUpdate, Draw, UI", ran)
+        table.clear(_G.ran)
+    "#).unwrap();
+
+    // start only ever runs once
+    bee2d.step(FRAME).unwrap();
+    bee2d.load_script("again", r#"
+        assert(_G.ran[1] == "PreUpdate", table.concat(_G.ran, ", "))
+    "#).unwrap();
+}
+
+#[test]
+fn binding_to_start_late_runs_right_away() {
+    let mut bee2d = headless();
+    bee2d.load_script("early", r#"
+        Bee2D.bindToStart(function()
+            -- bound while start is running, when it can't run again
+            Bee2D.bindToStart(function() _G.nested = true end)
+        end)
+    "#).unwrap();
+    bee2d.step(FRAME).unwrap();
+
+    bee2d.load_script("late", r#"
+        assert(_G.nested, "the start callback bound during start never ran")
+        local ran = false
+        local connection = Bee2D.bindToStart(function() ran = true end)
+        assert(ran, "the late start callback didn't run")
+        assert(not connection.Connected, "the late start callback says it is still connected")
+        connection:Disconnect()
+    "#).unwrap();
+    bee2d.step(FRAME).unwrap();
+}
+
+#[test]
+fn textures_finish_loading_in_the_order_they_were_asked_for() {
+    let paths = (0..8)
+        .map(|i| std::env::temp_dir().join(format!("bee2d-order-{}-{}.png", std::process::id(), i)))
+        .collect::<Vec<_>>();
+    for path in &paths {
+        Framebuffer::new(2, 2).unwrap().save_png(path).unwrap();
+    }
+    let list = paths.iter().map(|path| format!("{:?}", path.display().to_string())).collect::<Vec<_>>().join(", ");
+
+    let mut bee2d = headless();
+    bee2d.load_script("assets", &format!(r#"
+        local paths = {{ {} }}
+        _G.order = {{}}
+        -- some are loaded already, so they only have to be told
+        Bee2D.Assets.LoadTexture(paths[3])
+        Bee2D.Assets.LoadTexture(paths[6])
+        for i = #paths, 1, -1 do
+            Bee2D.Assets.LoadTextureAsync(paths[i]).Loaded:Connect(function(ok)
+                assert(ok, `texture {{i}} failed to load`)
+                table.insert(_G.order, i)
+            end)
+        end
+    "#, list)).unwrap();
+
+    // long enough for every background load to finish before the step looks
+    std::thread::sleep(std::time::Duration::from_millis(300));
+    bee2d.step(FRAME).unwrap();
+    for path in &paths {
+        std::fs::remove_file(path).unwrap();
+    }
+    bee2d.load_script("check", r#"
+        local order = table.concat(_G.order, ", ")
+        assert(order == "8, 7, 6, 5, 4, 3, 2, 1", order)
+    "#).unwrap();
+}
+
+#[test]
+fn simulated_input_is_pressed_held_and_released_across_steps() {
+    let mut bee2d = headless();
+    bee2d.load_script("press", r#"
+        local Input = Bee2D.Input
+        _G.events = {}
+        Input.InputBegan:Connect(function(input)
+            table.insert(_G.events, `began {input.InputType} {input.KeyCode}`)
+        end)
+        Input.InputEnded:Connect(function(input)
+            table.insert(_G.events, `ended {input.InputType} {input.KeyCode}`)
+        end)
+        Input.DefineAction("Jump", { Bindings = { { Key = "Space" } } })
+
+        Input.SimulateKey("Space", true)
+        Input.SimulateMouseButton("Left", true)
+        Input.SimulateMouseMove(Vector2.new(10, 20))
+        Input.SimulateMouseWheel(2)
+        assert(not Input.IsKeyDown("Space"), "simulated input applied before the next frame")
+        assert(not pcall(Input.SimulateKey, "NotAKey", true), "an unknown key was simulated")
+    "#).unwrap();
+
+    bee2d.step(FRAME).unwrap();
+    bee2d.load_script("pressed", r#"
+        local Input = Bee2D.Input
+        assert(Input.IsKeyDown("Space") and Input.IsKeyPressed("Space") and not Input.IsKeyReleased("Space"))
+        assert(Input.IsMouseButtonDown("Left") and Input.IsMouseButtonPressed("Left"))
+        assert(not Input.IsMouseButtonDown("Right"))
+        assert(Input.GetAction("Jump") and Input.GetActionPressed("Jump"))
+        assert(Input.GetMousePosition() == Vector2.new(10, 20), `the mouse is at {Input.GetMousePosition()}`)
+        assert(Input.GetMouseWheel() == 2)
+        local events = table.concat(_G.events, ", ")
+        assert(events == "began Keyboard Space, began MouseButton Left", events)
+        table.clear(_G.events)
+    "#).unwrap();
+
+    bee2d.step(FRAME).unwrap();
+    bee2d.load_script("held", r#"
+        local Input = Bee2D.Input
+        assert(Input.IsKeyDown("Space") and not Input.IsKeyPressed("Space") and not Input.IsKeyReleased("Space"))
+        assert(Input.IsMouseButtonDown("Left") and not Input.IsMouseButtonPressed("Left"))
+        assert(Input.GetAction("Jump") and not Input.GetActionPressed("Jump"))
+        -- the wheel only moves for the frame it was turned in, the mouse stays where it went
+        assert(Input.GetMouseWheel() == 0)
+        assert(Input.GetMousePosition() == Vector2.new(10, 20))
+        assert(#_G.events == 0, table.concat(_G.events, ", "))
+
+        Input.SimulateKey("Space", false)
+        Input.SimulateMouseButton("Left", false)
+    "#).unwrap();
+
+    bee2d.step(FRAME).unwrap();
+    bee2d.load_script("released", r#"
+        local Input = Bee2D.Input
+        assert(not Input.IsKeyDown("Space") and Input.IsKeyReleased("Space"))
+        assert(not Input.IsMouseButtonDown("Left") and Input.IsMouseButtonReleased("Left"))
+        assert(Input.GetActionReleased("Jump"))
+        local events = table.concat(_G.events, ", ")
+        assert(events == "ended Keyboard Space, ended MouseButton Left", events)
+    "#).unwrap();
+
+    bee2d.step(FRAME).unwrap();
+    bee2d.load_script("idle", r#"
+        local Input = Bee2D.Input
+        assert(not Input.IsKeyReleased("Space") and not Input.IsMouseButtonReleased("Left"))
+    "#).unwrap();
+}
+
+#[test]
+fn colors_convert_and_draw() {
+    let mut bee2d = headless();
+    bee2d.load_script("colors", r##"
+        local orange = Color.fromHex("#ff8000")
+        assert(orange == Color.fromRGB(255, 128, 0), "fromHex and fromRGB disagree")
+        assert(orange.Hex == "#ff8000", orange.Hex)
+        local r, g, b, a = orange:ToRGB()
+        assert(r == 255 and g == 128 and b == 0 and a == 255, "ToRGB lost a channel")
+
+        assert(Color.fromHSV(0, 1, 1) == Color.red, "fromHSV(0, 1, 1) is not red")
+        assert(math.abs(Color.green.Hue - 1 / 3) < 1e-4, "green has the wrong hue")
+        assert(Color.black:Lerp(Color.white, 0.5) == Color.gray, "Lerp missed the middle")
+        assert(Color.red + Color.blue == Color.magenta, "adding colors")
+        assert(Color.white - Color.white == Color.transparent, "subtracting colors")
+        assert((Color.white * 0.5).A == 1, "scaling changed alpha")
+
+        assert(not pcall(Color.fromHex, "#ff800"), "a 5-digit hex color was accepted")
+        assert(not pcall(Color.fromHex, "#zzzzzz"), "a hex color with bad digits was accepted")
+        assert(not pcall(function() return Color.white / 0 end), "dividing a color by zero")
+
+        Bee2D.bindToDraw(function()
+            Bee2D.drawRectangle(8, 8, 16, 16, orange)
+            Bee2D.drawRectangle(40, 40, 16, 16, { 0, 0, 255, 255 })
+        end)
+    "##).unwrap();
+
+    bee2d.step(FRAME).unwrap();
+    let framebuffer = bee2d.framebuffer().unwrap();
+    assert_eq!(pixel(framebuffer, 16, 16), [255, 128, 0, 255]);
+    assert_eq!(pixel(framebuffer, 48, 48), [0, 0, 255, 255]);
+}
+
+#[test]
+fn components_are_added_found_and_removed() {
+    let bee2d = headless();
+    bee2d.load_script("components", r#"
+        local object = GameObject.new("Object", Bee2D.Scene)
+        local sprite = object:AddComponent("SpriteRenderer")
+        local camera = object:AddComponent("Camera", { Zoom = 2 })
+
+        assert(object:GetComponent("SpriteRenderer") == sprite, "GetComponent found another sprite")
+        assert(object:GetComponent("Camera").Zoom == 2, "props were not applied")
+        assert(camera.GameObject == object, "the component lost its object")
+        assert(object:GetComponent("Collider") == nil, "found a component that was never added")
+        assert(#object:GetComponents() == 2, "wrong component count")
+
+        object:RemoveComponent(sprite)
+        object:RemoveComponent("Camera")
+        assert(object:GetComponent("SpriteRenderer") == nil and object:GetComponent("Camera") == nil, "removed components remain")
+        assert(#object:GetComponents() == 0, "components left behind")
+
+        assert(not pcall(object.AddComponent, object, "Nonexistent"), "an unknown component type was added")
+        assert(not pcall(object.AddComponent, object, "Camera", { Zoom = -1 }), "an invalid prop was accepted")
+        assert(object:GetComponent("Camera") == nil, "a half-configured component was kept")
+        assert(not pcall(Bee2D.registerComponent, "Camera", {}), "a built-in component was redefined")
+
+        -- AudioSource only records what was asked of it
+        local audio = object:AddComponent("AudioSource", { Volume = 0.5 })
+        audio:Play()
+        assert(not audio.Playing, "played without a clip")
+        audio.Clip = "beep.wav"
+        audio:Play()
+        assert(audio.Playing and audio.Volume == 0.5, "the audio settings were lost")
+
+        object:Destroy()
+        assert(not pcall(object.GetComponent, object, "AudioSource"), "a destroyed object still has components")
+    "#).unwrap();
+}
+
+#[test]
+fn script_components_follow_the_frame_loop() {
+    let mut bee2d = headless();
+    bee2d.load_script("scripts", r#"
+        events = {}
+        local Spinner = {}
+        function Spinner:Start()
+            table.insert(events, self.GameObject.Name .. " Start")
+        end
+        function Spinner:Update(dt)
+            self.elapsed = (self.elapsed or 0) + dt
+            table.insert(events, self.GameObject.Name .. " Update")
+        end
+        function Spinner:Draw()
+            Bee2D.drawRectangle(self.x, 0, 8, 8, { 255, 0, 0, 255 })
+        end
+        function Spinner:OnDestroy()
+            table.insert(events, self.GameObject.Name .. " OnDestroy")
+        end
+        Bee2D.registerComponent("Spinner", Spinner)
+
+        parent = GameObject.new("Parent", Bee2D.Scene)
+        child = GameObject.new("Child", parent)
+        spinner = parent:AddComponent("Spinner", { x = 0 })
+        child:AddComponent("Spinner", { x = 32 })
+        assert(parent:GetComponent("Spinner") == spinner, "GetComponent found another script")
+        assert(#events == 0, "callbacks ran before the first frame")
+    "#).unwrap();
+
+    bee2d.step(FRAME).unwrap();
+    let framebuffer = bee2d.framebuffer().unwrap();
+    assert_eq!(pixel(framebuffer, 4, 4), [255, 0, 0, 255]);
+    assert_eq!(pixel(framebuffer, 36, 4), [255, 0, 0, 255]);
+
+    bee2d.step(FRAME).unwrap();
+    bee2d.load_script("destroy", r#"
+        local expected = { "Parent Start", "Parent Update", "Child Start", "Child Update", "Parent Update", "Child Update" }
+        assert(table.concat(events, ", ") == table.concat(expected, ", "), table.concat(events, ", "))
+        assert(math.abs(spinner.elapsed - 2 / 60) < 1e-6, "Update got the wrong delta")
+
+        events = {}
+        parent:RemoveComponent(spinner)
+        assert(table.concat(events, ", ") == "Parent OnDestroy", table.concat(events, ", "))
+        child:Destroy()
+        assert(table.concat(events, ", ") == "Parent OnDestroy, Child OnDestroy", table.concat(events, ", "))
+    "#).unwrap();
+
+    bee2d.step(FRAME).unwrap();
+    // nothing is left to draw
+    let framebuffer = bee2d.framebuffer().unwrap();
+    assert_ne!(pixel(framebuffer, 4, 4), [255, 0, 0, 255]);
+    assert_ne!(pixel(framebuffer, 36, 4), [255, 0, 0, 255]);
+}
+
+#[test]
+fn sprite_renderers_draw_through_the_global_matrix() {
+    // red, green on top and blue, white below
+    let path = std::env::temp_dir().join(format!("bee2d-quadrants-{}.png", std::process::id()));
+    let texels = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255], [255, 255, 255, 255]];
+    Framebuffer::from_pixels(2, 2, texels.concat()).save_png(&path).unwrap();
+
+    let mut bee2d = headless();
+    bee2d.load_script("sprites", &format!(r#"
+        parent = GameObject.new("Parent", Bee2D.Scene)
+        parent.Transform.LocalPosition = Vector2.new(32, 32)
+        parent.Transform.LocalScale = Vector2.new(8, 8)
+        local child = GameObject.new("Child", parent)
+        sprite = child:AddComponent("SpriteRenderer", {{ Texture = Bee2D.Assets.LoadTexture("{}") }})
+    "#, path.display())).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let quadrants = |bee2d: &mut Bee2D, change: &str| {
+        bee2d.load_script("change", change).unwrap();
+        bee2d.step(FRAME).unwrap();
+        let framebuffer = bee2d.framebuffer().unwrap();
+        [pixel(framebuffer, 28, 28), pixel(framebuffer, 36, 28), pixel(framebuffer, 28, 36), pixel(framebuffer, 36, 36)]
+    };
+    let [red, green, blue, white] = texels;
+
+    // centered on the child, scaled by its parent
+    assert_eq!(quadrants(&mut bee2d, ""), [red, green, blue, white]);
+    assert_ne!(pixel(bee2d.framebuffer().unwrap(), 20, 20), red);
+    assert_eq!(quadrants(&mut bee2d, "sprite.FlipX = true"), [green, red, white, blue]);
+    assert_eq!(quadrants(&mut bee2d, "sprite.FlipY = true"), [white, blue, green, red]);
+    // turned a quarter clockwise with the parent
+    let turned = quadrants(&mut bee2d, r#"
+        sprite.FlipX, sprite.FlipY = false, false
+        parent.Transform.LocalRotation = Matrix3.fromRotationXYZ(math.pi / 2)
+    "#);
+    assert_eq!(turned, [blue, red, white, green]);
+
+    let tinted = quadrants(&mut bee2d, r#"
+        parent.Transform.LocalRotation = Matrix3.fromRotationXYZ(0)
+        sprite.Color = Color.new(1, 0.5, 0, 1)
+    "#);
+    assert_eq!(tinted[0], red);
+    assert!(tinted[3][0] == 255 && tinted[3][1].abs_diff(128) <= 1 && tinted[3][2] == 0, "{:?}", tinted[3]);
+
+    // the top left of the texture sits on the child
+    let pivoted = quadrants(&mut bee2d, r#"
+        sprite.Color = Color.white
+        sprite.Pivot = Vector2.new(0, 0)
+    "#);
+    assert_ne!(pivoted[0], red);
+    assert_eq!(pivoted[3], red);
+    assert_eq!(pixel(bee2d.framebuffer().unwrap(), 44, 44), white);
+
+    // only the green texel, one texel across
+    let cropped = quadrants(&mut bee2d, r#"
+        sprite.Pivot = Vector2.new(0.5, 0.5)
+        sprite.SourceOffset = Vector2.new(1, 0)
+        sprite.SourceSize = Vector2.new(1, 1)
+    "#);
+    let framebuffer = bee2d.framebuffer().unwrap();
+    assert_eq!(cropped[0], green);
+    assert_eq!(pixel(framebuffer, 35, 35), green);
+    assert_ne!(cropped[3], green);
+    assert_ne!(pixel(framebuffer, 26, 26), green);
+
+    let hidden = quadrants(&mut bee2d, "sprite.Visible = false");
+    assert!(hidden.iter().all(|&color| color != green), "a hidden sprite was drawn");
+}
+
+#[test]
+fn fixed_updates_run_at_their_own_rate() {
+    let mut bee2d = headless();
+    bee2d.load_script("fixed", r#"
+        for _, rate in { 0, -10, math.huge, 0 / 0 } do
+            assert(not pcall(Bee2D.setFixedRate, rate), `a fixed rate of {rate} was accepted`)
+        end
+        Bee2D.setFixedRate(10)
+        assert(math.abs(Bee2D.fixedDeltaTime - 0.1) < 1e-9, "the fixed rate was not applied")
+
+        fixedSteps, events = 0, {}
+        Bee2D.bindToFixedUpdate(function(dt)
+            assert(math.abs(dt - 0.1) < 1e-9, `a fixed update was passed {dt}`)
+            fixedSteps += 1
+            table.insert(events, "fixed")
+        end)
+        Bee2D.bindToUpdate(function()
+            table.insert(events, "update")
+        end)
+        Bee2D.bindToDraw(function(alpha)
+            drawAlpha = alpha
+        end)
+    "#).unwrap();
+
+    let check = |bee2d: &Bee2D, assertions: &str| bee2d.load_script("check", assertions).unwrap();
+
+    for _ in 0..3 {
+        bee2d.step(FRAME).unwrap();
+    }
+    check(&bee2d, r#"
+        assert(fixedSteps == 0, `{fixedSteps} fixed updates ran early`)
+        assert(math.abs(Bee2D.fixedAlpha - 0.5) < 1e-6, `the alpha is {Bee2D.fixedAlpha}`)
+        assert(drawAlpha == Bee2D.fixedAlpha, "draw callbacks were passed another alpha")
+        events = {}
+    "#);
+
+    // the accumulated time adds up to exactly one fixed step, which runs before the update
+    for _ in 0..3 {
+        bee2d.step(FRAME).unwrap();
+    }
+    check(&bee2d, r#"
+        assert(fixedSteps == 1, `{fixedSteps} fixed updates ran`)
+        assert(table.concat(events, ", ") == "update, update, fixed, update", table.concat(events, ", "))
+        assert(Bee2D.fixedAlpha < 1e-6, `the alpha is {Bee2D.fixedAlpha}`)
+    "#);
+
+    // a long frame catches up 5 steps at most and drops the rest
+    bee2d.step(1.05).unwrap();
+    check(&bee2d, r#"
+        assert(fixedSteps == 6, `{fixedSteps} fixed updates ran`)
+        assert(math.abs(Bee2D.fixedAlpha - 0.5) < 1e-6, `the alpha is {Bee2D.fixedAlpha}`)
+        Bee2D.setMaxFixedSteps(2)
+    "#);
+    bee2d.step(1.0).unwrap();
+    check(&bee2d, "assert(fixedSteps == 8, `{fixedSteps} fixed updates ran`)");
+}